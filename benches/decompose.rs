@@ -0,0 +1,37 @@
+use rust_mmr::utils::range::{decompose, Decomposition};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u64 = 10_000_000;
+
+fn main() {
+    for begin in [0_u64, 1, 12_345, 1 << 40] {
+        // Recompute the decomposition for every end, as `append` used to.
+        let started = Instant::now();
+        let mut acc = 0;
+        for end in begin..begin + ITERATIONS {
+            let (left, right) = decompose(black_box(begin), black_box(end));
+            acc ^= left ^ right;
+        }
+        black_box(acc);
+        let recomputed = started.elapsed();
+
+        // Update it incrementally, as batch appends do.
+        let started = Instant::now();
+        let mut acc = 0;
+        let mut decomposition = Decomposition::new(begin, begin);
+        for _ in 0..ITERATIONS {
+            decomposition.increment();
+            acc ^= decomposition.left() ^ decomposition.right();
+        }
+        black_box(acc);
+        let incremental = started.elapsed();
+
+        println!(
+            "begin {:>14}: decompose {:>6.2} ns/op, incremental {:>6.2} ns/op",
+            begin,
+            recomputed.as_nanos() as f64 / ITERATIONS as f64,
+            incremental.as_nanos() as f64 / ITERATIONS as f64,
+        );
+    }
+}
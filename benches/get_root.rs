@@ -0,0 +1,46 @@
+use rust_mmr::{
+    utils::{bag::bag_peaks, hash::get_random_hash, range::decompose},
+    MMR,
+};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn main() {
+    // One peak, two peaks on one side, one peak per side, and three peaks.
+    for (start, end) in [
+        (0_u64, 1 << 20),
+        (0, (1 << 20) + 1),
+        (3, 5),
+        (0, (1 << 20) + 3),
+    ] {
+        let (left, right) = decompose(start, end);
+        let num_peaks = (left.count_ones() + right.count_ones()) as usize;
+        let peaks: Vec<_> = (0..num_peaks).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_params(start, end, peaks.clone()).unwrap();
+
+        // Decompose and bag, as `get_root` did for every size.
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            let (left, right) = decompose(black_box(start), black_box(end));
+            black_box(bag_peaks(left, right, black_box(&peaks)));
+        }
+        let bagged = started.elapsed();
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            black_box(black_box(&mmr).get_root());
+        }
+        let fast = started.elapsed();
+
+        println!(
+            "[{:>7}, {:>7}) {} peaks: bag_peaks {:>7.2} ns/op, get_root {:>7.2} ns/op",
+            start,
+            end,
+            num_peaks,
+            bagged.as_nanos() as f64 / ITERATIONS as f64,
+            fast.as_nanos() as f64 / ITERATIONS as f64,
+        );
+    }
+}
@@ -1,67 +1,527 @@
-use alloy_primitives::keccak256;
-use mmr_sp1_programs::MerklizeProgramParams;
+use alloy_primitives::{keccak256, B256};
+use mmr_sp1_programs::{
+    MerklizeHintedProgramParams, MerklizeKaryProgramParams, MerklizeProgramParams,
+};
 use num_format::{Locale, ToFormattedString};
+use rust_mmr::utils::hint::get_append_hints;
+use serde_json::{json, Value};
 use sp1_build::{build_program_with_args, BuildArgs};
 use sp1_prover::utils::get_cycles;
-use sp1_sdk::SP1Stdin;
+use sp1_sdk::{network::NetworkProver, Prover, ProverClient, SP1Stdin};
 use std::{
     env,
     error::Error,
     fmt,
     fs::{self, File},
     io::{Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
 };
 
+/// A guest program to benchmark: the name its results are written under, the binary to
+/// build and the inputs to run it on.
+struct GuestProgram {
+    name: &'static str,
+    bin: &'static str,
+    inputs: fn() -> Vec<BenchInput>,
+}
+
+/// One run of a guest program, with the number of leaves it processes.
+struct BenchInput {
+    args: Vec<String>,
+    num_leaves: u64,
+    stdin: SP1Stdin,
+}
+
+/// The guest programs to benchmark. Registering a program here is all it takes to have its
+/// cycles measured and written to sp1-programs/bench-results. Its golden table is created by
+/// the first run with `--accept`.
+const PROGRAMS: &[GuestProgram] = &[
+    // Binary merklization.
+    GuestProgram {
+        name: "merklize",
+        bin: "merklize",
+        inputs: merklize_inputs,
+    },
+    // Binary merklization committing the peaks and range, to quantify the commit overhead.
+    GuestProgram {
+        name: "merklize_peaks",
+        bin: "merklize_peaks",
+        inputs: merklize_inputs,
+    },
+    // Binary merklization with host-computed append hints, to quantify the savings over `merklize`.
+    GuestProgram {
+        name: "merklize_hinted",
+        bin: "merklize_hinted",
+        inputs: merklize_hinted_inputs,
+    },
+    // Experimental k-ary merklization, for comparing arity trade-offs.
+    GuestProgram {
+        name: "merklize_kary",
+        bin: "merklize_kary",
+        inputs: merklize_kary_inputs,
+    },
+    // Binary merklization with SHA-256 and BLAKE3, for comparing hashers.
+    GuestProgram {
+        name: "merklize_sha256",
+        bin: "merklize_sha256",
+        inputs: merklize_inputs,
+    },
+    GuestProgram {
+        name: "merklize_blake3",
+        bin: "merklize_blake3",
+        inputs: merklize_inputs,
+    },
+];
+
+/// Programs merklizing the same inputs with different hashers, and the hasher of each. Their
+/// cycles per leaf are compared side by side in sp1-programs/bench-results/hashers.md.
+const HASHER_PROGRAMS: &[(&str, &str)] = &[
+    ("merklize", "Keccak256"),
+    ("merklize_sha256", "SHA-256"),
+    ("merklize_blake3", "BLAKE3"),
+];
+
+/// Regression over the golden cycles per leaf, in percent, that `--diff` tolerates by default
+const DEFAULT_THRESHOLD: f64 = 2.0;
+
+/// Environment variable enabling proving on the SP1 prover network, set to the largest
+/// number of leaves to prove. The network client reads its credentials from
+/// `NETWORK_PRIVATE_KEY`, and every proof is billed to that requester.
+const NETWORK_ENV: &str = "SP1_BENCH_NETWORK_MAX_LEAVES";
+
+/// Proves inputs on the SP1 prover network, to compare cycle counts with real proving
+/// latency.
+///
+/// Only the wall-clock proving time is recorded. Cost is not reported: the client returns
+/// the proof but not what it was billed, so it has to be read from the requester's account.
+struct NetworkBench {
+    client: NetworkProver,
+    max_leaves: u64,
+}
+
+impl NetworkBench {
+    /// Returns the network client if `NETWORK_ENV` is set, or `None` to only count cycles
+    fn from_env() -> Result<Option<Self>, Box<dyn Error>> {
+        let Ok(max_leaves) = env::var(NETWORK_ENV) else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            client: ProverClient::builder().network().build(),
+            max_leaves: max_leaves.parse()?,
+        }))
+    }
+
+    /// Submits every input of up to `max_leaves` leaves at once, one thread per proof, and
+    /// runs `count_cycles` while they are proven. Returns its result with the wall-clock time
+    /// of each proof from submission to fulfilment.
+    fn prove_while<T>(
+        &self,
+        elf: &[u8],
+        inputs: &[BenchInput],
+        count_cycles: impl FnOnce() -> T,
+    ) -> Result<(T, Vec<Option<Duration>>), Box<dyn Error>> {
+        let (pk, _) = self.client.setup(elf);
+        thread::scope(|scope| {
+            let proofs: Vec<_> = inputs
+                .iter()
+                .map(|input| {
+                    (input.num_leaves <= self.max_leaves).then(|| {
+                        let pk = &pk;
+                        scope.spawn(move || {
+                            let start = Instant::now();
+                            self.client
+                                .prove(pk, &input.stdin)
+                                .compressed()
+                                .run()
+                                .map(|_| start.elapsed())
+                                .map_err(|err| format!("network proving failed: {}", err))
+                        })
+                    })
+                })
+                .collect();
+            let cycles = count_cycles();
+            let proving_times = proofs
+                .into_iter()
+                .map(|proof| {
+                    proof
+                        .map(|proof| proof.join().expect("proving thread panicked"))
+                        .transpose()
+                })
+                .collect::<Result<_, _>>()?;
+            Ok((cycles, proving_times))
+        })
+    }
+}
+
+/// What a run does with the measured cycles, from the command line:
+///
+/// * no flags - Writes the markdown tables to sp1-programs/bench-results.
+/// * `--accept` - Also stores the results as the golden tables in
+///   sp1-programs/bench-results/golden, the accepted baseline.
+/// * `--diff [--threshold <percent>]` - Prints the change from the golden tables instead of
+///   writing anything, and fails if any input regressed by more than the threshold.
+struct Mode {
+    accept: bool,
+    diff: bool,
+    threshold: f64,
+}
+
+impl Mode {
+    fn from_args() -> Result<Self, Box<dyn Error>> {
+        let mut mode = Mode {
+            accept: false,
+            diff: false,
+            threshold: DEFAULT_THRESHOLD,
+        };
+        // `cargo bench` passes `--bench` to every target, so unknown flags are ignored.
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--accept" => mode.accept = true,
+                "--diff" => mode.diff = true,
+                "--threshold" => {
+                    mode.threshold = args
+                        .next()
+                        .ok_or("--threshold needs a percentage")?
+                        .parse()?;
+                }
+                _ => {}
+            }
+        }
+        if mode.accept && mode.diff {
+            return Err("--accept and --diff are exclusive".into());
+        }
+        Ok(mode)
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
-    // Build the ELF.
-    let program_path_fragment = "sp1-programs";
-    let program_crate_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(program_path_fragment);
+    let mode = Mode::from_args()?;
+    let program_crate_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(PROGRAM_PATH_FRAGMENT);
+    let mut regressions = 0;
+    let mut hasher_results = Vec::new();
+    let network = NetworkBench::from_env()?;
+
+    for program in PROGRAMS {
+        let elf = build_elf(program.bin);
+        let inputs = (program.inputs)();
+        let count_cycles = || {
+            inputs
+                .iter()
+                .map(|input| get_cycles(&elf, &input.stdin))
+                .collect::<Vec<_>>()
+        };
+        let (cycles, proving_times) = match &network {
+            Some(network) => network.prove_while(&elf, &inputs, count_cycles)?,
+            None => (count_cycles(), vec![None; inputs.len()]),
+        };
+        let bench_results = inputs
+            .into_iter()
+            .zip(cycles.into_iter().zip(proving_times))
+            .enumerate()
+            .map(
+                |(iteration, (input, (cycles, proving_time)))| MerklizeBenchResult {
+                    iteration: iteration as u64,
+                    args: input.args,
+                    total_cycles: cycles,
+                    cycles_per_leaf: cycles / input.num_leaves.max(1),
+                    proving_time,
+                },
+            )
+            .collect::<Vec<_>>();
+        if HASHER_PROGRAMS
+            .iter()
+            .any(|(name, _)| *name == program.name)
+        {
+            let cycles_per_leaf = bench_results
+                .iter()
+                .map(|result| (result.args.join(","), result.cycles_per_leaf))
+                .collect::<Vec<_>>();
+            hasher_results.push((program.name, cycles_per_leaf));
+        }
+        regressions += record_results(
+            &mode,
+            &program_crate_path,
+            program.name,
+            MerklizeBenchResults(bench_results),
+        )?;
+    }
+
+    if !mode.diff {
+        write_hasher_comparison(&program_crate_path, &hasher_results)?;
+    }
+    if regressions > 0 {
+        return Err(format!(
+            "{} inputs regressed by more than {}% over the golden tables",
+            regressions, mode.threshold
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Inputs of 2^0 to 2^15 leaves.
+fn merklize_inputs() -> Vec<BenchInput> {
+    (0..16)
+        .map(|i| {
+            let num_leaves = 2_u64.pow(i);
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&MerklizeProgramParams {
+                leaves: get_leaves(num_leaves),
+            });
+            BenchInput {
+                args: vec![format!("2^{} = {} leaves", i, num_leaves)],
+                num_leaves,
+                stdin,
+            }
+        })
+        .collect()
+}
+
+/// Inputs of 2^0 to 2^15 leaves, with one append hint per leaf.
+fn merklize_hinted_inputs() -> Vec<BenchInput> {
+    (0..16)
+        .map(|i| {
+            let num_leaves = 2_u64.pow(i);
+            let hints = get_append_hints(0, 0, num_leaves)
+                .into_iter()
+                .map(|hint| (hint.right, hint.merges))
+                .collect();
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&MerklizeHintedProgramParams {
+                leaves: get_leaves(num_leaves),
+                hints,
+            });
+            BenchInput {
+                args: vec![format!("2^{} = {} leaves", i, num_leaves)],
+                num_leaves,
+                stdin,
+            }
+        })
+        .collect()
+}
+
+/// Inputs of 2^0 to 2^15 leaves for arities 4 and 8.
+fn merklize_kary_inputs() -> Vec<BenchInput> {
+    [4_u64, 8]
+        .iter()
+        .flat_map(|&arity| (0..16).map(move |i| (arity, i)))
+        .map(|(arity, i)| {
+            let num_leaves = 2_u64.pow(i);
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&MerklizeKaryProgramParams {
+                arity,
+                leaves: get_leaves(num_leaves),
+            });
+            BenchInput {
+                args: vec![
+                    format!("arity {}", arity),
+                    format!("2^{} = {} leaves", i, num_leaves),
+                ],
+                num_leaves,
+                stdin,
+            }
+        })
+        .collect()
+}
+
+const PROGRAM_PATH_FRAGMENT: &str = "sp1-programs";
+
+/// Builds the given guest binary and returns its ELF.
+fn build_elf(program_name: &str) -> Vec<u8> {
+    let program_crate_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(PROGRAM_PATH_FRAGMENT);
     let output_path = program_crate_path.join("elfs");
-    let program_name = "merklize";
     let args = BuildArgs {
         binary: program_name.to_string(),
         locked: true,
         output_directory: output_path.to_str().unwrap().to_string(),
         ..Default::default()
     };
-    build_program_with_args(program_path_fragment, args);
+    build_program_with_args(PROGRAM_PATH_FRAGMENT, args);
+    get_elf_bytes(output_path.join(program_name).as_path())
+}
 
-    // Get the ELF.
-    let elf = get_elf_bytes(output_path.join(program_name).as_path());
+fn get_leaves(num_leaves: u64) -> Vec<B256> {
+    (0..num_leaves)
+        .map(|leaf_idx| keccak256(leaf_idx.to_ne_bytes()))
+        .collect()
+}
 
-    // // Run some iterations with various inputs set.
-    let bench_results = (0..16)
-        .map(|i| {
-            let num_leaves = 2_u64.pow(i as u32);
-            let leaves = (0..num_leaves)
-                .map(|leaf_idx| keccak256(leaf_idx.to_ne_bytes()))
-                .collect();
-            let mut stdin = SP1Stdin::new();
-            stdin.write(&MerklizeProgramParams { leaves });
-            let cycles = get_cycles(&elf, &stdin);
-            MerklizeBenchResult {
-                iteration: i,
-                args: vec![format!("2^{} = {} leaves", i, num_leaves)],
-                total_cycles: cycles,
-                cycles_per_leaf: cycles / num_leaves,
-            }
-        })
-        .collect::<Vec<_>>();
-    let bench_results = MerklizeBenchResults(bench_results);
-    // Print the results as a table.
+/// Prints the results as a table and writes them to sp1-programs/bench-results/{program_name}.md
+fn write_results(
+    program_crate_path: &Path,
+    program_name: &str,
+    bench_results: MerklizeBenchResults,
+) -> Result<(), Box<dyn Error>> {
     println!("{}", bench_results);
-    // Write the results as a md table in sp1-programs/bench-results/{program_name}.md
     let bench_results_path = program_crate_path
         .join("bench-results")
         .join(format!("{}.md", program_name));
     fs::create_dir_all(bench_results_path.parent().unwrap())?;
     let mut file = File::create(bench_results_path)?;
     write!(file, "{}", bench_results)?;
+    Ok(())
+}
 
+/// Prints the cycles per leaf of the `HASHER_PROGRAMS`, one column per hasher, and writes
+/// them to sp1-programs/bench-results/hashers.md
+fn write_hasher_comparison(
+    program_crate_path: &Path,
+    hasher_results: &[(&str, Vec<(String, u64)>)],
+) -> Result<(), Box<dyn Error>> {
+    let Some((_, rows)) = hasher_results.first() else {
+        return Ok(());
+    };
+    let mut table = String::from("## Cycles Per Leaf By Hasher\n| Args |");
+    for (program, _) in hasher_results {
+        let (_, hasher) = HASHER_PROGRAMS
+            .iter()
+            .find(|(name, _)| name == program)
+            .unwrap();
+        table += &format!(" {} |", hasher);
+    }
+    table += &format!("\n|------|{}\n", "---|".repeat(hasher_results.len()));
+    for (args, _) in rows {
+        table += &format!("| {} |", args);
+        for (_, results) in hasher_results {
+            let cycles = results
+                .iter()
+                .find(|(other, _)| other == args)
+                .map_or("-".to_string(), |(_, cycles)| {
+                    cycles.to_formatted_string(&Locale::en)
+                });
+            table += &format!(" {} |", cycles);
+        }
+        table += "\n";
+    }
+
+    println!("{}", table);
+    fs::write(
+        program_crate_path.join("bench-results").join("hashers.md"),
+        table,
+    )?;
     Ok(())
 }
 
+/// Writes, accepts or diffs a program's results as selected by `mode`, returning the number
+/// of inputs that regressed
+fn record_results(
+    mode: &Mode,
+    program_crate_path: &Path,
+    program_name: &str,
+    bench_results: MerklizeBenchResults,
+) -> Result<usize, Box<dyn Error>> {
+    if mode.diff {
+        return diff_results(
+            program_crate_path,
+            program_name,
+            &bench_results,
+            mode.threshold,
+        );
+    }
+    if mode.accept {
+        write_golden(program_crate_path, program_name, &bench_results)?;
+    }
+    write_results(program_crate_path, program_name, bench_results)?;
+    Ok(0)
+}
+
+/// Returns the path of the golden table of a program
+fn golden_path(program_crate_path: &Path, program_name: &str) -> PathBuf {
+    program_crate_path
+        .join("bench-results")
+        .join("golden")
+        .join(format!("{}.json", program_name))
+}
+
+/// Stores the results as the golden table of a program
+fn write_golden(
+    program_crate_path: &Path,
+    program_name: &str,
+    bench_results: &MerklizeBenchResults,
+) -> Result<(), Box<dyn Error>> {
+    let results = bench_results
+        .0
+        .iter()
+        .map(|result| {
+            json!({
+                "args": result.args,
+                "total_cycles": result.total_cycles,
+                "cycles_per_leaf": result.cycles_per_leaf,
+            })
+        })
+        .collect::<Vec<_>>();
+    let golden = json!({ "program": program_name, "results": results });
+    let path = golden_path(program_crate_path, program_name);
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, serde_json::to_string_pretty(&golden)? + "\n")?;
+    Ok(())
+}
+
+/// Prints the change in cycles per leaf from the golden table of a program, returning the
+/// number of inputs that regressed by more than `threshold` percent.
+///
+/// Inputs missing from the golden table are reported but not counted, so new inputs and
+/// programs only need accepting.
+fn diff_results(
+    program_crate_path: &Path,
+    program_name: &str,
+    bench_results: &MerklizeBenchResults,
+    threshold: f64,
+) -> Result<usize, Box<dyn Error>> {
+    let path = golden_path(program_crate_path, program_name);
+    let golden: Value = match fs::read_to_string(&path) {
+        Ok(golden) => serde_json::from_str(&golden)?,
+        Err(_) => {
+            println!(
+                "## {}: no golden table, run with --accept to create it\n",
+                program_name
+            );
+            return Ok(0);
+        }
+    };
+    let golden_cycles = |args: &[String]| {
+        golden["results"]
+            .as_array()?
+            .iter()
+            .find(|result| result["args"] == json!(args))?["cycles_per_leaf"]
+            .as_u64()
+    };
+
+    println!("## {} vs. golden (threshold {}%)", program_name, threshold);
+    println!("| Args | Golden Cycles Per Leaf | Cycles Per Leaf | Delta | |");
+    println!("|------|------------------------|-----------------|-------|-|");
+    let mut regressions = 0;
+    for result in &bench_results.0 {
+        let current = result.cycles_per_leaf.to_formatted_string(&Locale::en);
+        let Some(golden) = golden_cycles(&result.args) else {
+            println!("| {} | - | {} | - | new |", result.args.join(","), current);
+            continue;
+        };
+        let delta = (result.cycles_per_leaf as f64 / golden.max(1) as f64 - 1.0) * 100.0;
+        let status = if delta > threshold {
+            regressions += 1;
+            "REGRESSED"
+        } else if delta < -threshold {
+            "improved"
+        } else {
+            ""
+        };
+        println!(
+            "| {} | {} | {} | {:+.2}% | {} |",
+            result.args.join(","),
+            golden.to_formatted_string(&Locale::en),
+            current,
+            delta,
+            status
+        );
+    }
+    println!();
+    Ok(regressions)
+}
+
 fn get_elf_bytes(path: &Path) -> Vec<u8> {
     let mut buffer = Vec::new();
     File::open(path)
@@ -76,6 +536,8 @@ struct MerklizeBenchResult {
     args: Vec<String>,
     total_cycles: u64,
     cycles_per_leaf: u64,
+    /// Wall-clock time of proving on the prover network, if it was enabled for this input
+    proving_time: Option<Duration>,
 }
 
 struct MerklizeBenchResults(Vec<MerklizeBenchResult>);
@@ -83,10 +545,21 @@ struct MerklizeBenchResults(Vec<MerklizeBenchResult>);
 impl fmt::Display for MerklizeBenchResults {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "## Merklize Bench Results")?;
-        writeln!(f, "| Iteration | Args | Total Cycles | Cycles Per Leaf |")?;
-        writeln!(f, "|-----------|------|--------------|----------------|")?;
+        // The network column only appears when some input was proven, so cycle-only runs
+        // keep writing the same tables.
+        let proven = self.0.iter().any(|result| result.proving_time.is_some());
+        write!(f, "| Iteration | Args | Total Cycles | Cycles Per Leaf |")?;
+        if proven {
+            write!(f, " Network Proving Time |")?;
+        }
+        writeln!(f)?;
+        write!(f, "|-----------|------|--------------|----------------|")?;
+        if proven {
+            write!(f, "----------------------|")?;
+        }
+        writeln!(f)?;
         for result in &self.0 {
-            writeln!(
+            write!(
                 f,
                 "| {} | {} | {} | {} |",
                 result.iteration,
@@ -94,6 +567,13 @@ impl fmt::Display for MerklizeBenchResults {
                 result.total_cycles.to_formatted_string(&Locale::en),
                 result.cycles_per_leaf.to_formatted_string(&Locale::en)
             )?;
+            if proven {
+                match result.proving_time {
+                    Some(time) => write!(f, " {:.1}s |", time.as_secs_f64())?,
+                    None => write!(f, " - |")?,
+                }
+            }
+            writeln!(f)?;
         }
         Ok(())
     }
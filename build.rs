@@ -0,0 +1,7 @@
+fn main() {
+    println!("cargo:rerun-if-changed=proto");
+    // The gRPC service is generated from the shipped protos, which needs `protoc` on the
+    // path or in `PROTOC`.
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/mmr.proto").expect("failed to compile proto/mmr.proto");
+}
@@ -0,0 +1,47 @@
+//! Appends leaves while proving each chunk on a pool of workers.
+//!
+//! The stand-in prover sleeps for a time proportional to the cycles the SP1 bench measured
+//! for the chunk size. A real one would send `VerifyAppendProgramParams` built from the
+//! witness to the `verify_append` guest through the SP1 SDK.
+//!
+//! Run with `cargo run --example pipeline --features prover`.
+
+use alloy_primitives::B256;
+use rust_mmr::pipeline::{append_and_prove, ChunkWitness, PipelineConfig};
+use rust_mmr::prover::estimate_cycles;
+use rust_mmr::utils::hash::hash_leaf_bytes;
+use rust_mmr::MMR;
+use std::time::{Duration, Instant};
+
+/// Simulated proving throughput
+const CYCLES_PER_MILLISECOND: u64 = 100_000;
+
+fn prove(witness: ChunkWitness) -> Result<u64, String> {
+    witness.verify().map_err(|err| err.to_string())?;
+    let cycles = estimate_cycles("merklize", witness.leaves.len() as u64)
+        .ok_or("no cycle table for merklize")?;
+    std::thread::sleep(Duration::from_millis(cycles / CYCLES_PER_MILLISECOND));
+    Ok(cycles)
+}
+
+fn main() {
+    let leaves: Vec<B256> = (0..4096u64)
+        .map(|i| hash_leaf_bytes(&i.to_be_bytes()))
+        .collect();
+    let config = PipelineConfig {
+        chunk_size: 256,
+        workers: 4,
+        queue_depth: 4,
+    };
+
+    let started = Instant::now();
+    let mut mmr = MMR::new();
+    let cycles = append_and_prove(&mut mmr, &leaves, config, prove).expect("proving failed");
+    println!(
+        "proved {} chunks, {} cycles in total, in {:?}",
+        cycles.len(),
+        cycles.iter().sum::<u64>(),
+        started.elapsed()
+    );
+    println!("root after {} leaves: {}", mmr.end(), mmr.get_root());
+}
@@ -0,0 +1,14 @@
+#!/usr/bin/env bash
+# Checks that the library builds with no features and with each feature on its own, so that
+# every optional module declares the features it needs.
+set -euo pipefail
+cd "$(dirname "$0")/.."
+
+features=$(sed -n '/^\[features\]/,/^\[/p' Cargo.toml | grep -oE '^[a-z0-9-]+' | grep -v '^default$')
+
+echo "checking without features"
+cargo check --lib --no-default-features "$@"
+for feature in $features; do
+    echo "checking feature $feature"
+    cargo check --lib --no-default-features --features "$feature" "$@"
+done
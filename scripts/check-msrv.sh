@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+# Checks that the library builds on the minimum supported Rust version declared in
+# Cargo.toml, with no features and with each feature that shares it. Clippy's
+# incompatible_msrv lint catches newer standard library APIs on the current toolchain; this
+# catches newer language features.
+set -euo pipefail
+cd "$(dirname "$0")/.."
+
+msrv=$(sed -n 's/^rust-version = "\(.*\)"/\1/p' Cargo.toml)
+# The alloy provider crates behind `eth` require a newer toolchain.
+features=$(sed -n '/^\[features\]/,/^\[/p' Cargo.toml | grep -oE '^[a-z0-9-]+' | grep -vE '^(default|eth)$')
+
+echo "checking on $msrv without features"
+cargo "+$msrv" check --lib --no-default-features "$@"
+for feature in $features; do
+    echo "checking on $msrv with feature $feature"
+    cargo "+$msrv" check --lib --no-default-features --features "$feature" "$@"
+done
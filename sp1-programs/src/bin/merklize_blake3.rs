@@ -0,0 +1,15 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use alloy_primitives::B256;
+use mmr_sp1_programs::{merklize_with, MerklizeProgramParams};
+
+pub fn main() {
+    let MerklizeProgramParams { leaves } = sp1_zkvm::io::read();
+    let root = merklize_with(&leaves, |left, right| {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(left.as_slice());
+        hasher.update(right.as_slice());
+        B256::from(*hasher.finalize().as_bytes())
+    });
+    sp1_zkvm::io::commit(&root);
+}
@@ -0,0 +1,14 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use mmr_sp1_programs::MerklizeHintedProgramParams;
+use rust_mmr::{utils::hint::AppendHint, MMR};
+
+pub fn main() {
+    let MerklizeHintedProgramParams { leaves, hints } = sp1_zkvm::io::read();
+    let hints: Vec<AppendHint> = hints
+        .into_iter()
+        .map(|(right, merges)| AppendHint { right, merges })
+        .collect();
+    let mmr = MMR::from_leaves_hinted(&leaves, &hints).expect("invalid hints");
+    sp1_zkvm::io::commit(&mmr.get_root());
+}
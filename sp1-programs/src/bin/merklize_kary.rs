@@ -0,0 +1,10 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use mmr_sp1_programs::MerklizeKaryProgramParams;
+use rust_mmr::kary::KaryMMR;
+
+pub fn main() {
+    let MerklizeKaryProgramParams { arity, leaves } = sp1_zkvm::io::read();
+    let mmr = KaryMMR::from_leaves(arity, &leaves).expect("invalid arity");
+    sp1_zkvm::io::commit(&mmr.get_root());
+}
@@ -0,0 +1,13 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use mmr_sp1_programs::MerklizeProgramParams;
+use rust_mmr::MMR;
+
+/// Like `merklize`, but commits the full state instead of the root, so that downstream
+/// programs can continue appending to it. The public values are the state encoded by
+/// `MMR::to_commit_bytes`, which the host decodes with `MMR::from_commit_bytes`.
+pub fn main() {
+    let MerklizeProgramParams { leaves } = sp1_zkvm::io::read();
+    let mmr = MMR::from_leaves(&leaves);
+    sp1_zkvm::io::commit_slice(&mmr.to_commit_bytes());
+}
@@ -0,0 +1,18 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use alloy_primitives::B256;
+use mmr_sp1_programs::{merklize_with, MerklizeProgramParams};
+use sha2::{Digest, Sha256};
+
+pub fn main() {
+    let MerklizeProgramParams { leaves } = sp1_zkvm::io::read();
+    let root = merklize_with(&leaves, |left, right| {
+        B256::from(<[u8; 32]>::from(
+            Sha256::new()
+                .chain_update(left)
+                .chain_update(right)
+                .finalize(),
+        ))
+    });
+    sp1_zkvm::io::commit(&root);
+}
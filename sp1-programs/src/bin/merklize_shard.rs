@@ -0,0 +1,18 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use mmr_sp1_programs::MerklizeShardProgramParams;
+use rust_mmr::{scheme::SchemeDescriptor, writer::IndexRange};
+
+/// Hashes one shard of a distributed build, committing its state encoded by
+/// `MMR::to_commit_bytes`, as `verify_shards` expects it.
+pub fn main() {
+    let MerklizeShardProgramParams { start, leaves } = sp1_zkvm::io::read();
+    let range = IndexRange {
+        start,
+        end: start + leaves.len() as u64,
+    };
+    let shard = range
+        .shard(&leaves, SchemeDescriptor::DEFAULT)
+        .expect("leaves fill the range");
+    sp1_zkvm::io::commit_slice(&shard.to_commit_bytes());
+}
@@ -0,0 +1,29 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use mmr_sp1_programs::VerifyAnchorProgramParams;
+use rust_mmr::{
+    peaks::Peaks,
+    presets::{AnchorProof, StateAnchor},
+};
+
+/// Proves a state root anchored in an MMR, committing the root, the anchored height and
+/// the anchored state root. Invalid anchors make the guest panic, so they have no proof.
+pub fn main() {
+    let VerifyAnchorProgramParams {
+        tag,
+        proof,
+        start,
+        end,
+        peaks,
+        root,
+    } = sp1_zkvm::io::read();
+    let proof = AnchorProof::from_bytes(&proof).expect("invalid anchor proof encoding");
+    let peaks = Peaks::new(start, end, peaks).expect("invalid peaks");
+    assert!(
+        proof.verify_root(&StateAnchor::new(&tag), &peaks, root),
+        "invalid anchor"
+    );
+    sp1_zkvm::io::commit(&root);
+    sp1_zkvm::io::commit(&proof.height);
+    sp1_zkvm::io::commit(&proof.state_root);
+}
@@ -0,0 +1,27 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use mmr_sp1_programs::VerifyAppendProgramParams;
+use rust_mmr::{compact::CompactRange, sequence::verify_append_sequence};
+
+/// Proves an append claim valid, committing the old and new roots and the number of
+/// appended leaves. Invalid claims make the guest panic, so they have no proof.
+pub fn main() {
+    let VerifyAppendProgramParams {
+        old_root,
+        leaves,
+        claimed_new_root,
+        start,
+        end,
+        peaks,
+    } = sp1_zkvm::io::read();
+    let witness = CompactRange {
+        begin: start,
+        end,
+        hashes: peaks,
+    };
+    verify_append_sequence(old_root, &leaves, claimed_new_root, &witness)
+        .expect("invalid append claim");
+    sp1_zkvm::io::commit(&old_root);
+    sp1_zkvm::io::commit(&claimed_new_root);
+    sp1_zkvm::io::commit(&(leaves.len() as u64));
+}
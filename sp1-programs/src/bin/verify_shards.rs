@@ -0,0 +1,40 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+use mmr_sp1_programs::VerifyShardsProgramParams;
+use rust_mmr::{
+    writer::{IndexRange, ShardManifest},
+    MMR,
+};
+use sha2::{Digest, Sha256};
+
+/// Verifies the `merklize_shard` proofs of a distributed build and checks that their shards
+/// are contiguous and merge into the manifest root, committing the root and the shard
+/// verifying key. The shard proofs must be supplied to the prover as proofs to aggregate.
+pub fn main() {
+    let VerifyShardsProgramParams {
+        shard_vkey,
+        shards,
+        root,
+        public_values,
+    } = sp1_zkvm::io::read();
+    let states: Vec<MMR> = public_values
+        .iter()
+        .map(|values| {
+            let digest: [u8; 32] = Sha256::digest(values).into();
+            sp1_zkvm::lib::verify::verify_sp1_proof(&shard_vkey, &digest);
+            MMR::from_commit_bytes(values).expect("malformed shard public values")
+        })
+        .collect();
+    let manifest = ShardManifest {
+        shards: shards
+            .into_iter()
+            .map(|(start, end)| IndexRange { start, end })
+            .collect(),
+        root,
+    };
+    let mmr = manifest
+        .verify(&states)
+        .expect("shards don't match the manifest");
+    sp1_zkvm::io::commit(&mmr.get_root());
+    sp1_zkvm::io::commit(&shard_vkey);
+}
@@ -5,3 +5,80 @@ use serde::{Deserialize, Serialize};
 pub struct MerklizeProgramParams {
     pub leaves: Vec<B256>,
 }
+
+/// Leaves to hash into the shard of a distributed build starting at the leaf `start`.
+#[derive(Deserialize, Serialize)]
+pub struct MerklizeShardProgramParams {
+    pub start: u64,
+    pub leaves: Vec<B256>,
+}
+
+/// A shard manifest to check: the `[start, end)` ranges of the shards and the root they merge
+/// into, plus the public values of one `merklize_shard` proof per shard, proven with the
+/// program whose verifying key digest is `shard_vkey`.
+#[derive(Deserialize, Serialize)]
+pub struct VerifyShardsProgramParams {
+    pub shard_vkey: [u32; 8],
+    pub shards: Vec<(u64, u64)>,
+    pub root: B256,
+    pub public_values: Vec<Vec<u8>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct MerklizeKaryProgramParams {
+    pub arity: u64,
+    pub leaves: Vec<B256>,
+}
+
+/// Leaves plus one `(right, merges)` append hint per leaf, as computed by
+/// `rust_mmr::utils::hint::get_append_hints`.
+#[derive(Deserialize, Serialize)]
+pub struct MerklizeHintedProgramParams {
+    pub leaves: Vec<B256>,
+    pub hints: Vec<(u64, u32)>,
+}
+
+/// An append claim to check: `leaves` appended to the MMR over `[start, end)` with `peaks`,
+/// whose root is `old_root`, yield `claimed_new_root`.
+#[derive(Deserialize, Serialize)]
+pub struct VerifyAppendProgramParams {
+    pub old_root: B256,
+    pub leaves: Vec<B256>,
+    pub claimed_new_root: B256,
+    pub start: u64,
+    pub end: u64,
+    pub peaks: Vec<B256>,
+}
+
+/// An anchor to check: `proof`, an `AnchorProof` encoded with `to_bytes`, anchors its state
+/// root under the application `tag` in the MMR over `[start, end)` with `peaks` and `root`.
+#[derive(Deserialize, Serialize)]
+pub struct VerifyAnchorProgramParams {
+    pub tag: Vec<u8>,
+    pub proof: Vec<u8>,
+    pub start: u64,
+    pub end: u64,
+    pub peaks: Vec<B256>,
+    pub root: B256,
+}
+
+/// Computes the root of the zero-starting MMR over `leaves`, as `MMR::from_leaves` does,
+/// with `hash_pair` in place of Keccak256. The MMR only hashes with Keccak256, so guests
+/// comparing other hash functions merklize with this instead.
+pub fn merklize_with(leaves: &[B256], hash_pair: impl Fn(&B256, &B256) -> B256) -> B256 {
+    let mut peaks: Vec<B256> = Vec::new();
+    for (index, leaf) in leaves.iter().enumerate() {
+        // The leaf at `index` merges with one peak per trailing one bit of its index.
+        let mut node = *leaf;
+        for _ in 0..index.trailing_ones() {
+            node = hash_pair(&peaks.pop().unwrap(), &node);
+        }
+        peaks.push(node);
+    }
+    // Zero-starting peaks all sit on the right side, which bags right to left.
+    peaks
+        .into_iter()
+        .rev()
+        .reduce(|root, peak| hash_pair(&peak, &root))
+        .unwrap_or(B256::ZERO)
+}
@@ -0,0 +1,217 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::proof::{prove, InclusionProof};
+use crate::utils::hash::hash_to_parent;
+use alloy_primitives::B256;
+
+/// A data MMR with an annotation MMR maintained in lockstep, so the annotation at an index
+/// describes the data leaf at the same index, e.g. its availability attestation.
+///
+/// Both MMRs are committed to at once by `hash_to_parent(data_root, annotation_root)`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AnnotatedMMR {
+    data: MMR,
+    annotations: MMR,
+}
+
+impl AnnotatedMMR {
+    /// Creates a new empty pair of MMRs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pairs a data MMR with its annotations, or returns `MMRError::RangeMismatch` if they
+    /// don't cover the same range
+    pub fn from_parts(data: MMR, annotations: MMR) -> Result<Self, MMRError> {
+        if data.start() != annotations.start() || data.end() != annotations.end() {
+            return Err(MMRError::RangeMismatch);
+        }
+        Ok(Self { data, annotations })
+    }
+
+    /// Returns the data MMR
+    pub fn data(&self) -> &MMR {
+        &self.data
+    }
+
+    /// Returns the annotation MMR
+    pub fn annotations(&self) -> &MMR {
+        &self.annotations
+    }
+
+    /// Returns the start index of both MMRs
+    pub fn start(&self) -> u64 {
+        self.data.start()
+    }
+
+    /// Returns the end index of both MMRs
+    pub fn end(&self) -> u64 {
+        self.data.end()
+    }
+
+    /// Appends a data leaf together with its annotation
+    pub fn append(&mut self, leaf: B256, annotation: B256) {
+        self.data.append(leaf);
+        self.annotations.append(annotation);
+    }
+
+    /// Appends data leaves with one annotation each, or returns `MMRError::InvalidLeaves`
+    /// without appending if the counts differ
+    pub fn append_batch(&mut self, leaves: &[B256], annotations: &[B256]) -> Result<(), MMRError> {
+        if leaves.len() != annotations.len() {
+            return Err(MMRError::InvalidLeaves);
+        }
+        self.data.append_batch(leaves);
+        self.annotations.append_batch(annotations);
+        Ok(())
+    }
+
+    /// Returns the combined commitment `hash_to_parent(data_root, annotation_root)`
+    pub fn commitment(&self) -> B256 {
+        hash_to_parent(&self.data.get_root(), &self.annotations.get_root())
+    }
+}
+
+/// Proof that a data leaf and its annotation are included at the same index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JointProof {
+    pub data: InclusionProof,
+    pub annotation: InclusionProof,
+}
+
+impl JointProof {
+    /// Verifies that `leaf` and `annotation` are included in `mmr` at the same index
+    pub fn verify(&self, mmr: &AnnotatedMMR, leaf: B256, annotation: B256) -> bool {
+        self.data.leaf_index() == self.annotation.leaf_index()
+            && self.data.verify(&mmr.data, leaf)
+            && self.annotation.verify(&mmr.annotations, annotation)
+    }
+
+    /// Verifies `leaf` and `annotation` against untrusted peaks of both MMRs, which must
+    /// cover the same range and combine into the trusted `commitment`
+    pub fn verify_commitment<P: AsRef<[B256]>, Q: AsRef<[B256]>>(
+        &self,
+        data_peaks: &Peaks<P>,
+        annotation_peaks: &Peaks<Q>,
+        leaf: B256,
+        annotation: B256,
+        commitment: B256,
+    ) -> bool {
+        let (data_root, annotation_root) = (data_peaks.root(), annotation_peaks.root());
+        self.data.leaf_index() == self.annotation.leaf_index()
+            && data_peaks.start() == annotation_peaks.start()
+            && data_peaks.end() == annotation_peaks.end()
+            && hash_to_parent(&data_root, &annotation_root) == commitment
+            && self.data.verify_peaks(data_peaks, leaf, data_root)
+            && self
+                .annotation
+                .verify_peaks(annotation_peaks, annotation, annotation_root)
+    }
+}
+
+/// Generates a joint proof for the data leaf and annotation at `leaf_index`.
+///
+/// # Arguments
+///
+/// * `mmr` - The pair of MMRs the proof is generated against.
+/// * `leaves` - All data leaves, i.e. those at indices `mmr.start()..mmr.end()`.
+/// * `annotations` - All annotations, at the same indices.
+/// * `leaf_index` - The index of the leaf to prove.
+///
+/// # Returns
+///
+/// The proof, or the error of proving either leaf.
+pub fn prove_joint(
+    mmr: &AnnotatedMMR,
+    leaves: &[B256],
+    annotations: &[B256],
+    leaf_index: u64,
+) -> Result<JointProof, MMRError> {
+    Ok(JointProof {
+        data: prove(&mmr.data, leaves, leaf_index)?,
+        annotation: prove(&mmr.annotations, annotations, leaf_index)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    fn annotated(len: usize) -> (AnnotatedMMR, Vec<B256>, Vec<B256>) {
+        let leaves: Vec<B256> = (0..len).map(|_| get_random_hash()).collect();
+        let annotations: Vec<B256> = (0..len).map(|_| get_random_hash()).collect();
+        let mut mmr = AnnotatedMMR::new();
+        mmr.append_batch(&leaves, &annotations).unwrap();
+        (mmr, leaves, annotations)
+    }
+
+    #[test]
+    fn test_lockstep() {
+        let (mmr, leaves, annotations) = annotated(11);
+        assert_eq!(mmr.data(), &MMR::from_leaves(&leaves));
+        assert_eq!(mmr.annotations(), &MMR::from_leaves(&annotations));
+        assert_eq!(
+            mmr.commitment(),
+            hash_to_parent(
+                &MMR::from_leaves(&leaves).get_root(),
+                &MMR::from_leaves(&annotations).get_root()
+            )
+        );
+
+        let mut appended = AnnotatedMMR::new();
+        for (leaf, annotation) in leaves.iter().zip(&annotations) {
+            appended.append(*leaf, *annotation);
+        }
+        assert_eq!(appended, mmr);
+
+        assert!(matches!(
+            appended.append_batch(&leaves[..2], &annotations[..1]),
+            Err(MMRError::InvalidLeaves)
+        ));
+        assert_eq!(appended, mmr);
+        assert!(matches!(
+            AnnotatedMMR::from_parts(
+                MMR::from_leaves(&leaves),
+                MMR::from_leaves(&annotations[1..])
+            ),
+            Err(MMRError::RangeMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_joint_proofs() {
+        let (mmr, leaves, annotations) = annotated(11);
+        let (data_peaks, annotation_peaks) = (mmr.data().as_peaks(), mmr.annotations().as_peaks());
+        for index in 0..11 {
+            let proof = prove_joint(&mmr, &leaves, &annotations, index as u64).unwrap();
+            assert!(proof.verify(&mmr, leaves[index], annotations[index]));
+            assert!(proof.verify_commitment(
+                data_peaks,
+                annotation_peaks,
+                leaves[index],
+                annotations[index],
+                mmr.commitment()
+            ));
+
+            // The annotation of another index doesn't verify.
+            let other = (index + 1) % 11;
+            assert!(!proof.verify(&mmr, leaves[index], annotations[other]));
+            assert!(!proof.verify_commitment(
+                data_peaks,
+                annotation_peaks,
+                leaves[index],
+                annotations[other],
+                mmr.commitment()
+            ));
+        }
+
+        // Proofs for different indices don't combine.
+        let mixed = JointProof {
+            data: prove(mmr.data(), &leaves, 2).unwrap(),
+            annotation: prove(mmr.annotations(), &annotations, 3).unwrap(),
+        };
+        assert!(!mixed.verify(&mmr, leaves[2], annotations[3]));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::error::MMRError;
+use crate::lru::Lru;
+use crate::mmr::MMR;
+use crate::proof::InclusionProof;
+use crate::utils::hash::Hasher;
+use alloy_primitives::B256;
+use std::fs;
+use std::path::PathBuf;
+
+/// Hit/miss counters of a [`ProofCache`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+}
+
+/// LRU cache of inclusion proofs keyed by (root, start, end, leaf index), with optional disk
+/// persistence.
+///
+/// The range is part of the key because MMRs over different ranges can share a root while
+/// their proofs differ. The in-memory cache only ever holds proofs for a single MMR: looking
+/// up a different one invalidates it. Disk entries are stored per MMR, so they never go stale.
+#[derive(Debug)]
+pub struct ProofCache {
+    mmr: (B256, u64, u64),
+    entries: Lru<u64, InclusionProof>,
+    disk: Option<PathBuf>,
+    metrics: CacheMetrics,
+}
+
+impl ProofCache {
+    /// Creates an in-memory cache holding up to `capacity` proofs
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            mmr: (B256::ZERO, 0, 0),
+            entries: Lru::new(capacity),
+            disk: None,
+            metrics: CacheMetrics::default(),
+        }
+    }
+
+    /// Additionally persists proofs under `dir`, one file per (root, start, end, leaf index)
+    pub fn with_disk<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.disk = Some(dir.into());
+        self
+    }
+
+    /// Returns the hit/miss counters
+    pub fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    /// Returns the number of proofs held in memory
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no proofs are held in memory
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the cached proof for `leaf_index` in `mmr`, or generates and caches it.
+    pub fn get_or_prove<H: Hasher, F>(
+        &mut self,
+        mmr: &MMR<H>,
+        leaf_index: u64,
+        prove: F,
+    ) -> Result<InclusionProof, MMRError>
+    where
+        F: FnOnce() -> Result<InclusionProof, MMRError>,
+    {
+        let key = (mmr.get_root(), mmr.start(), mmr.end());
+        if key != self.mmr {
+            if !self.entries.is_empty() {
+                self.metrics.invalidations += 1;
+            }
+            self.entries.clear();
+            self.mmr = key;
+        }
+
+        if let Some(proof) = self.entries.get(&leaf_index) {
+            self.metrics.hits += 1;
+            return Ok(proof.clone());
+        }
+
+        let proof = match self.read_disk(leaf_index) {
+            Some(proof) => {
+                self.metrics.disk_hits += 1;
+                proof
+            }
+            None => {
+                self.metrics.misses += 1;
+                let proof = prove()?;
+                self.write_disk(&proof);
+                proof
+            }
+        };
+        if self.entries.insert(leaf_index, proof.clone()) {
+            self.metrics.evictions += 1;
+        }
+        Ok(proof)
+    }
+
+    fn disk_path(&self, leaf_index: u64) -> Option<PathBuf> {
+        self.disk.as_ref().map(|dir| {
+            let (root, start, end) = self.mmr;
+            dir.join(format!("{}-{}-{}", root, start, end))
+                .join(leaf_index.to_string())
+        })
+    }
+
+    /// Reads a proof stored as its leaf index followed by the concatenated path.
+    fn read_disk(&self, leaf_index: u64) -> Option<InclusionProof> {
+        let bytes = fs::read(self.disk_path(leaf_index)?).ok()?;
+        let (index, path) = bytes.split_first_chunk::<8>()?;
+        if u64::from_le_bytes(*index) != leaf_index || path.len() % 32 != 0 {
+            return None;
+        }
+        let path = path.chunks_exact(32).map(B256::from_slice).collect();
+        Some(InclusionProof::new(leaf_index, path))
+    }
+
+    /// Best-effort write; a failed write only costs a future regeneration.
+    fn write_disk(&self, proof: &InclusionProof) {
+        let Some(path) = self.disk_path(proof.leaf_index()) else {
+            return;
+        };
+        let mut bytes = proof.leaf_index().to_le_bytes().to_vec();
+        for node in proof.path() {
+            bytes.extend_from_slice(node.as_slice());
+        }
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_hits_and_misses() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let mut cache = ProofCache::new(2);
+
+        let proof = cache
+            .get_or_prove(&mmr, 3, || prove(&mmr, &leaves, 3))
+            .unwrap();
+        assert!(proof.verify(&mmr, leaves[3]));
+        let cached = cache
+            .get_or_prove(&mmr, 3, || panic!("should be cached"))
+            .unwrap();
+        assert_eq!(cached, proof);
+        assert_eq!(
+            cache.metrics(),
+            CacheMetrics {
+                hits: 1,
+                misses: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let mut cache = ProofCache::new(2);
+        for index in [0, 1, 0, 2] {
+            cache
+                .get_or_prove(&mmr, index, || prove(&mmr, &leaves, index))
+                .unwrap();
+        }
+        // Leaf 1 was the least recently used.
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.metrics().evictions, 1);
+        cache
+            .get_or_prove(&mmr, 0, || panic!("should be cached"))
+            .unwrap();
+        cache
+            .get_or_prove(&mmr, 1, || prove(&mmr, &leaves, 1))
+            .unwrap();
+        assert_eq!(cache.metrics().misses, 4);
+    }
+
+    #[test]
+    fn test_root_change_invalidates() {
+        let mut leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let mut cache = ProofCache::new(4);
+        cache
+            .get_or_prove(&mmr, 0, || prove(&mmr, &leaves, 0))
+            .unwrap();
+
+        leaves.push(get_random_hash());
+        let mmr = MMR::from_leaves(&leaves);
+        let proof = cache
+            .get_or_prove(&mmr, 0, || prove(&mmr, &leaves, 0))
+            .unwrap();
+        assert!(proof.verify(&mmr, leaves[0]));
+        assert_eq!(cache.metrics().invalidations, 1);
+        assert_eq!(cache.metrics().misses, 2);
+    }
+
+    #[test]
+    fn test_range_is_part_of_the_key() {
+        let leaves: Vec<B256> = (0..2).map(|_| get_random_hash()).collect();
+        // A single peak is the root whatever range it covers.
+        let mmr = MMR::from_leaves(&leaves);
+        let shifted = MMR::from_params(2, 4, mmr.peaks().to_vec()).unwrap();
+        assert_eq!(shifted.get_root(), mmr.get_root());
+
+        let mut cache = ProofCache::new(4);
+        cache
+            .get_or_prove(&mmr, 0, || prove(&mmr, &leaves, 0))
+            .unwrap();
+        let proof = cache
+            .get_or_prove(&shifted, 2, || prove(&shifted, &leaves, 2))
+            .unwrap();
+        assert!(proof.verify(&shifted, leaves[0]));
+        assert_eq!(cache.metrics().misses, 2);
+        assert_eq!(cache.metrics().invalidations, 1);
+    }
+
+    #[test]
+    fn test_disk_persistence() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let dir = std::env::temp_dir().join(format!("rust-mmr-cache-{}", get_random_hash()));
+
+        let mut cache = ProofCache::new(4).with_disk(&dir);
+        let proof = cache
+            .get_or_prove(&mmr, 5, || prove(&mmr, &leaves, 5))
+            .unwrap();
+
+        // A fresh cache over the same directory reads the proof back from disk.
+        let mut cache = ProofCache::new(4).with_disk(&dir);
+        let cached = cache
+            .get_or_prove(&mmr, 5, || panic!("should be on disk"))
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(cached, proof);
+        assert_eq!(cache.metrics().disk_hits, 1);
+    }
+}
@@ -0,0 +1,82 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of time for the features that measure or record it.
+///
+/// Taking a clock instead of reading the system time keeps those features deterministic in
+/// tests and usable in zkVMs, which have no wall clock.
+pub trait Clock {
+    /// Returns the current time, as the duration since the Unix epoch
+    fn now(&self) -> Duration;
+}
+
+/// The system's wall clock. Only available with the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        // A system clock set before 1970 reads as the epoch.
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// A clock that only moves when told to, with nanosecond resolution.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    nanos: AtomicU64,
+}
+
+impl ManualClock {
+    /// Creates a clock reading `now`
+    pub fn new(now: Duration) -> Self {
+        Self {
+            nanos: AtomicU64::new(now.as_nanos() as u64),
+        }
+    }
+
+    /// Sets the time to `now`
+    pub fn set(&self, now: Duration) {
+        self.nanos.store(now.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Moves the time forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.nanos
+            .fetch_add(by.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(Duration::from_secs(10));
+        assert_eq!(clock.now(), Duration::from_secs(10));
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now(), Duration::from_millis(11_500));
+        clock.set(Duration::ZERO);
+        assert_eq!(clock.now(), Duration::ZERO);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_system_clock() {
+        // Any time after this code was written.
+        assert!(SystemClock.now() > Duration::from_secs(1_700_000_000));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::error::MMRError;
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use crate::utils::hash::Hasher;
+use alloy_primitives::B256;
+
+/// A compact range in the layout of transparency-dev/merkle's `compact.Range`.
+///
+/// `hashes` are the roots of the perfect subtrees covering `[begin, end)`, ordered left to
+/// right. This is the same order as the peaks of an `MMR` over the same range: the left
+/// peaks grow and the right peaks shrink from left to right.
+///
+/// The Go library hashes nodes with RFC 6962 domain separation, so its hashes are only
+/// interchangeable with an MMR built with `Rfc6962Hasher`, whose leaves are hashed with
+/// `Rfc6962Hasher::hash_leaf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactRange {
+    pub begin: u64,
+    pub end: u64,
+    pub hashes: Vec<B256>,
+}
+
+impl CompactRange {
+    /// Encodes the range as `begin || end || hashes`, with the indices as 8 big-endian bytes
+    /// each and the hashes concatenated
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + 32 * self.hashes.len());
+        bytes.extend_from_slice(&self.begin.to_be_bytes());
+        bytes.extend_from_slice(&self.end.to_be_bytes());
+        for hash in &self.hashes {
+            bytes.extend_from_slice(hash.as_slice());
+        }
+        bytes
+    }
+
+    /// Decodes a range encoded by `to_bytes`, within `DecodeLimits::DEFAULT`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MMRError> {
+        Self::from_bytes_with_limits(bytes, &DecodeLimits::DEFAULT)
+    }
+
+    /// Decodes a range encoded by `to_bytes`, or returns `MMRError::TooManyPeaks` if it holds
+    /// more than `limits.max_peaks` hashes
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, MMRError> {
+        let (begin, rest) = bytes
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (end, hashes) = rest
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        if hashes.len() % 32 != 0 {
+            return Err(MMRError::InvalidEncoding);
+        }
+        limits.check_peaks(hashes.len() / 32)?;
+        Ok(Self {
+            begin: u64::from_be_bytes(*begin),
+            end: u64::from_be_bytes(*end),
+            hashes: hashes.chunks_exact(32).map(B256::from_slice).collect(),
+        })
+    }
+}
+
+impl<H: Hasher> From<&MMR<H>> for CompactRange {
+    fn from(mmr: &MMR<H>) -> Self {
+        Self {
+            begin: mmr.start(),
+            end: mmr.end(),
+            hashes: mmr.peaks().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<CompactRange> for MMR {
+    type Error = MMRError;
+
+    fn try_from(range: CompactRange) -> Result<Self, MMRError> {
+        MMR::from_params(range.begin, range.end, range.hashes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::{get_random_hash, hash_subtree};
+    use crate::utils::range::MAX_PEAKS;
+
+    #[test]
+    fn test_hashes_ordered_left_to_right() {
+        let leaves: Vec<B256> = (0..16).map(|_| get_random_hash()).collect();
+
+        // [3, 8) is covered by the leaf 3 and the subtree [4, 8).
+        let mut mmr = MMR::from_params(3, 3, vec![]).unwrap();
+        for leaf in &leaves[3..8] {
+            mmr.append(*leaf);
+        }
+        let range = CompactRange::from(&mmr);
+        assert_eq!(range.hashes, vec![leaves[3], hash_subtree(&leaves[4..8])]);
+
+        // [0, 7) is covered by the subtrees [0, 4), [4, 6) and the leaf 6.
+        let range = CompactRange::from(&MMR::from_leaves(&leaves[..7]));
+        assert_eq!(
+            range.hashes,
+            vec![
+                hash_subtree(&leaves[..4]),
+                hash_subtree(&leaves[4..6]),
+                leaves[6]
+            ]
+        );
+    }
+
+    /// Leaves and tree heads of transparency-dev/merkle's `testonly` package, shared with
+    /// the RFC 6962 test data of certificate-transparency-go.
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_rfc6962_vectors() {
+        use crate::utils::hash::Rfc6962Hasher;
+        use alloy_primitives::{b256, hex};
+
+        let leaves = [
+            &hex!("")[..],
+            &hex!("00"),
+            &hex!("10"),
+            &hex!("2021"),
+            &hex!("3031"),
+            &hex!("40414243"),
+            &hex!("5051525354555657"),
+            &hex!("606162636465666768696a6b6c6d6e6f"),
+        ];
+        let roots = [
+            b256!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            b256!("6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"),
+            b256!("fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"),
+            b256!("aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77"),
+            b256!("d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"),
+            b256!("4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4"),
+            b256!("76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef"),
+            b256!("ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c"),
+            b256!("5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"),
+        ];
+
+        let mut mmr: MMR<Rfc6962Hasher> = MMR::with_hasher();
+        assert_eq!(mmr.get_root(), roots[0]);
+        for (leaf, root) in leaves.iter().zip(&roots[1..]) {
+            mmr.append(Rfc6962Hasher::hash_leaf(leaf));
+            assert_eq!(mmr.get_root(), *root);
+        }
+
+        // The compact range of the 7-leaf tree holds the subtrees [0, 4), [4, 6) and [6, 7),
+        // which the Go library folds right to left into the same head.
+        let mmr: MMR<Rfc6962Hasher> = leaves[..7]
+            .iter()
+            .map(|leaf| Rfc6962Hasher::hash_leaf(leaf))
+            .collect();
+        let hashes = CompactRange::from(&mmr).hashes;
+        assert_eq!(hashes.len(), 3);
+        let head = hashes[..2].iter().rfold(hashes[2], |acc, hash| {
+            Rfc6962Hasher::hash_parent(hash, &acc)
+        });
+        assert_eq!(head, roots[7]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut mmr = MMR::from_params(5, 5, vec![]).unwrap();
+        for _ in 0..20 {
+            mmr.append(get_random_hash());
+        }
+        let range = CompactRange::from(&mmr);
+        let bytes = range.to_bytes();
+        assert_eq!(bytes.len(), 16 + 32 * mmr.peaks().len());
+        assert_eq!(
+            &bytes[..16],
+            &[0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 25]
+        );
+
+        let decoded = CompactRange::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, range);
+        assert_eq!(MMR::try_from(decoded).unwrap(), mmr);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        assert!(matches!(
+            CompactRange::from_bytes(&[0; 15]),
+            Err(MMRError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            CompactRange::from_bytes(&[0; 17]),
+            Err(MMRError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            CompactRange::from_bytes(&[0; 16 + 32 * (MAX_PEAKS + 1)]),
+            Err(MMRError::TooManyPeaks)
+        ));
+        let range = CompactRange {
+            begin: 0,
+            end: 3,
+            hashes: vec![get_random_hash()],
+        };
+        assert!(matches!(
+            MMR::try_from(range),
+            Err(MMRError::InvalidNumberOfPeaks)
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::mmr::MMR;
+use crate::utils::hash::{Hasher, Keccak256Hasher};
+use alloy_primitives::{b256, B256, U256};
+
+/// Number of leaves in the conformance vector shared with plasma-lib.
+pub const PLASMA_NUM_LEAVES: u64 = (1 << 10) + 12345;
+
+/// Root of the MMR over `plasma_leaf(0)..plasma_leaf(PLASMA_NUM_LEAVES)`.
+pub const PLASMA_ROOT: B256 =
+    b256!("f20ad78c9e954b1ab6f4e3d4d45d5eb2c3092e6d49c284403adc63f1ec4bd94a");
+
+/// Peaks of the MMR over `plasma_leaf(0)..plasma_leaf(PLASMA_NUM_LEAVES)`.
+pub const PLASMA_PEAKS: [B256; 7] = [
+    b256!("9cd2165f9ca0b9f495678716ecef463c15442c5078b35d1afa4feb2730f93af1"),
+    b256!("e9c7c8c1f62832a1aeca64cfdf95b47563e048d98fc668c9f7c0da3fa0c349d7"),
+    b256!("8d4c7f591cbcc0333a106c16fdcd176c69f506706e81bc7578eeed49fb161f65"),
+    b256!("5f5270c99f31d41394adc86ace55db213cb1441baaa3d90d42ce6f59431407de"),
+    b256!("9b605c9eccb93ad289b8b91a2691a1417b01a45beadab0f0c3847af1e058533b"),
+    b256!("e2d9d763b82d01e7b716f6526e8c85cc860c60fdf3553bb245337a614249e3d7"),
+    b256!("0000000000000000000000000000000000000000000000000000000000003438"),
+];
+
+/// Root of the MMR over the same leaves, hashed with `Sha256Hasher`.
+#[cfg(feature = "sha2")]
+pub const PLASMA_SHA256_ROOT: B256 =
+    b256!("e16e6750409cf397798693f32ea0a583460a48c82900560675971ccbc098a4d2");
+
+/// Root of the MMR over the same leaves, hashed with `Sha3Hasher`.
+#[cfg(feature = "sha3")]
+pub const PLASMA_SHA3_ROOT: B256 =
+    b256!("e6cd4844fe1d92937099898d532f7b4690c0612d66ecff9ac5b7ec91c81e7185");
+
+/// Split points used to rebuild the vector from two shards.
+pub const PLASMA_SPLITS: [u64; 6] = [1, 1024, 1025, 4096, 8191, PLASMA_NUM_LEAVES - 1];
+
+/// Returns the leaf at `index`: the index as a big-endian 32-byte word
+pub fn plasma_leaf(index: u64) -> B256 {
+    U256::from(index).into()
+}
+
+/// Computes the root of the MMR over the first `n_leaves` conformance leaves
+pub fn plasma_conformance_root(n_leaves: u64) -> B256 {
+    plasma_conformance_root_with::<Keccak256Hasher>(n_leaves)
+}
+
+/// Like `plasma_conformance_root`, hashing with `H`
+pub fn plasma_conformance_root_with<H: Hasher>(n_leaves: u64) -> B256 {
+    let mut mmr: MMR<H> = MMR::with_hasher();
+    for index in 0..n_leaves {
+        mmr.append(plasma_leaf(index));
+    }
+    mmr.get_root()
+}
+
+/// Asserts that `build`, given the conformance leaves, returns the expected root and peaks.
+pub fn assert_plasma_conformance<F>(build: F)
+where
+    F: FnOnce(&[B256]) -> (B256, Vec<B256>),
+{
+    let leaves: Vec<B256> = (0..PLASMA_NUM_LEAVES).map(plasma_leaf).collect();
+    let (root, peaks) = build(&leaves);
+    assert_eq!(peaks, PLASMA_PEAKS, "conformance peaks mismatch");
+    assert_eq!(root, PLASMA_ROOT, "conformance root mismatch");
+}
+
+/// Asserts that building the conformance leaves as a zero-starting shard and a non-zero
+/// starting shard, then merging them, yields the expected root and peaks at every split.
+///
+/// `build` receives the shard's start and leaves and returns the shard; `merge` combines two
+/// bordering shards.
+pub fn assert_plasma_merge_conformance<B, M>(build: B, merge: M)
+where
+    B: Fn(u64, &[B256]) -> MMR,
+    M: Fn(&MMR, &MMR) -> MMR,
+{
+    let leaves: Vec<B256> = (0..PLASMA_NUM_LEAVES).map(plasma_leaf).collect();
+    for split in PLASMA_SPLITS {
+        let left = build(0, &leaves[..split as usize]);
+        let right = build(split, &leaves[split as usize..]);
+        let merged = merge(&left, &right);
+        assert_eq!(
+            merged.peaks(),
+            PLASMA_PEAKS,
+            "conformance peaks mismatch at split {}",
+            split
+        );
+        assert_eq!(
+            merged.get_root(),
+            PLASMA_ROOT,
+            "conformance root mismatch at split {}",
+            split
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plasma_conformance() {
+        assert_eq!(plasma_conformance_root(PLASMA_NUM_LEAVES), PLASMA_ROOT);
+        assert_plasma_conformance(|leaves| {
+            let mmr = MMR::from_leaves(leaves);
+            (mmr.get_root(), mmr.peaks().to_vec())
+        });
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_plasma_sha256_conformance() {
+        use crate::utils::hash::{compute_root_with, Sha256Hasher};
+        let leaves: Vec<B256> = (0..PLASMA_NUM_LEAVES).map(plasma_leaf).collect();
+        assert_eq!(
+            plasma_conformance_root_with::<Sha256Hasher>(PLASMA_NUM_LEAVES),
+            PLASMA_SHA256_ROOT
+        );
+        assert_eq!(
+            compute_root_with::<Sha256Hasher>(&leaves),
+            PLASMA_SHA256_ROOT
+        );
+    }
+
+    #[cfg(feature = "sha3")]
+    #[test]
+    fn test_plasma_sha3_conformance() {
+        use crate::utils::hash::{compute_root_with, Sha3Hasher};
+        let leaves: Vec<B256> = (0..PLASMA_NUM_LEAVES).map(plasma_leaf).collect();
+        assert_eq!(
+            plasma_conformance_root_with::<Sha3Hasher>(PLASMA_NUM_LEAVES),
+            PLASMA_SHA3_ROOT
+        );
+        assert_eq!(compute_root_with::<Sha3Hasher>(&leaves), PLASMA_SHA3_ROOT);
+    }
+
+    #[test]
+    fn test_plasma_merge_conformance() {
+        assert_plasma_merge_conformance(
+            |start, leaves| {
+                let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+                mmr.append_batch(leaves);
+                mmr
+            },
+            |left, right| left.merge(right).unwrap(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "conformance root mismatch")]
+    fn test_detects_wrong_root() {
+        assert_plasma_conformance(|leaves| {
+            let mmr = MMR::from_leaves(leaves);
+            (B256::ZERO, mmr.peaks().to_vec())
+        });
+    }
+}
@@ -0,0 +1,298 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::trace::AppendTrace;
+use crate::utils::range::decompose;
+use alloy_primitives::B256;
+use std::collections::{HashMap, VecDeque};
+
+/// Proof that an MMR over `[0, new_end)` extends the one over `[0, old_end)`.
+///
+/// It holds the peaks of `[old_end, new_end)`, left to right: merging them into the old MMR
+/// must give the new root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    old_end: u64,
+    new_end: u64,
+    peaks: Vec<B256>,
+}
+
+impl ConsistencyProof {
+    /// Creates a proof from the range it spans and the peaks of `[old_end, new_end)`
+    pub fn new(old_end: u64, new_end: u64, peaks: Vec<B256>) -> Self {
+        Self {
+            old_end,
+            new_end,
+            peaks,
+        }
+    }
+
+    /// Returns the end of the old MMR
+    pub fn old_end(&self) -> u64 {
+        self.old_end
+    }
+
+    /// Returns the end of the new MMR
+    pub fn new_end(&self) -> u64 {
+        self.new_end
+    }
+
+    /// Returns the peaks of the appended range
+    pub fn peaks(&self) -> &[B256] {
+        &self.peaks
+    }
+
+    /// Verifies that the MMR with root `new_root` extends `old`, hashing like `old`
+    pub fn verify(&self, old: &MMR, new_root: B256) -> bool {
+        if old.start() != 0 || old.end() != self.old_end {
+            return false;
+        }
+        let Ok(peaks) = Peaks::new(self.old_end, self.new_end, self.peaks.clone()) else {
+            return false;
+        };
+        old.merge(&old.with_peaks(peaks))
+            .is_ok_and(|merged| merged.get_root() == new_root)
+    }
+
+    /// Returns an upper bound on the hashes `verify` computes against an old MMR with
+    /// `old_peaks` peaks, from the sizes of the inputs only
+    pub fn max_hashes(&self, old_peaks: usize) -> u64 {
+        // Merging n + p peaks into m takes n + p - m hashes, and bagging m peaks m - 1.
+        (old_peaks + self.peaks.len()).saturating_sub(1) as u64
+    }
+
+    /// Verifies like `verify`, unless it could take more than `max_hashes` hashes, in which
+    /// case it fails with `MMRError::BudgetExceeded` before hashing
+    pub fn verify_with_budget(
+        &self,
+        old: &MMR,
+        new_root: B256,
+        max_hashes: u64,
+    ) -> Result<bool, MMRError> {
+        if self.max_hashes(old.peaks().len()) > max_hashes {
+            return Err(MMRError::BudgetExceeded);
+        }
+        Ok(self.verify(old, new_root))
+    }
+}
+
+/// The state of an MMR at an indexed end.
+#[derive(Debug, Clone, PartialEq)]
+struct Checkpoint {
+    mmr: MMR,
+    /// The subtrees ending at the checkpoint, from height `granularity` upwards
+    completed: Vec<B256>,
+}
+
+/// Index of the states a zero-starting MMR had at every end that is a multiple of
+/// `2^granularity`, retaining the most recent `capacity` of them.
+///
+/// Each checkpoint holds the MMR at its end and the subtrees the append reaching it
+/// completed. Every subtree in the proof of consistency between two indexed ends is one of
+/// those, so proofs are generated from the index alone, without leaf or node storage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakIndex {
+    granularity: u32,
+    capacity: usize,
+    order: VecDeque<u64>,
+    checkpoints: HashMap<u64, Checkpoint>,
+}
+
+impl PeakIndex {
+    /// Creates an index of the ends that are multiples of `2^granularity`, retaining up to
+    /// `capacity` checkpoints
+    pub fn new(granularity: u32, capacity: usize) -> Self {
+        Self {
+            granularity,
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            checkpoints: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Records a checkpoint if `mmr` starts at zero and ends at an indexed end, evicting the
+    /// oldest checkpoint when full.
+    ///
+    /// `trace` is the trace of the append that brought `mmr` to its end.
+    pub fn record(&mut self, mmr: &MMR, trace: &AppendTrace) {
+        let end = mmr.end();
+        if self.capacity == 0
+            || mmr.start() != 0
+            || end == 0
+            || end.trailing_zeros() < self.granularity
+            || self.checkpoints.contains_key(&end)
+        {
+            return;
+        }
+        // Replay the append: the node at each height is the subtree of that height ending here.
+        let merged = trace.siblings().iter().scan(trace.leaf(), |node, sibling| {
+            *node = mmr.hash_to_parent(sibling, node);
+            Some(*node)
+        });
+        let completed = std::iter::once(trace.leaf())
+            .chain(merged)
+            .skip(self.granularity as usize)
+            .collect();
+
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.checkpoints.remove(&oldest);
+            }
+        }
+        self.checkpoints.insert(
+            end,
+            Checkpoint {
+                mmr: mmr.clone(),
+                completed,
+            },
+        );
+        self.order.push_back(end);
+    }
+
+    /// Returns the MMR as of `end`, if its checkpoint is retained
+    pub fn mmr_at(&self, end: u64) -> Option<MMR> {
+        Some(self.checkpoints.get(&end)?.mmr.clone())
+    }
+
+    /// Generates a proof that the MMR as of `new_end` extends the one as of `old_end`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if `old_end > new_end`, or if a checkpoint the proof needs is not retained. The
+    /// proof needs the checkpoints at the ends of the subtrees decomposing
+    /// `[old_end, new_end)`, which are all indexed if both ends are.
+    pub fn prove_consistency(&self, old_end: u64, new_end: u64) -> Option<ConsistencyProof> {
+        if old_end > new_end {
+            return None;
+        }
+        let (left, right) = decompose(old_end, new_end);
+        let heights = (0..u64::BITS)
+            .filter(|height| left >> height & 1 == 1)
+            .chain(
+                (0..u64::BITS)
+                    .rev()
+                    .filter(|height| right >> height & 1 == 1),
+            );
+        let mut subtree_end = old_end;
+        let peaks = heights
+            .map(|height| {
+                subtree_end += 1 << height;
+                let checkpoint = self.checkpoints.get(&subtree_end)?;
+                let offset = height.checked_sub(self.granularity)?;
+                checkpoint.completed.get(offset as usize).copied()
+            })
+            .collect::<Option<_>>()?;
+        Some(ConsistencyProof {
+            old_end,
+            new_end,
+            peaks,
+        })
+    }
+
+    /// Returns the number of retained checkpoints
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns true if no checkpoints are retained
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    fn indexed(num_leaves: usize, granularity: u32, capacity: usize) -> (Vec<B256>, PeakIndex) {
+        let leaves: Vec<B256> = (0..num_leaves).map(|_| get_random_hash()).collect();
+        let mut index = PeakIndex::new(granularity, capacity);
+        let mut mmr = MMR::new();
+        for leaf in &leaves {
+            let trace = mmr.append_traced(*leaf);
+            index.record(&mmr, &trace);
+        }
+        (leaves, index)
+    }
+
+    #[test]
+    fn test_prove_consistency() {
+        let (leaves, index) = indexed(64, 2, 16);
+        for old_end in (0..=64).step_by(4) {
+            for new_end in (old_end..=64).step_by(4) {
+                let proof = index.prove_consistency(old_end, new_end).unwrap();
+                let old = MMR::from_leaves(&leaves[..old_end as usize]);
+                let new_root = MMR::from_leaves(&leaves[..new_end as usize]).get_root();
+                assert!(proof.verify(&old, new_root));
+                assert!(!proof.verify(&old, get_random_hash()) || old_end == new_end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unindexed_ends() {
+        let (_, index) = indexed(64, 2, 16);
+        assert!(index.prove_consistency(4, 10).is_none());
+        // [2, 4), [5, 6) and [6, 8) are below the granularity.
+        assert!(index.prove_consistency(2, 8).is_none());
+        assert!(index.prove_consistency(5, 8).is_none());
+        assert!(index.prove_consistency(8, 4).is_none());
+        assert!(index.mmr_at(6).is_none());
+    }
+
+    #[test]
+    fn test_retention() {
+        let (leaves, index) = indexed(64, 3, 4);
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.mmr_at(40), Some(MMR::from_leaves(&leaves[..40])));
+        // The peak set at 24 was evicted.
+        assert!(index.mmr_at(24).is_none());
+        assert!(index.prove_consistency(32, 64).is_some());
+        assert!(index.prove_consistency(16, 64).is_none());
+    }
+
+    #[test]
+    fn test_verify_with_budget() {
+        let (leaves, index) = indexed(32, 2, 8);
+        let proof = index.prove_consistency(12, 32).unwrap();
+        let old = MMR::from_leaves(&leaves[..12]);
+        let new_root = MMR::from_leaves(&leaves).get_root();
+        // Peaks [0, 8) and [8, 12) merged with [12, 16) and [16, 32): 3 merges, no bagging.
+        assert_eq!(proof.max_hashes(old.peaks().len()), 3);
+        assert!(proof.verify_with_budget(&old, new_root, 3).unwrap());
+        assert!(matches!(
+            proof.verify_with_budget(&old, new_root, 2),
+            Err(MMRError::BudgetExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_other_old_mmr() {
+        let (leaves, index) = indexed(32, 2, 8);
+        let proof = index.prove_consistency(16, 32).unwrap();
+        let new_root = MMR::from_leaves(&leaves).get_root();
+        let mut tampered = leaves[..16].to_vec();
+        tampered[3] = get_random_hash();
+        assert!(!proof.verify(&MMR::from_leaves(&tampered), new_root));
+        assert!(!proof.verify(&MMR::from_leaves(&leaves[..12]), new_root));
+    }
+
+    #[test]
+    fn test_keyed() {
+        let key = get_random_hash();
+        let leaves: Vec<B256> = (0..32).map(|_| get_random_hash()).collect();
+        let mut index = PeakIndex::new(2, 8);
+        let mut mmr = MMR::new().with_key(key);
+        for leaf in &leaves {
+            let trace = mmr.append_traced(*leaf);
+            index.record(&mmr, &trace);
+        }
+
+        let old = index.mmr_at(12).unwrap();
+        assert!(old.is_keyed());
+        let proof = index.prove_consistency(12, 32).unwrap();
+        assert!(proof.verify(&old, mmr.get_root()));
+        assert!(!proof.verify(&MMR::from_leaves(&leaves[..12]), mmr.get_root()));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use alloy_primitives::B256;
+use std::collections::HashMap;
+
+/// Result of appending to a [`DedupMMR`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupOutcome {
+    /// The leaf was new and appended at this index.
+    Appended(u64),
+    /// The leaf was already present at this index and was not appended again.
+    Duplicate(u64),
+}
+
+/// An MMR with set semantics: a leaf hash is appended at most once.
+///
+/// Leaves are tracked in an exact in-memory index, so only leaves appended through the
+/// wrapper are deduplicated.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DedupMMR {
+    mmr: MMR,
+    indices: HashMap<B256, u64>,
+}
+
+impl DedupMMR {
+    /// Wraps `mmr`; leaves appended before wrapping are not known to the index
+    pub fn new(mmr: MMR) -> Self {
+        Self {
+            mmr,
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Builds a zero-starting MMR from the distinct leaves, keeping the first occurrence
+    pub fn from_leaves(leaves: &[B256]) -> Self {
+        let mut mmr = Self::default();
+        for leaf in leaves {
+            mmr.append(*leaf);
+        }
+        mmr
+    }
+
+    /// Appends `element` unless it is already present.
+    ///
+    /// Panics under the same conditions as `MMR::append`.
+    pub fn append(&mut self, element: B256) -> DedupOutcome {
+        self.try_append(element)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Appends `element` unless it is already present, returning errors from
+    /// `MMR::try_append`
+    pub fn try_append(&mut self, element: B256) -> Result<DedupOutcome, MMRError> {
+        if let Some(index) = self.indices.get(&element) {
+            return Ok(DedupOutcome::Duplicate(*index));
+        }
+        let index = self.mmr.end();
+        self.mmr.try_append(element)?;
+        self.indices.insert(element, index);
+        Ok(DedupOutcome::Appended(index))
+    }
+
+    /// Returns the index of `leaf`, if it was appended through this wrapper
+    pub fn index_of(&self, leaf: &B256) -> Option<u64> {
+        self.indices.get(leaf).copied()
+    }
+
+    /// Returns the underlying MMR
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_rejects_duplicates() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let mut mmr = DedupMMR::new(MMR::new());
+        assert_eq!(mmr.append(leaves[0]), DedupOutcome::Appended(0));
+        assert_eq!(mmr.append(leaves[1]), DedupOutcome::Appended(1));
+        assert_eq!(mmr.append(leaves[0]), DedupOutcome::Duplicate(0));
+        assert_eq!(mmr.append(leaves[2]), DedupOutcome::Appended(2));
+        assert_eq!(mmr.index_of(&leaves[2]), Some(2));
+        assert_eq!(mmr.index_of(&leaves[3]), None);
+        assert_eq!(mmr.mmr(), &MMR::from_leaves(&leaves[..3]));
+    }
+
+    #[test]
+    fn test_from_leaves_keeps_first_occurrence() {
+        let leaves: Vec<B256> = (0..3).map(|_| get_random_hash()).collect();
+        let mmr = DedupMMR::from_leaves(&[leaves[0], leaves[1], leaves[0], leaves[2], leaves[1]]);
+        assert_eq!(mmr.mmr(), &MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_nonzero_start() {
+        let leaf = get_random_hash();
+        let mut mmr = DedupMMR::new(MMR::from_params(7, 7, vec![]).unwrap().with_max_end(8));
+        assert_eq!(mmr.append(leaf), DedupOutcome::Appended(7));
+        // Duplicates are reported before the range limit is checked.
+        assert_eq!(mmr.try_append(leaf).unwrap(), DedupOutcome::Duplicate(7));
+        assert!(matches!(
+            mmr.try_append(get_random_hash()),
+            Err(MMRError::RangeLimitReached)
+        ));
+    }
+}
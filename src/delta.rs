@@ -0,0 +1,247 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::utils::range::get_peak_heights;
+use alloy_primitives::B256;
+
+/// The change between two states of the same MMR: the subtree roots covering the leaves
+/// between `start` and `end`.
+///
+/// `start` is the old end, or the end of an earlier old peak when the delta replaces the
+/// peaks after it. Replicas sync by shipping a delta, which holds at most two peaks per
+/// level of the new range, instead of the new leaves or the full peak set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MMRDelta {
+    range: MMR,
+}
+
+impl MMRDelta {
+    /// Creates a delta for appending `leaves` to an unkeyed MMR with the default scheme
+    /// ending at `old_end`. See `for_mmr` for other MMRs.
+    pub fn new(old_end: u64, leaves: &[B256]) -> Self {
+        let mut range =
+            MMR::from_params(old_end, old_end, vec![]).expect("an empty range is always valid");
+        for leaf in leaves {
+            range.append(*leaf);
+        }
+        Self { range }
+    }
+
+    /// Creates a delta for appending `leaves` to `mmr`, hashed with its scheme and key.
+    ///
+    /// Fails like `MMR::try_append` if `mmr` can't take the leaves.
+    pub fn for_mmr(mmr: &MMR, leaves: &[B256]) -> Result<Self, MMRError> {
+        let mut range = mmr.with_peaks(Peaks::empty(mmr.end()));
+        for leaf in leaves {
+            range.try_append(*leaf)?;
+        }
+        Ok(Self { range })
+    }
+
+    /// Creates a delta from the compact range of the appended leaves, validating it
+    pub fn from_params(start: u64, end: u64, peaks: Vec<B256>) -> Result<Self, MMRError> {
+        MMR::from_params(start, end, peaks).map(|range| Self { range })
+    }
+
+    /// Returns the leaf index the delta starts at, the end of the peaks it keeps
+    pub fn start(&self) -> u64 {
+        self.range.start()
+    }
+
+    /// Returns the end of the MMR after applying this delta
+    pub fn end(&self) -> u64 {
+        self.range.end()
+    }
+
+    /// Returns the subtree roots covering the appended leaves
+    pub fn peaks(&self) -> &[B256] {
+        self.range.peaks()
+    }
+
+    /// Checks that applying this delta to `old`, whose root is `old_root`, yields `new_root`
+    pub fn verify(&self, old: &MMR, old_root: B256, new_root: B256) -> bool {
+        if old.get_root() != old_root {
+            return false;
+        }
+        old.applied(self)
+            .is_ok_and(|new| new.get_root() == new_root)
+    }
+}
+
+impl MMR {
+    /// Applies a delta produced against this MMR's current end, or against the end of one
+    /// of its peaks, in which case the peaks after it are replaced
+    pub fn apply(&mut self, delta: &MMRDelta) -> Result<(), MMRError> {
+        *self = self.applied(delta)?;
+        Ok(())
+    }
+
+    /// Returns the delta that turns this MMR into `newer`, a later snapshot of the same log.
+    ///
+    /// The delta holds only the peaks of `newer` that this MMR lacks: its peaks before the
+    /// first one `newer` merged are kept by the receiver. Only those kept peaks can be
+    /// compared, so a `newer` that rewrote the merged ones is not detected here; check the
+    /// new root with `MMRDelta::verify` or a `ConsistencyProof` where that matters.
+    ///
+    /// # Returns
+    ///
+    /// `MMRError::SchemeMismatch` if the schemes or keys differ, `MMRError::RangeMismatch` if
+    /// `newer` starts elsewhere or ends before this MMR, and `MMRError::Diverged` if the peaks
+    /// both should share differ.
+    pub fn delta(&self, newer: &MMR) -> Result<MMRDelta, MMRError> {
+        if !self.hashes_like(newer) {
+            return Err(MMRError::SchemeMismatch);
+        }
+        if self.start() != newer.start() || self.end() > newer.end() {
+            return Err(MMRError::RangeMismatch);
+        }
+        // Peaks are maximal subtrees, so a leading peak of the same height in both ranges is
+        // the same node.
+        let kept = get_peak_heights(self.start(), self.end())
+            .into_iter()
+            .zip(get_peak_heights(newer.start(), newer.end()))
+            .take_while(|(old, new)| old == new)
+            .map(|(height, _)| height)
+            .collect::<Vec<_>>();
+        if self.peaks()[..kept.len()] != newer.peaks()[..kept.len()] {
+            return Err(MMRError::Diverged);
+        }
+        let start = kept
+            .iter()
+            .fold(self.start(), |end, height| end + (1 << height));
+        let peaks = newer.peaks()[kept.len()..].to_vec();
+        let range = newer.with_peaks(Peaks::new(start, newer.end(), peaks)?);
+        Ok(MMRDelta { range })
+    }
+
+    /// Returns this MMR with `delta` applied, keeping its peaks before the delta's start
+    fn applied(&self, delta: &MMRDelta) -> Result<MMR, MMRError> {
+        if delta.start() == self.end() {
+            return self.merge(&delta.range);
+        }
+        // The peaks of a range split at a peak boundary are those on either side of it.
+        let (kept, _) = self
+            .split(delta.start(), self.peaks())
+            .map_err(|_| MMRError::MergeError)?;
+        kept.merge(&delta.range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_apply_delta() {
+        let leaves: Vec<B256> = (0..45).map(|_| get_random_hash()).collect();
+        let mut replica = MMR::from_leaves(&leaves[..13]);
+        let delta = MMRDelta::new(13, &leaves[13..]);
+        // [13, 45) is covered by subtrees of sizes [1, 2, 16], [8, 4, 1].
+        assert_eq!(delta.peaks().len(), 6);
+
+        replica.apply(&delta).unwrap();
+        assert_eq!(replica, MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_keyed_delta_for_mmr() {
+        let leaves: Vec<B256> = (0..20).map(|_| get_random_hash()).collect();
+        let mut keyed = MMR::new().with_key(get_random_hash());
+        keyed.append_batch(&leaves[..7]);
+        let mut replica = keyed.clone();
+        let delta = MMRDelta::for_mmr(&keyed, &leaves[7..]).unwrap();
+        replica.apply(&delta).unwrap();
+        keyed.append_batch(&leaves[7..]);
+        assert_eq!(replica, keyed);
+
+        // A delta hashed without the key doesn't apply.
+        let plain = MMRDelta::new(keyed.end(), &leaves[..3]);
+        assert!(matches!(keyed.apply(&plain), Err(MMRError::SchemeMismatch)));
+    }
+
+    #[test]
+    fn test_apply_delta_wrong_end() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mut replica = MMR::from_leaves(&leaves[..4]);
+        let delta = MMRDelta::new(5, &leaves[5..]);
+        assert!(matches!(replica.apply(&delta), Err(MMRError::MergeError)));
+        assert_eq!(replica.end(), 4);
+    }
+
+    #[test]
+    fn test_delta_between_snapshots() {
+        let leaves: Vec<B256> = (0..40).map(|_| get_random_hash()).collect();
+        let key = get_random_hash();
+        for (start, keyed) in [(0, false), (3, false), (0, true), (3, true)] {
+            for old_end in start..24 {
+                for new_end in old_end..40 {
+                    let old = MMR::from_params(start, start, vec![]).unwrap();
+                    let old = if keyed { old.with_key(key) } else { old };
+                    let (mut old, mut new) = (old.clone(), old);
+                    old.append_batch(&leaves[start as usize..old_end as usize]);
+                    new.append_batch(&leaves[start as usize..new_end as usize]);
+
+                    let delta = old.delta(&new).unwrap();
+                    assert_eq!(delta.end(), new_end);
+                    assert!(new.peaks().ends_with(delta.peaks()));
+                    assert!(delta.verify(&old, old.get_root(), new.get_root()));
+                    old.apply(&delta).unwrap();
+                    assert_eq!(old, new);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_delta_sends_only_new_peaks() {
+        let leaves: Vec<B256> = (0..21).map(|_| get_random_hash()).collect();
+        let old = MMR::from_leaves(&leaves[..20]);
+        let new = MMR::from_leaves(&leaves);
+        // [0, 20) has peaks [16, 4], and [0, 21) adds a single leaf peak.
+        let delta = old.delta(&new).unwrap();
+        assert_eq!(delta.start(), 20);
+        assert_eq!(delta.peaks(), &[leaves[20]]);
+
+        // [0, 24) merges the peak of [16, 20).
+        let mut newer = new.clone();
+        newer.append_batch(&[get_random_hash(); 3]);
+        let delta = old.delta(&newer).unwrap();
+        assert_eq!(delta.start(), 16);
+        assert_eq!(delta.peaks().len(), 1);
+    }
+
+    #[test]
+    fn test_delta_errors() {
+        let leaves: Vec<B256> = (0..20).map(|_| get_random_hash()).collect();
+        let old = MMR::from_leaves(&leaves[..16]);
+        let new = MMR::from_leaves(&leaves);
+        assert!(matches!(new.delta(&old), Err(MMRError::RangeMismatch)));
+
+        let shifted = MMR::from_params(1, 1, vec![]).unwrap();
+        assert!(matches!(shifted.delta(&new), Err(MMRError::RangeMismatch)));
+
+        // [0, 16) is a single peak which [0, 20) keeps, so a fork below it is caught.
+        let forked = MMR::from_leaves(&[vec![get_random_hash()], leaves[1..].to_vec()].concat());
+        assert!(matches!(old.delta(&forked), Err(MMRError::Diverged)));
+    }
+
+    #[test]
+    fn test_verify_delta() {
+        let leaves: Vec<B256> = (0..20).map(|_| get_random_hash()).collect();
+        let old = MMR::from_leaves(&leaves[..7]);
+        let new = MMR::from_leaves(&leaves);
+        let delta = MMRDelta::new(7, &leaves[7..]);
+        assert!(delta.verify(&old, old.get_root(), new.get_root()));
+        assert!(!delta.verify(&old, old.get_root(), get_random_hash()));
+        assert!(!delta.verify(&old, get_random_hash(), new.get_root()));
+
+        let tampered = MMRDelta::from_params(
+            delta.start(),
+            delta.end(),
+            delta.peaks().iter().map(|_| get_random_hash()).collect(),
+        )
+        .unwrap();
+        assert!(!tampered.verify(&old, old.get_root(), new.get_root()));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::utils::hash::Hasher;
+use alloy_primitives::B256;
+
+/// An MMR committed under two hashers at once, e.g. Keccak256 for EVM verification and a
+/// SNARK-friendly hash for circuits.
+///
+/// Both sides cover the same range and are fed by one append stream: each leaf is checked
+/// against both sides and its merge path computed once, then folded into both sets of
+/// peaks. The two roots therefore always commit to the same leaves, unlike two MMRs kept
+/// side by side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DualMMR<A: Hasher, B: Hasher> {
+    first: MMR<A>,
+    second: MMR<B>,
+}
+
+impl<A: Hasher, B: Hasher> Default for DualMMR<A, B> {
+    fn default() -> Self {
+        Self::with_hashers()
+    }
+}
+
+impl<A: Hasher, B: Hasher> DualMMR<A, B> {
+    /// Creates a new empty MMR hashing with both `A` and `B`
+    pub fn with_hashers() -> Self {
+        Self {
+            first: MMR::with_hasher(),
+            second: MMR::with_hasher(),
+        }
+    }
+
+    /// Pairs two MMRs built from the same leaves, returning `MMRError::RangeMismatch` if
+    /// their ranges differ
+    pub fn from_parts(first: MMR<A>, second: MMR<B>) -> Result<Self, MMRError> {
+        if first.start() != second.start() || first.end() != second.end() {
+            return Err(MMRError::RangeMismatch);
+        }
+        Ok(Self { first, second })
+    }
+
+    /// Returns the side hashed with `A`
+    pub fn first(&self) -> &MMR<A> {
+        &self.first
+    }
+
+    /// Returns the side hashed with `B`
+    pub fn second(&self) -> &MMR<B> {
+        &self.second
+    }
+
+    /// Splits the dual MMR into its two sides
+    pub fn into_parts(self) -> (MMR<A>, MMR<B>) {
+        (self.first, self.second)
+    }
+
+    /// Returns the start index of the MMR
+    pub fn start(&self) -> u64 {
+        self.first.start()
+    }
+
+    /// Returns the end index of the MMR
+    pub fn end(&self) -> u64 {
+        self.first.end()
+    }
+
+    /// Returns the roots under `A` and `B`
+    pub fn roots(&self) -> (B256, B256) {
+        (self.first.get_root(), self.second.get_root())
+    }
+
+    /// Appends an element to both sides.
+    ///
+    /// Panics if either side would reject `element`; use `try_append` to handle this.
+    pub fn append(&mut self, element: B256) {
+        if let Err(err) = self.try_append(element) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Appends an element to both sides, or returns the error of the first side rejecting
+    /// it, as `MMR::try_append` would. Nothing is appended to either side on error.
+    pub fn try_append(&mut self, element: B256) -> Result<(), MMRError> {
+        self.first.check_append(element)?;
+        self.second.check_append(element)?;
+        // Both sides cover the same range, so the leaf merges with as many peaks on each.
+        let merges = self.first.next_merges();
+        self.first.append_merged(element, merges);
+        self.second.append_merged(element, merges);
+        Ok(())
+    }
+}
+
+impl<A: Hasher, B: Hasher> Extend<B256> for DualMMR<A, B> {
+    fn extend<I: IntoIterator<Item = B256>>(&mut self, iter: I) {
+        for element in iter {
+            self.append(element);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::{
+        get_random_hash, Bn254Keccak256Hasher, Keccak256Hasher, BN254_SCALAR_MODULUS,
+    };
+
+    type Dual = DualMMR<Keccak256Hasher, Bn254Keccak256Hasher>;
+
+    fn field_leaf() -> B256 {
+        Bn254Keccak256Hasher::reduce(get_random_hash())
+    }
+
+    #[test]
+    fn test_roots_match_separate_mmrs() {
+        let leaves: Vec<B256> = (0..37).map(|_| field_leaf()).collect();
+        let mut dual = Dual::with_hashers();
+        dual.extend(leaves.iter().copied());
+
+        let keccak = MMR::from_leaves(&leaves);
+        let mut bn254: MMR<Bn254Keccak256Hasher> = MMR::with_hasher();
+        bn254.append_batch(&leaves);
+        assert_eq!(dual.roots(), (keccak.get_root(), bn254.get_root()));
+        assert_eq!(dual.end(), 37);
+        assert_eq!(dual.into_parts(), (keccak, bn254));
+    }
+
+    #[test]
+    fn test_rejected_leaf_appends_to_neither_side() {
+        let mut dual = Dual::with_hashers();
+        dual.append(field_leaf());
+        let roots = dual.roots();
+
+        // Keccak256 accepts any leaf, but the BN254 side rejects out-of-field ones.
+        let modulus = B256::from(BN254_SCALAR_MODULUS.to_be_bytes::<32>());
+        assert!(matches!(
+            dual.try_append(modulus),
+            Err(MMRError::LeafOutOfDomain)
+        ));
+        assert_eq!(dual.first().end(), 1);
+        assert_eq!(dual.second().end(), 1);
+        assert_eq!(dual.roots(), roots);
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let leaves: Vec<B256> = (0..5).map(|_| field_leaf()).collect();
+        let keccak = MMR::from_leaves(&leaves);
+        let mut bn254: MMR<Bn254Keccak256Hasher> = MMR::with_hasher();
+        bn254.append_batch(&leaves[..4]);
+        assert!(matches!(
+            Dual::from_parts(keccak.clone(), bn254.clone()),
+            Err(MMRError::RangeMismatch)
+        ));
+
+        bn254.append(leaves[4]);
+        let mut dual = Dual::from_parts(keccak, bn254).unwrap();
+        dual.append(field_leaf());
+        assert_eq!(dual.first().end(), dual.second().end());
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_keccak256_and_poseidon() {
+        use crate::utils::hash::PoseidonHasher;
+
+        let leaves: Vec<B256> = (0..21).map(|_| field_leaf()).collect();
+        let mut dual: DualMMR<Keccak256Hasher, PoseidonHasher> = DualMMR::with_hashers();
+        dual.extend(leaves.iter().copied());
+
+        let mut poseidon: MMR<PoseidonHasher> = MMR::with_hasher();
+        poseidon.append_batch(&leaves);
+        assert_eq!(
+            dual.roots(),
+            (MMR::from_leaves(&leaves).get_root(), poseidon.get_root())
+        );
+
+        // Like the BN254 side, the Poseidon side rejects out-of-field leaves for both.
+        let modulus = B256::from(BN254_SCALAR_MODULUS.to_be_bytes::<32>());
+        assert!(matches!(
+            dual.try_append(modulus),
+            Err(MMRError::LeafOutOfDomain)
+        ));
+        assert_eq!(dual.end(), 21);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::proof::InclusionProof;
+use alloy_primitives::{Keccak256, B256};
+
+/// An MMR closed at an epoch boundary, together with its final root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinalizedEpoch {
+    mmr: MMR,
+    root: B256,
+}
+
+impl FinalizedEpoch {
+    /// Returns the final state of the epoch's MMR
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+
+    /// Returns the final root of the epoch
+    pub fn root(&self) -> B256 {
+        self.root
+    }
+
+    /// Returns the leaf the next epoch starts with, committing to this epoch
+    pub fn chain_leaf(&self) -> B256 {
+        chain_leaf(self.root, self.mmr.end())
+    }
+}
+
+/// Computes the first leaf of an epoch following one that ended at `end` with `root`.
+///
+/// # Arguments
+///
+/// * `root` - The final root of the previous epoch.
+/// * `end` - The final end of the previous epoch.
+///
+/// # Returns
+///
+/// `keccak256(root || end)`, with `end` encoded as 8 big-endian bytes.
+pub fn chain_leaf(root: B256, end: u64) -> B256 {
+    let mut hasher = Keccak256::new();
+    hasher.update(root.as_slice());
+    hasher.update(end.to_be_bytes());
+    hasher.finalize()
+}
+
+impl MMR {
+    /// Finalizes this MMR and starts the next epoch.
+    ///
+    /// The next MMR starts at 0 with the same scheme, `max_end`, strictness and key, and its
+    /// first leaf is the finalized epoch's `chain_leaf`.
+    pub fn rotate(self) -> (FinalizedEpoch, MMR) {
+        let mut next = self.with_peaks(Peaks::empty(0));
+        let epoch = FinalizedEpoch {
+            root: self.get_root(),
+            mmr: self,
+        };
+        next.append(epoch.chain_leaf());
+        (epoch, next)
+    }
+}
+
+/// One step of an [`EpochProof`]: the state of the next epoch and a proof that its first
+/// leaf chains from the previous one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochLink {
+    peaks: Peaks,
+    proof: InclusionProof,
+}
+
+/// Proof that a leaf is included in an earlier epoch of a chain whose latest root is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochProof {
+    peaks: Peaks,
+    proof: InclusionProof,
+    links: Vec<EpochLink>,
+}
+
+impl EpochProof {
+    /// Starts a proof from the leaf's inclusion proof within `epoch`
+    pub fn new(epoch: &FinalizedEpoch, proof: InclusionProof) -> Self {
+        Self {
+            peaks: epoch.mmr.as_peaks().clone(),
+            proof,
+            links: vec![],
+        }
+    }
+
+    /// Extends the proof to the next epoch.
+    ///
+    /// `next` is the state of the next epoch, finalized or current, and `chain_proof` the
+    /// inclusion proof of its first leaf.
+    pub fn push_link(&mut self, next: &MMR, chain_proof: InclusionProof) {
+        self.links.push(EpochLink {
+            peaks: next.as_peaks().clone(),
+            proof: chain_proof,
+        });
+    }
+
+    /// Returns the number of epoch boundaries the proof spans
+    pub fn links(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Verifies that `leaf` is included in the chain ending with `root`
+    pub fn verify(&self, leaf: B256, root: B256) -> bool {
+        let epoch_root = self.peaks.root();
+        if !self.proof.verify_peaks(&self.peaks, leaf, epoch_root) {
+            return false;
+        }
+        let (mut root_so_far, mut end) = (epoch_root, self.peaks.end());
+        for link in &self.links {
+            let next_root = link.peaks.root();
+            if link.proof.leaf_index() != 0
+                || !link
+                    .proof
+                    .verify_peaks(&link.peaks, chain_leaf(root_so_far, end), next_root)
+            {
+                return false;
+            }
+            (root_so_far, end) = (next_root, link.peaks.end());
+        }
+        root_so_far == root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_rotate_chains_root() {
+        let leaves: Vec<B256> = (0..5).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves).with_max_end(16);
+        let (epoch, next) = mmr.clone().rotate();
+        assert_eq!(epoch.root(), mmr.get_root());
+        assert_eq!(epoch.mmr(), &mmr);
+        assert_eq!(next, MMR::from_leaves(&[chain_leaf(mmr.get_root(), 5)]));
+        assert_eq!(next.max_end(), Some(16));
+    }
+
+    #[test]
+    fn test_rotate_keeps_key_and_strictness() {
+        let keyed = MMR::new().with_key(get_random_hash()).with_strict();
+        let mut mmr = keyed.clone();
+        mmr.append_batch(&(0..5).map(|_| get_random_hash()).collect::<Vec<_>>());
+        let (epoch, next) = mmr.rotate();
+        assert!(next.is_keyed() && next.is_strict());
+        assert_eq!(next.scheme(), keyed.scheme());
+
+        let mut expected = keyed;
+        expected.append(epoch.chain_leaf());
+        assert_eq!(next, expected);
+    }
+
+    #[test]
+    fn test_proof_across_epochs() {
+        // Three epochs of 6, 9 and 4 leaves, each after the first starting with a chain leaf.
+        let mut epochs = vec![];
+        let mut epoch_leaves: Vec<Vec<B256>> = vec![];
+        let mut mmr = MMR::new();
+        let mut leaves = vec![];
+        for count in [6, 9, 4] {
+            for _ in 0..count {
+                let leaf = get_random_hash();
+                mmr.append(leaf);
+                leaves.push(leaf);
+            }
+            epoch_leaves.push(leaves);
+            let (epoch, next) = mmr.rotate();
+            leaves = vec![epoch.chain_leaf()];
+            epochs.push(epoch);
+            mmr = next;
+        }
+        let current = epochs.pop().unwrap().mmr().clone();
+
+        let leaf = epoch_leaves[0][3];
+        let mut proof = EpochProof::new(
+            &epochs[0],
+            prove(epochs[0].mmr(), &epoch_leaves[0], 3).unwrap(),
+        );
+        proof.push_link(
+            epochs[1].mmr(),
+            prove(epochs[1].mmr(), &epoch_leaves[1], 0).unwrap(),
+        );
+        assert!(!proof.verify(leaf, current.get_root()));
+        assert!(proof.verify(leaf, epochs[1].root()));
+
+        proof.push_link(&current, prove(&current, &epoch_leaves[2], 0).unwrap());
+        assert_eq!(proof.links(), 2);
+        assert!(proof.verify(leaf, current.get_root()));
+        assert!(!proof.verify(epoch_leaves[0][4], current.get_root()));
+        assert!(!proof.verify(leaf, get_random_hash()));
+    }
+
+    #[test]
+    fn test_link_must_prove_first_leaf() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let (epoch, mut next) = MMR::from_leaves(&leaves).rotate();
+        let mut next_leaves = vec![epoch.chain_leaf()];
+        next_leaves.push(epoch.chain_leaf());
+        next.append(epoch.chain_leaf());
+
+        let mut proof = EpochProof::new(&epoch, prove(epoch.mmr(), &leaves, 2).unwrap());
+        proof.push_link(&next, prove(&next, &next_leaves, 1).unwrap());
+        assert!(!proof.verify(leaves[2], next.get_root()));
+    }
+}
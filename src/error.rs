@@ -1,10 +1,124 @@
-use std::fmt;
+#[cfg(feature = "serde")]
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::fmt;
 
-#[derive(Debug)]
+/// Error returned by MMR operations.
+///
+/// Every variant has a stable numeric `code`, so the error can cross RPC and FFI boundaries
+/// without matching on its message. Codes are never reused or renumbered; new variants take
+/// the next free one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "ErrorRepr", try_from = "ErrorRepr")
+)]
 pub enum MMRError {
-    StartGreaterThanEnd,
-    InvalidNumberOfPeaks,
-    MergeError,
+    StartGreaterThanEnd = 1,
+    InvalidNumberOfPeaks = 2,
+    MergeError = 3,
+    InvalidArity = 4,
+    InvalidHint = 5,
+    LeafIndexOutOfRange = 6,
+    InvalidLeaves = 7,
+    BufferTooSmall = 8,
+    Cancelled = 9,
+    IndexOverflow = 10,
+    UnreservedRange = 11,
+    UnknownScheme = 12,
+    SchemeMismatch = 13,
+    RangeLimitReached = 14,
+    InvalidEncoding = 15,
+    ZeroLeaf = 16,
+    ZeroPeak = 17,
+    InvalidWitness = 18,
+    RootMismatch = 19,
+    BudgetExceeded = 20,
+    StateMismatch = 21,
+    RangeMismatch = 22,
+    TooManyPeaks = 23,
+    PathTooLong = 24,
+    TooManyLeaves = 25,
+    DuplicatePeaks = 26,
+    Diverged = 27,
+    EmptyRange = 28,
+    LeafOutOfDomain = 29,
+}
+
+impl MMRError {
+    /// Returns the stable code of the error
+    pub fn code(&self) -> u16 {
+        *self as u16
+    }
+
+    /// Returns the error with the given code, or `None` if no error has it
+    pub fn from_code(code: u16) -> Option<Self> {
+        let err = match code {
+            1 => MMRError::StartGreaterThanEnd,
+            2 => MMRError::InvalidNumberOfPeaks,
+            3 => MMRError::MergeError,
+            4 => MMRError::InvalidArity,
+            5 => MMRError::InvalidHint,
+            6 => MMRError::LeafIndexOutOfRange,
+            7 => MMRError::InvalidLeaves,
+            8 => MMRError::BufferTooSmall,
+            9 => MMRError::Cancelled,
+            10 => MMRError::IndexOverflow,
+            11 => MMRError::UnreservedRange,
+            12 => MMRError::UnknownScheme,
+            13 => MMRError::SchemeMismatch,
+            14 => MMRError::RangeLimitReached,
+            15 => MMRError::InvalidEncoding,
+            16 => MMRError::ZeroLeaf,
+            17 => MMRError::ZeroPeak,
+            18 => MMRError::InvalidWitness,
+            19 => MMRError::RootMismatch,
+            20 => MMRError::BudgetExceeded,
+            21 => MMRError::StateMismatch,
+            22 => MMRError::RangeMismatch,
+            23 => MMRError::TooManyPeaks,
+            24 => MMRError::PathTooLong,
+            25 => MMRError::TooManyLeaves,
+            26 => MMRError::DuplicatePeaks,
+            27 => MMRError::Diverged,
+            28 => MMRError::EmptyRange,
+            29 => MMRError::LeafOutOfDomain,
+            _ => return None,
+        };
+        Some(err)
+    }
+}
+
+/// Serialized form of `MMRError`: the code, which identifies the error, and the message for
+/// readers that don't know it.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ErrorRepr {
+    code: u16,
+    #[serde(default)]
+    message: String,
+}
+
+#[cfg(feature = "serde")]
+impl From<MMRError> for ErrorRepr {
+    fn from(err: MMRError) -> Self {
+        Self {
+            code: err.code(),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ErrorRepr> for MMRError {
+    type Error = String;
+
+    fn try_from(repr: ErrorRepr) -> Result<Self, Self::Error> {
+        MMRError::from_code(repr.code).ok_or_else(|| format!("unknown error code {}", repr.code))
+    }
 }
 
 impl fmt::Display for MMRError {
@@ -15,8 +129,75 @@ impl fmt::Display for MMRError {
                 write!(f, "Invalid number of peaks for the given range")
             }
             MMRError::MergeError => write!(f, "Error while merging MMRs"),
+            MMRError::InvalidArity => write!(f, "Arity must be at least 2"),
+            MMRError::InvalidHint => write!(f, "Append hint does not match the MMR range"),
+            MMRError::LeafIndexOutOfRange => write!(f, "Leaf index is outside the MMR range"),
+            MMRError::InvalidLeaves => write!(f, "Number of leaves does not match the MMR range"),
+            MMRError::BufferTooSmall => write!(f, "Buffer is too small to hold the proof"),
+            MMRError::Cancelled => write!(f, "Operation was cancelled"),
+            MMRError::IndexOverflow => write!(f, "Leaf index would overflow u64"),
+            MMRError::UnreservedRange => {
+                write!(f, "Range does not match an outstanding reservation")
+            }
+            MMRError::UnknownScheme => write!(f, "Unknown scheme descriptor"),
+            MMRError::SchemeMismatch => write!(f, "MMRs were built with different schemes"),
+            MMRError::RangeLimitReached => write!(f, "MMR has reached its maximum end"),
+            MMRError::InvalidEncoding => write!(f, "Malformed encoding"),
+            MMRError::ZeroLeaf => write!(f, "Zero leaves are rejected in strict mode"),
+            MMRError::ZeroPeak => write!(f, "Zero peaks are rejected in strict mode"),
+            MMRError::InvalidWitness => write!(f, "Witness does not match the old root"),
+            MMRError::RootMismatch => {
+                write!(f, "Claimed root does not match the appended leaves")
+            }
+            MMRError::BudgetExceeded => write!(f, "Verification would exceed the hash budget"),
+            MMRError::StateMismatch => write!(f, "MMR does not match the stored nodes"),
+            MMRError::RangeMismatch => write!(f, "MMRs cover different ranges"),
+            MMRError::TooManyPeaks => write!(f, "Peak count exceeds the decode limit"),
+            MMRError::PathTooLong => write!(f, "Proof path length exceeds the decode limit"),
+            MMRError::TooManyLeaves => write!(f, "Leaf count exceeds the decode limit"),
+            MMRError::DuplicatePeaks => {
+                write!(f, "Adjacent peaks of equal height are identical")
+            }
+            MMRError::Diverged => write!(f, "MMRs are not snapshots of the same log"),
+            MMRError::EmptyRange => write!(f, "Range must not be empty"),
+            MMRError::LeafOutOfDomain => write!(f, "Leaf is outside the hasher's output domain"),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for MMRError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_round_trip() {
+        let errors: Vec<MMRError> = (0..=u16::MAX).filter_map(MMRError::from_code).collect();
+        assert_eq!(errors.len(), 29);
+        for err in errors {
+            assert_eq!(MMRError::from_code(err.code()), Some(err));
+        }
+        assert_eq!(MMRError::from_code(0), None);
+        // Codes are part of the wire format and must not change.
+        assert_eq!(MMRError::StartGreaterThanEnd.code(), 1);
+        assert_eq!(MMRError::Diverged.code(), 27);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_value(MMRError::PathTooLong).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": 24,
+                "message": "Proof path length exceeds the decode limit",
+            })
+        );
+        let err: MMRError = serde_json::from_value(serde_json::json!({ "code": 24 })).unwrap();
+        assert_eq!(err, MMRError::PathTooLong);
+        assert!(serde_json::from_value::<MMRError>(serde_json::json!({ "code": 0 })).is_err());
+    }
+}
@@ -0,0 +1,458 @@
+use crate::full::FullMMR;
+use crate::utils::hash::hash_to_parent;
+use alloy_primitives::{hex, B256};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+const MANIFEST_FILE: &str = "manifest";
+const MANIFEST_TMP_FILE: &str = "manifest.tmp";
+
+const NODES_MAGIC: &[u8; 8] = b"MMRNODE1";
+const NODES_CSV_HEADER: &str = "level,index,hash";
+
+/// Encoding of the node stream written by `FullMMR::export_nodes`.
+///
+/// Both formats list every node as a `(level, index, hash)` triple, where the node is the
+/// root of the `2^level` leaves starting at `index * 2^level`. Nodes come in the order they
+/// are completed: each leaf, followed by the parents it completes, from the lowest up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeFormat {
+    /// The magic `MMRNODE1` and the number of leaves as 8 big-endian bytes, then one
+    /// 41-byte record per node: the level as 1 byte, the index as 8 big-endian bytes and
+    /// the 32-byte hash
+    Binary,
+    /// A `level,index,hash` header line, then one line per node with the hash in
+    /// `0x`-prefixed hex
+    Csv,
+}
+
+/// A complete subtree written by `FullMMR::export_subtrees`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportedSubtree {
+    /// The first leaf covered by the subtree
+    pub start: u64,
+    /// One past the last leaf covered by the subtree
+    pub end: u64,
+    /// The root of the subtree, which also names its blob
+    pub root: B256,
+}
+
+impl ExportedSubtree {
+    /// Returns the height of the subtree
+    pub fn height(&self) -> u32 {
+        (self.end - self.start).trailing_zeros()
+    }
+
+    /// Returns the name of the subtree's blob: its root in lowercase hex, without prefix
+    pub fn file_name(&self) -> String {
+        hex::encode(self.root)
+    }
+
+    /// Checks that `blob` holds the nodes of this subtree
+    pub fn verify_blob(&self, blob: &[u8]) -> bool {
+        let num_leaves = (self.end - self.start) as usize;
+        if blob.len() != 32 * (2 * num_leaves - 1) {
+            return false;
+        }
+        let mut nodes = blob.chunks_exact(32).map(B256::from_slice);
+        let mut level: Vec<B256> = nodes.by_ref().take(num_leaves).collect();
+        while level.len() > 1 {
+            let parents: Vec<B256> = level
+                .chunks_exact(2)
+                .map(|pair| hash_to_parent(&pair[0], &pair[1]))
+                .collect();
+            let stored: Vec<B256> = nodes.by_ref().take(parents.len()).collect();
+            if stored != parents {
+                return false;
+            }
+            level = parents;
+        }
+        level[0] == self.root
+    }
+}
+
+impl FullMMR {
+    /// Exports the MMR as content-addressed subtree blobs plus a manifest, so that proof
+    /// servers can fetch only the subtrees they need from object storage.
+    ///
+    /// The leaves are tiled left to right with the largest complete subtrees of height at
+    /// most `max_height`. Each subtree is written to a blob named after its root, holding
+    /// its nodes level by level from the leaves up to the root. Since the root commits to
+    /// every node, identical subtrees share a blob and a fetched blob can be checked with
+    /// `ExportedSubtree::verify_blob`.
+    ///
+    /// The manifest lists one subtree per line as `start,end,root` in leaf order, and is
+    /// written last through an atomic rename, so it only ever references complete blobs.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to write to, created if needed
+    /// * `max_height` - The height of the largest exported subtree
+    ///
+    /// # Returns
+    ///
+    /// The exported subtrees, in manifest order.
+    pub fn export_subtrees<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        max_height: u32,
+    ) -> io::Result<Vec<ExportedSubtree>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut subtrees = vec![];
+        let mut manifest = String::new();
+        let mut start = 0;
+        while start < self.end() {
+            // The largest aligned subtree starting at `start` that fits.
+            let height = start
+                .trailing_zeros()
+                .min(63 - (self.end() - start).leading_zeros())
+                .min(max_height);
+            let subtree = ExportedSubtree {
+                start,
+                end: start + (1 << height),
+                root: self.node(height, start >> height).unwrap(),
+            };
+
+            let path = dir.join(subtree.file_name());
+            if !path.exists() {
+                let mut blob = Vec::with_capacity(32 * ((2 << height) - 1));
+                for level in 0..=height {
+                    for index in (subtree.start >> level)..(subtree.end >> level) {
+                        blob.extend_from_slice(self.node(level, index).unwrap().as_slice());
+                    }
+                }
+                fs::write(path, blob)?;
+            }
+
+            manifest.push_str(&format!(
+                "{},{},{}\n",
+                subtree.start,
+                subtree.end,
+                subtree.file_name()
+            ));
+            subtrees.push(subtree);
+            start = subtree.end;
+        }
+
+        fs::write(dir.join(MANIFEST_TMP_FILE), manifest)?;
+        fs::rename(dir.join(MANIFEST_TMP_FILE), dir.join(MANIFEST_FILE))?;
+        Ok(subtrees)
+    }
+}
+
+impl FullMMR {
+    /// Streams every node of the MMR to `writer`, so that tools in other languages can
+    /// consume the tree without linking this crate.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The destination, written to record by record
+    /// * `format` - The encoding of the stream, documented on `NodeFormat`
+    ///
+    /// # Returns
+    ///
+    /// The number of nodes written.
+    pub fn export_nodes<W: Write>(&self, mut writer: W, format: NodeFormat) -> io::Result<u64> {
+        match format {
+            NodeFormat::Binary => {
+                writer.write_all(NODES_MAGIC)?;
+                writer.write_all(&self.end().to_be_bytes())?;
+            }
+            NodeFormat::Csv => writeln!(writer, "{}", NODES_CSV_HEADER)?,
+        }
+        let mut written = 0;
+        for leaf_index in 0..self.end() {
+            // The leaf, then the `trailing_zeros(leaf_index + 1)` parents it completes.
+            for level in 0..=(leaf_index + 1).trailing_zeros() {
+                let index = ((leaf_index + 1) >> level) - 1;
+                let hash = self.node(level, index).unwrap();
+                match format {
+                    NodeFormat::Binary => {
+                        writer.write_all(&[level as u8])?;
+                        writer.write_all(&index.to_be_bytes())?;
+                        writer.write_all(hash.as_slice())?;
+                    }
+                    NodeFormat::Csv => writeln!(writer, "{},{},{}", level, index, hash)?,
+                }
+                written += 1;
+            }
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Rebuilds an MMR from a stream written by `export_nodes`.
+    ///
+    /// The leaves are appended in order and every inner node is checked against the one
+    /// recomputed from them, so a stream that is truncated, reordered or inconsistent is
+    /// rejected rather than imported.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The source of the stream
+    /// * `format` - The encoding of the stream
+    ///
+    /// # Returns
+    ///
+    /// The imported MMR, or an `InvalidData` error if the stream is malformed.
+    pub fn import_nodes<R: BufRead>(mut reader: R, format: NodeFormat) -> io::Result<Self> {
+        let mut import = NodeImport::default();
+        match format {
+            NodeFormat::Binary => {
+                let mut header = [0u8; 16];
+                reader.read_exact(&mut header)?;
+                if &header[..8] != NODES_MAGIC {
+                    return Err(invalid_data("not a node stream"));
+                }
+                let end = u64::from_be_bytes(header[8..].try_into().unwrap());
+                let mut record = [0u8; 41];
+                while !reader.fill_buf()?.is_empty() {
+                    reader.read_exact(&mut record)?;
+                    let index = u64::from_be_bytes(record[1..9].try_into().unwrap());
+                    import.push(record[0] as u32, index, B256::from_slice(&record[9..]))?;
+                }
+                if import.full.end() != end {
+                    return Err(invalid_data("leaf count does not match the header"));
+                }
+            }
+            NodeFormat::Csv => {
+                let mut lines = reader.lines();
+                if lines.next().transpose()?.as_deref() != Some(NODES_CSV_HEADER) {
+                    return Err(invalid_data("missing header"));
+                }
+                for line in lines {
+                    let line = line?;
+                    let (level, index, hash) =
+                        parse_node_line(&line).ok_or_else(|| invalid_data("malformed line"))?;
+                    import.push(level, index, hash)?;
+                }
+            }
+        }
+        import.finish()
+    }
+}
+
+/// State of `FullMMR::import_nodes`: the MMR rebuilt so far and the number of parents the
+/// last leaf completed that the stream has yet to list.
+#[derive(Default)]
+struct NodeImport {
+    full: FullMMR,
+    pending_parents: u32,
+}
+
+impl NodeImport {
+    fn push(&mut self, level: u32, index: u64, hash: B256) -> io::Result<()> {
+        let end = self.full.end();
+        if level == 0 {
+            if self.pending_parents != 0 || index != end {
+                return Err(invalid_data("leaf out of order"));
+            }
+            self.full.append(hash);
+            self.pending_parents = (end + 1).trailing_zeros();
+            return Ok(());
+        }
+        let expected_level = (end.trailing_zeros() - self.pending_parents) + 1;
+        if self.pending_parents == 0 || level != expected_level || index != (end >> level) - 1 {
+            return Err(invalid_data("inner node out of order"));
+        }
+        if self.full.node(level, index) != Some(hash) {
+            return Err(invalid_data("inner node does not match its leaves"));
+        }
+        self.pending_parents -= 1;
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<FullMMR> {
+        if self.pending_parents != 0 {
+            return Err(invalid_data("stream ends before the last inner nodes"));
+        }
+        Ok(self.full)
+    }
+}
+
+fn parse_node_line(line: &str) -> Option<(u32, u64, B256)> {
+    let mut fields = line.split(',');
+    let level = fields.next()?.parse().ok()?;
+    let index = fields.next()?.parse().ok()?;
+    let hash = fields.next()?.parse().ok()?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some((level, index, hash))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parses a manifest written by `FullMMR::export_subtrees`.
+///
+/// # Arguments
+///
+/// * `manifest` - The contents of the manifest
+///
+/// # Returns
+///
+/// The listed subtrees, or `None` if a line is malformed or they don't tile `[0, end)`.
+pub fn parse_manifest(manifest: &str) -> Option<Vec<ExportedSubtree>> {
+    let mut subtrees: Vec<ExportedSubtree> = vec![];
+    for line in manifest.lines() {
+        let mut fields = line.split(',');
+        let start = fields.next()?.parse().ok()?;
+        let end: u64 = fields.next()?.parse().ok()?;
+        let root = fields.next()?.parse().ok()?;
+        if fields.next().is_some()
+            || start != subtrees.last().map_or(0, |subtree| subtree.end)
+            || end <= start
+            || !(end - start).is_power_of_two()
+            || start % (end - start) != 0
+        {
+            return None;
+        }
+        subtrees.push(ExportedSubtree { start, end, root });
+    }
+    Some(subtrees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::{get_random_hash, hash_subtree};
+    use std::path::PathBuf;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("rust-mmr-export-{}", get_random_hash()))
+    }
+
+    #[test]
+    fn test_export_subtrees() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        let dir = temp_dir();
+        let subtrees = full.export_subtrees(&dir, 3).unwrap();
+
+        // Tiles of 8 leaves, then the peaks of the remaining 7.
+        let ranges: Vec<(u64, u64)> = subtrees.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(ranges, vec![(0, 8), (8, 16), (16, 20), (20, 22), (22, 23)]);
+        for subtree in &subtrees {
+            let range = subtree.start as usize..subtree.end as usize;
+            assert_eq!(subtree.root, hash_subtree(&leaves[range]));
+            let blob = fs::read(dir.join(subtree.file_name())).unwrap();
+            assert!(subtree.verify_blob(&blob));
+        }
+
+        let manifest = fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(parse_manifest(&manifest), Some(subtrees));
+        assert!(!dir.join(MANIFEST_TMP_FILE).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_identical_subtrees_share_a_blob() {
+        let leaf = get_random_hash();
+        let full = FullMMR::from_leaves(&[leaf; 8]);
+        let dir = temp_dir();
+        let subtrees = full.export_subtrees(&dir, 1).unwrap();
+        assert_eq!(subtrees.len(), 4);
+        // Four identical subtrees and the manifest.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_blob_rejects_tampering() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        let dir = temp_dir();
+        let subtree = full.export_subtrees(&dir, 2).unwrap()[0];
+        let mut blob = fs::read(dir.join(subtree.file_name())).unwrap();
+        assert!(subtree.verify_blob(&blob));
+        // An inner node that doesn't match its children.
+        blob[4 * 32] ^= 1;
+        assert!(!subtree.verify_blob(&blob));
+        assert!(!subtree.verify_blob(&blob[32..]));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_nodes_round_trip() {
+        for num_leaves in [0u64, 1, 2, 7, 8, 23] {
+            let leaves: Vec<B256> = (0..num_leaves).map(|_| get_random_hash()).collect();
+            let full = FullMMR::from_leaves(&leaves);
+            for format in [NodeFormat::Binary, NodeFormat::Csv] {
+                let mut stream = vec![];
+                let written = full.export_nodes(&mut stream, format).unwrap();
+                // A zero-starting MMR of n leaves has 2n - popcount(n) nodes.
+                assert_eq!(written, 2 * num_leaves - num_leaves.count_ones() as u64);
+                let imported = FullMMR::import_nodes(&stream[..], format).unwrap();
+                assert_eq!(imported, full);
+            }
+        }
+    }
+
+    #[test]
+    fn test_export_nodes_layout() {
+        let leaves: Vec<B256> = (0..3).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        let mut stream = vec![];
+        full.export_nodes(&mut stream, NodeFormat::Csv).unwrap();
+        let expected = format!(
+            "level,index,hash\n0,0,{}\n0,1,{}\n1,0,{}\n0,2,{}\n",
+            leaves[0],
+            leaves[1],
+            hash_to_parent(&leaves[0], &leaves[1]),
+            leaves[2]
+        );
+        assert_eq!(String::from_utf8(stream).unwrap(), expected);
+
+        let mut stream = vec![];
+        full.export_nodes(&mut stream, NodeFormat::Binary).unwrap();
+        assert_eq!(stream.len(), 16 + 4 * 41);
+        assert_eq!(&stream[..16], b"MMRNODE1\0\0\0\0\0\0\0\x03");
+    }
+
+    #[test]
+    fn test_import_nodes_rejects_invalid_streams() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        let mut stream = vec![];
+        full.export_nodes(&mut stream, NodeFormat::Binary).unwrap();
+        let import = |stream: &[u8]| FullMMR::import_nodes(stream, NodeFormat::Binary);
+
+        // Truncated, both mid-record and before the last inner nodes.
+        assert!(import(&stream[..stream.len() - 1]).is_err());
+        assert!(import(&stream[..stream.len() - 41]).is_err());
+        // A tampered inner node.
+        let mut tampered = stream.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(import(&tampered).is_err());
+        // Swapped records.
+        let mut reordered = stream.clone();
+        reordered[16..16 + 2 * 41].rotate_left(41);
+        assert!(import(&reordered).is_err());
+        // A header claiming more leaves.
+        let mut header = stream.clone();
+        header[15] = 5;
+        assert!(import(&header).is_err());
+
+        let mut csv = vec![];
+        full.export_nodes(&mut csv, NodeFormat::Csv).unwrap();
+        assert!(FullMMR::import_nodes(&csv[17..], NodeFormat::Csv).is_err());
+        let csv = String::from_utf8(csv).unwrap().replace("1,1,", "1,2,");
+        assert!(FullMMR::import_nodes(csv.as_bytes(), NodeFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn test_parse_manifest_rejects_gaps() {
+        let root = B256::ZERO;
+        assert_eq!(parse_manifest(""), Some(vec![]));
+        assert!(parse_manifest(&format!("0,4,{}\n4,6,{}\n", root, root)).is_some());
+        assert!(parse_manifest(&format!("0,4,{}\n5,6,{}\n", root, root)).is_none());
+        assert!(parse_manifest(&format!("0,3,{}\n", root)).is_none());
+        assert!(parse_manifest(&format!("0,4,{},extra\n", root)).is_none());
+    }
+}
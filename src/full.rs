@@ -0,0 +1,419 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::proof::InclusionProof;
+use crate::range_proof::RangeProof;
+use crate::utils::range::get_peak_heights;
+use alloy_primitives::B256;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// A zero-starting MMR that stores every node, alongside the stateless `MMR` it tracks.
+///
+/// `nodes[level][index]` is the root of the perfect subtree of `2^level` leaves starting at
+/// leaf `index * 2^level`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FullMMR {
+    mmr: MMR,
+    nodes: Vec<Vec<B256>>,
+}
+
+impl FullMMR {
+    /// Creates a new empty MMR
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_leaves(leaves: &[B256]) -> Self {
+        let mut mmr = Self::new();
+        for leaf in leaves {
+            mmr.append(*leaf);
+        }
+        mmr
+    }
+
+    /// Hashes parents with `key`, like `MMR::with_key`. Set the key before appending.
+    pub fn with_key(mut self, key: B256) -> Self {
+        self.mmr = self.mmr.with_key(key);
+        self
+    }
+
+    /// Appends a leaf, storing every subtree root it completes
+    pub fn append(&mut self, element: B256) {
+        self.mmr.append(element);
+        let mut node = element;
+        let mut level = 0;
+        loop {
+            if self.nodes.len() == level {
+                self.nodes.push(vec![]);
+            }
+            self.nodes[level].push(node);
+            let len = self.nodes[level].len();
+            if len % 2 == 1 {
+                break;
+            }
+            node = self.mmr.hash_to_parent(&self.nodes[level][len - 2], &node);
+            level += 1;
+        }
+    }
+
+    /// Returns the stateless view of the MMR
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+
+    /// Returns the number of leaves
+    pub fn end(&self) -> u64 {
+        self.mmr.end()
+    }
+
+    pub fn get_root(&self) -> B256 {
+        self.mmr.get_root()
+    }
+
+    /// Returns the leaves
+    pub fn leaves(&self) -> &[B256] {
+        self.nodes.first().map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the root of the perfect subtree at `(level, index)`, if it is complete
+    pub fn node(&self, level: u32, index: u64) -> Option<B256> {
+        self.nodes.get(level as usize)?.get(index as usize).copied()
+    }
+
+    /// Generates an inclusion proof from the stored nodes, without rehashing
+    pub fn prove(&self, leaf_index: u64) -> Result<InclusionProof, MMRError> {
+        if leaf_index >= self.end() {
+            return Err(MMRError::LeafIndexOutOfRange);
+        }
+        // The leaf's peak is the highest complete subtree containing it.
+        let path = (0..)
+            .map_while(|level| {
+                self.node(level, (leaf_index >> level) ^ 1)
+                    .filter(|_| self.node(level + 1, leaf_index >> (level + 1)).is_some())
+            })
+            .collect();
+        Ok(InclusionProof::new(leaf_index, path))
+    }
+
+    /// Returns the minimal set of complete subtrees exactly covering `[range_start, range_end)`.
+    ///
+    /// The subtrees are the peaks an MMR over the subrange would have, listed as
+    /// `(start, end, root)` in leaf order, which makes them the building block of range
+    /// proofs and of syncing a subrange.
+    pub fn cover(
+        &self,
+        range_start: u64,
+        range_end: u64,
+    ) -> Result<Vec<(u64, u64, B256)>, MMRError> {
+        if range_start > range_end {
+            return Err(MMRError::StartGreaterThanEnd);
+        }
+        if range_end > self.end() {
+            return Err(MMRError::LeafIndexOutOfRange);
+        }
+        let mut start = range_start;
+        Ok(get_peak_heights(range_start, range_end)
+            .into_iter()
+            .map(|height| {
+                let end = start + (1 << height);
+                let subtree = (start, end, self.node(height, start >> height).unwrap());
+                start = end;
+                subtree
+            })
+            .collect())
+    }
+
+    /// Returns the stateless MMR over the leaves `[range_start, range_end)`, built from the
+    /// stored nodes without rehashing.
+    ///
+    /// This is the accumulator a verifier following only that window, e.g. one epoch,
+    /// would hold after appending its leaves.
+    pub fn window(&self, range_start: u64, range_end: u64) -> Result<MMR, MMRError> {
+        let peaks = self
+            .cover(range_start, range_end)?
+            .into_iter()
+            .map(|(_, _, root)| root)
+            .collect();
+        Ok(self
+            .mmr
+            .with_peaks(Peaks::new(range_start, range_end, peaks)?))
+    }
+
+    /// Returns the witness `MMR::split` needs to split the MMR at the leaf `at`: the peaks of
+    /// `[0, at)` followed by those of `[at, end)`, read from the stored nodes
+    pub fn split_witness(&self, at: u64) -> Result<Vec<B256>, MMRError> {
+        let mut witness = self.window(0, at)?.peaks().to_vec();
+        witness.extend_from_slice(self.window(at, self.end())?.peaks());
+        Ok(witness)
+    }
+
+    /// Generates a proof for the leaves `[range_start, range_end)` from the stored nodes,
+    /// without rehashing
+    pub fn prove_range(&self, range_start: u64, range_end: u64) -> Result<RangeProof, MMRError> {
+        if range_start > range_end {
+            return Err(MMRError::StartGreaterThanEnd);
+        }
+        if range_end > self.end() {
+            return Err(MMRError::LeafIndexOutOfRange);
+        }
+        let roots =
+            |cover: Vec<(u64, u64, B256)>| cover.into_iter().map(|(_, _, root)| root).collect();
+        let right = self.cover(range_end, self.end())?;
+        let left = self.cover(0, range_start)?;
+        Ok(RangeProof::new(range_start, roots(left), roots(right)))
+    }
+
+    /// Checks that `mmr` has the range and peaks derived from the stored nodes.
+    ///
+    /// Meant to be run at startup, to detect a stateless accumulator that diverged from the
+    /// stored nodes. `mmr` may start past zero, but must end where the stored nodes do.
+    ///
+    /// # Returns
+    ///
+    /// `MMRError::StateMismatch` if the ranges or any peak differ.
+    pub fn assert_matches(&self, mmr: &MMR) -> Result<(), MMRError> {
+        if mmr.end() != self.end() {
+            return Err(MMRError::StateMismatch);
+        }
+        let peaks = self.cover(mmr.start(), mmr.end())?;
+        if !peaks.iter().map(|(_, _, root)| root).eq(mmr.peaks().iter()) {
+            return Err(MMRError::StateMismatch);
+        }
+        Ok(())
+    }
+
+    /// Renders the nodes covering any leaf in `range` as a Graphviz DOT graph.
+    ///
+    /// Nodes are labelled with their `(level, index)` position and a truncated hash, and
+    /// peaks are drawn bold. Leaves outside the MMR are ignored.
+    pub fn to_dot(&self, range: Range<u64>) -> String {
+        let range = range.start..range.end.min(self.end());
+        let levels = if range.is_empty() {
+            0
+        } else {
+            self.nodes.len()
+        };
+        let mut dot = String::from(
+            "digraph mmr {\n    rankdir=BT;\n    node [shape=box, fontname=monospace];\n",
+        );
+        for (level, nodes) in self.nodes.iter().enumerate().take(levels) {
+            let first = range.start >> level;
+            let last = range.end.saturating_sub(1) >> level;
+            for index in first..=last.min(nodes.len() as u64 - 1) {
+                let hash = nodes[index as usize].to_string();
+                let is_peak = self.node(level as u32 + 1, index >> 1).is_none();
+                let _ = writeln!(
+                    dot,
+                    "    n{}_{} [label=\"({}, {})\\n{}…{}\"{}];",
+                    level,
+                    index,
+                    level,
+                    index,
+                    &hash[..8],
+                    &hash[hash.len() - 4..],
+                    if is_peak { ", style=bold" } else { "" },
+                );
+                if level > 0 {
+                    for child in [index << 1, (index << 1) | 1] {
+                        let _ =
+                            writeln!(dot, "    n{}_{} -> n{}_{};", level - 1, child, level, index);
+                    }
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove;
+    use crate::range_proof::prove_range;
+    use crate::utils::hash::{get_random_hash, hash_subtree};
+
+    #[test]
+    fn test_nodes_match_subtrees() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        assert_eq!(full.mmr(), &MMR::from_leaves(&leaves));
+        assert_eq!(full.leaves(), &leaves[..]);
+        assert_eq!(full.node(2, 1), Some(hash_subtree(&leaves[4..8])));
+        assert_eq!(full.node(3, 0), Some(hash_subtree(&leaves[..8])));
+        assert_eq!(full.node(3, 1), None);
+        assert_eq!(full.node(1, 6), None);
+    }
+
+    #[test]
+    fn test_prove_matches_stateless() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        for index in 0..23 {
+            let proof = full.prove(index).unwrap();
+            assert_eq!(proof, prove(full.mmr(), &leaves, index).unwrap());
+            assert!(proof.verify(full.mmr(), leaves[index as usize]));
+        }
+        assert!(matches!(full.prove(23), Err(MMRError::LeafIndexOutOfRange)));
+    }
+
+    #[test]
+    fn test_cover() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        for range_start in 0..=23 {
+            for range_end in range_start..=23 {
+                let cover = full.cover(range_start, range_end).unwrap();
+                // The cover tiles the range with its subtrees, which are the peaks of the
+                // stateless MMR over it.
+                let mut mmr = MMR::from_params(range_start, range_start, vec![]).unwrap();
+                for leaf in &leaves[range_start as usize..range_end as usize] {
+                    mmr.append(*leaf);
+                }
+                let roots: Vec<B256> = cover.iter().map(|(_, _, root)| *root).collect();
+                assert_eq!(roots, mmr.peaks());
+                let mut start = range_start;
+                for (subtree_start, end, root) in cover {
+                    assert_eq!(subtree_start, start);
+                    assert_eq!(root, hash_subtree(&leaves[start as usize..end as usize]));
+                    start = end;
+                }
+                assert_eq!(start, range_end);
+            }
+        }
+        assert!(matches!(
+            full.cover(5, 4),
+            Err(MMRError::StartGreaterThanEnd)
+        ));
+        assert!(matches!(
+            full.cover(20, 24),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_window() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        for range_start in 0..=23 {
+            for range_end in range_start..=23 {
+                let mut expected = MMR::from_params(range_start, range_start, vec![]).unwrap();
+                expected.append_batch(&leaves[range_start as usize..range_end as usize]);
+                assert_eq!(full.window(range_start, range_end).unwrap(), expected);
+            }
+        }
+        assert_eq!(full.window(0, 23).unwrap(), *full.mmr());
+        assert!(matches!(
+            full.window(20, 24),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_split_witness() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        for at in 0..=23 {
+            let witness = full.split_witness(at).unwrap();
+            let (left, right) = full.mmr().split(at, &witness).unwrap();
+            assert_eq!(left, full.window(0, at).unwrap());
+            assert_eq!(right, full.window(at, 23).unwrap());
+        }
+        assert!(matches!(
+            full.split_witness(24),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_prove_range_matches_stateless() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        for range_start in 0..=23 {
+            for range_end in range_start..=23 {
+                let proof = full.prove_range(range_start, range_end).unwrap();
+                assert_eq!(
+                    proof,
+                    prove_range(full.mmr(), &leaves, range_start, range_end).unwrap()
+                );
+            }
+        }
+        assert!(matches!(
+            full.prove_range(5, 4),
+            Err(MMRError::StartGreaterThanEnd)
+        ));
+        assert!(matches!(
+            full.prove_range(20, 24),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_assert_matches() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        full.assert_matches(&MMR::from_leaves(&leaves)).unwrap();
+        let cover = full.cover(5, 13).unwrap();
+        let peaks = cover.iter().map(|(_, _, root)| *root).collect();
+        full.assert_matches(&MMR::from_params(5, 13, peaks).unwrap())
+            .unwrap();
+
+        // A lagging accumulator, and one with a diverged leaf.
+        assert!(matches!(
+            full.assert_matches(&MMR::from_leaves(&leaves[..12])),
+            Err(MMRError::StateMismatch)
+        ));
+        let mut diverged = leaves.clone();
+        diverged[7] = get_random_hash();
+        assert!(matches!(
+            full.assert_matches(&MMR::from_leaves(&diverged)),
+            Err(MMRError::StateMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_keyed() {
+        let key = get_random_hash();
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mut full = FullMMR::new().with_key(key);
+        for leaf in &leaves {
+            full.append(*leaf);
+        }
+        let mut mmr = MMR::new().with_key(key);
+        mmr.append_batch(&leaves);
+        assert_eq!(full.mmr(), &mmr);
+        full.assert_matches(&mmr).unwrap();
+
+        for index in 0..13 {
+            assert!(full
+                .prove(index)
+                .unwrap()
+                .verify(&mmr, leaves[index as usize]));
+        }
+        let proof = full.prove_range(3, 9).unwrap();
+        assert!(proof.verify(&mmr, &leaves[3..9]));
+
+        // Windows keep the key, so they merge back into the MMR.
+        let witness = full.split_witness(5).unwrap();
+        let (left, right) = mmr.split(5, &witness).unwrap();
+        assert_eq!(full.window(0, 5).unwrap(), left);
+        assert_eq!(left.merge(&full.window(5, 13).unwrap()).unwrap(), mmr);
+        assert_eq!(right, full.window(5, 13).unwrap());
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let leaves: Vec<B256> = (0..6).map(|_| get_random_hash()).collect();
+        let full = FullMMR::from_leaves(&leaves);
+        let dot = full.to_dot(3..5);
+        // Leaves 3 and 4, their parents (1, 1) and (1, 2), and the peak (2, 0).
+        assert_eq!(dot.matches("[label=").count(), 5);
+        assert!(dot.contains("n2_0 [label=\"(2, 0)"));
+        assert!(dot.contains("n1_1 -> n2_0;"));
+        assert!(dot.contains("n0_4 -> n1_2;"));
+        // (1, 2) is the second peak, (2, 0) the first.
+        assert_eq!(dot.matches("style=bold").count(), 2);
+        assert!(full.to_dot(6..9).ends_with("fontname=monospace];\n}\n"));
+    }
+}
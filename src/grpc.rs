@@ -0,0 +1,278 @@
+use crate::error::MMRError;
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use crate::store::{append_leaves, prove, prove_consistency, NodeStore, StoreError};
+use alloy_primitives::B256;
+use proto::mmr_server::{Mmr, MmrServer};
+use proto::{
+    AppendRequest, ConsistencyResponse, GetConsistencyRequest, GetProofRequest, GetRootRequest,
+    ProofResponse, RootResponse, WatchRootsRequest,
+};
+use std::pin::Pin;
+use tokio::sync::{watch, Mutex};
+use tokio_stream::wrappers::WatchStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+/// Messages, client and server generated from `proto/mmr.proto`.
+pub mod proto {
+    tonic::include_proto!("mmr.v1");
+}
+
+/// Metadata key of the `MMRError` or `StoreError` code carried by failed calls.
+pub const ERROR_CODE: &str = "mmr-error-code";
+
+/// gRPC service appending to an MMR whose nodes live in a [`NodeStore`], defined by the
+/// `mmr.v1.Mmr` service in `proto/mmr.proto`.
+///
+/// * `Append` - Appends 32-byte leaves, writing the nodes they complete to the store.
+/// * `GetRoot` - Returns the current end and root.
+/// * `GetProof` - Returns the inclusion proof of a leaf against the current root.
+/// * `GetConsistency` - Returns the peaks proving the current MMR extends an older one.
+/// * `WatchRoots` - Streams the current root, then the root after every append.
+///
+/// Appends are serialized. Reads run against a snapshot of the MMR, so they don't wait
+/// for appends to be stored. Failed calls carry the code of the error behind them under
+/// [`ERROR_CODE`].
+#[derive(Debug)]
+pub struct MmrService<S> {
+    store: S,
+    /// The MMR appended to, ahead of `committed` while an append is being stored
+    mmr: Mutex<MMR>,
+    committed: watch::Sender<MMR>,
+    limits: DecodeLimits,
+}
+
+impl<S: NodeStore + Send + 'static> MmrService<S> {
+    /// Creates a service appending to `mmr`, which must have all its nodes in `store`.
+    ///
+    /// Returns `MMRError::RangeMismatch` if `mmr` doesn't start at 0, which `GetConsistency`
+    /// needs.
+    pub fn new(store: S, mmr: MMR) -> Result<Self, MMRError> {
+        if mmr.start() != 0 {
+            return Err(MMRError::RangeMismatch);
+        }
+        let (committed, _) = watch::channel(mmr.clone());
+        Ok(Self {
+            store,
+            mmr: Mutex::new(mmr),
+            committed,
+            limits: DecodeLimits::DEFAULT,
+        })
+    }
+
+    /// Sets the limits enforced on the leaves of `Append` requests
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Wraps the service for `tonic::transport::Server::add_service`
+    pub fn into_server(self) -> MmrServer<Self> {
+        MmrServer::new(self)
+    }
+
+    /// Returns a copy of the MMR as of the last completed append
+    pub fn snapshot(&self) -> MMR {
+        self.committed.borrow().clone()
+    }
+}
+
+type RootStream = Pin<Box<dyn Stream<Item = Result<RootResponse, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl<S: NodeStore + Send + 'static> Mmr for MmrService<S> {
+    type WatchRootsStream = RootStream;
+
+    async fn append(
+        &self,
+        request: Request<AppendRequest>,
+    ) -> Result<Response<RootResponse>, Status> {
+        let leaves = request.into_inner().leaves;
+        self.limits
+            .check_leaves(leaves.len())
+            .map_err(|err| status(err.into()))?;
+        let leaves = leaves
+            .iter()
+            .map(|leaf| B256::try_from(leaf.as_slice()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Status::invalid_argument("Leaves must be 32 bytes"))?;
+        let mut mmr = self.mmr.lock().await;
+        append_leaves(&self.store, &mut mmr, &leaves)
+            .await
+            .map_err(status)?;
+        self.committed.send_replace(mmr.clone());
+        Ok(Response::new(root_response(&mmr)))
+    }
+
+    async fn get_root(
+        &self,
+        _request: Request<GetRootRequest>,
+    ) -> Result<Response<RootResponse>, Status> {
+        Ok(Response::new(root_response(&self.committed.borrow())))
+    }
+
+    async fn get_proof(
+        &self,
+        request: Request<GetProofRequest>,
+    ) -> Result<Response<ProofResponse>, Status> {
+        let leaf_index = request.into_inner().leaf_index;
+        let mmr = self.snapshot();
+        let proof = prove(&self.store, &mmr, leaf_index).await.map_err(status)?;
+        Ok(Response::new(ProofResponse {
+            leaf_index,
+            end: mmr.end(),
+            root: mmr.get_root().to_vec(),
+            path: proof.path().map(|node| node.to_vec()).collect(),
+        }))
+    }
+
+    async fn get_consistency(
+        &self,
+        request: Request<GetConsistencyRequest>,
+    ) -> Result<Response<ConsistencyResponse>, Status> {
+        let old_end = request.into_inner().old_end;
+        let mmr = self.snapshot();
+        let proof = prove_consistency(&self.store, &mmr, old_end)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(ConsistencyResponse {
+            old_end,
+            new_end: mmr.end(),
+            new_root: mmr.get_root().to_vec(),
+            peaks: proof.peaks().iter().map(|peak| peak.to_vec()).collect(),
+        }))
+    }
+
+    async fn watch_roots(
+        &self,
+        _request: Request<WatchRootsRequest>,
+    ) -> Result<Response<Self::WatchRootsStream>, Status> {
+        let roots = WatchStream::new(self.committed.subscribe())
+            .map(|mmr| root_response(&mmr))
+            .map(Ok);
+        Ok(Response::new(Box::pin(roots)))
+    }
+}
+
+fn root_response(mmr: &MMR) -> RootResponse {
+    RootResponse {
+        end: mmr.end(),
+        root: mmr.get_root().to_vec(),
+    }
+}
+
+/// Maps a failed operation to a status carrying its code under [`ERROR_CODE`]
+fn status(err: StoreError) -> Status {
+    let mut status = match &err {
+        StoreError::Mmr(MMRError::LeafIndexOutOfRange | MMRError::StartGreaterThanEnd) => {
+            Status::out_of_range(err.to_string())
+        }
+        StoreError::Mmr(_) => Status::invalid_argument(err.to_string()),
+        _ => Status::internal(err.to_string()),
+    };
+    status.metadata_mut().insert(ERROR_CODE, err.code().into());
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consistency::ConsistencyProof;
+    use crate::proof::InclusionProof;
+    use crate::store::MemoryStore;
+    use crate::utils::hash::get_random_hash;
+    use tonic::Code;
+
+    async fn append(service: &MmrService<MemoryStore>, leaves: &[B256]) -> RootResponse {
+        let leaves = leaves.iter().map(|leaf| leaf.to_vec()).collect();
+        service
+            .append(Request::new(AppendRequest { leaves }))
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_append_and_prove() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let service = MmrService::new(MemoryStore::new(), MMR::new()).unwrap();
+        append(&service, &leaves[..5]).await;
+        let root = append(&service, &leaves[5..]).await;
+
+        let mmr = MMR::from_leaves(&leaves);
+        assert_eq!(root.end, 13);
+        assert_eq!(root.root, mmr.get_root().to_vec());
+        let current = service.get_root(Request::new(GetRootRequest {})).await;
+        assert_eq!(current.unwrap().into_inner(), root);
+
+        let proof = service
+            .get_proof(Request::new(GetProofRequest { leaf_index: 6 }))
+            .await
+            .unwrap()
+            .into_inner();
+        let path: Vec<B256> = proof
+            .path
+            .iter()
+            .map(|node| B256::from_slice(node))
+            .collect();
+        assert!(InclusionProof::new(6, path).verify(&mmr, leaves[6]));
+
+        let consistency = service
+            .get_consistency(Request::new(GetConsistencyRequest { old_end: 5 }))
+            .await
+            .unwrap()
+            .into_inner();
+        let peaks = consistency.peaks.iter().map(|peak| B256::from_slice(peak));
+        let proof = ConsistencyProof::new(5, consistency.new_end, peaks.collect());
+        assert!(proof.verify(&MMR::from_leaves(&leaves[..5]), mmr.get_root()));
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let service = MmrService::new(MemoryStore::new(), MMR::new()).unwrap();
+        let short = service
+            .append(Request::new(AppendRequest {
+                leaves: vec![vec![1; 31]],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(short.code(), Code::InvalidArgument);
+
+        let missing = service
+            .get_proof(Request::new(GetProofRequest { leaf_index: 0 }))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::OutOfRange);
+        let code = MMRError::LeafIndexOutOfRange.code().to_string();
+        let carried = missing.metadata().get(ERROR_CODE).unwrap();
+        assert_eq!(carried.to_str().unwrap(), code);
+
+        let ahead = service
+            .get_consistency(Request::new(GetConsistencyRequest { old_end: 1 }))
+            .await
+            .unwrap_err();
+        assert_eq!(ahead.code(), Code::OutOfRange);
+
+        let shifted = MMR::from_params(1, 1, vec![]).unwrap();
+        assert!(matches!(
+            MmrService::new(MemoryStore::new(), shifted),
+            Err(MMRError::RangeMismatch)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_watch_roots() {
+        let leaves: Vec<B256> = (0..3).map(|_| get_random_hash()).collect();
+        let service = MmrService::new(MemoryStore::new(), MMR::new()).unwrap();
+        let mut roots = service
+            .watch_roots(Request::new(WatchRootsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(roots.next().await.unwrap().unwrap().end, 0);
+
+        let root = append(&service, &leaves).await;
+        assert_eq!(roots.next().await.unwrap().unwrap(), root);
+    }
+}
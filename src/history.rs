@@ -0,0 +1,269 @@
+use crate::consistency::{ConsistencyProof, PeakIndex};
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::proof::InclusionProof;
+use alloy_primitives::B256;
+use std::collections::{HashMap, VecDeque};
+
+/// Ring buffer of the most recent `(end, root)` pairs of an MMR.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RootHistory {
+    capacity: usize,
+    order: VecDeque<u64>,
+    roots: HashMap<u64, B256>,
+}
+
+impl RootHistory {
+    /// Creates a history retaining up to `capacity` roots
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            roots: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Records the root at `end`, evicting the oldest entry when full
+    pub fn record(&mut self, end: u64, root: B256) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.roots.insert(end, root).is_some() {
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.roots.remove(&oldest);
+            }
+        }
+        self.order.push_back(end);
+    }
+
+    /// Returns the root the MMR had when it ended at `end`, if still retained
+    pub fn root_at_end(&self, end: u64) -> Option<B256> {
+        self.roots.get(&end).copied()
+    }
+
+    /// Verifies an inclusion proof against a retained historical root.
+    ///
+    /// The root at `end_hint` is tried first, then every other retained root from newest to
+    /// oldest, since clients often hold proofs generated a few appends ago.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The inclusion proof of `leaf`.
+    /// * `start` - The start of the MMR the proof was generated against.
+    /// * `peaks` - The peaks of the MMR the proof was generated against.
+    /// * `leaf` - The proven leaf.
+    /// * `end_hint` - The end the client believes the proof was generated at.
+    ///
+    /// # Returns
+    ///
+    /// The end whose root the proof matched, or `None` if it matched no retained root.
+    pub fn verify<P: AsRef<[B256]>>(
+        &self,
+        proof: &InclusionProof<P>,
+        start: u64,
+        peaks: &[B256],
+        leaf: B256,
+        end_hint: u64,
+    ) -> Option<u64> {
+        std::iter::once(end_hint)
+            .chain(
+                self.order
+                    .iter()
+                    .rev()
+                    .copied()
+                    .filter(|end| *end != end_hint),
+            )
+            .find(|end| {
+                self.root_at_end(*end)
+                    .is_some_and(|root| proof.verify_root(start, *end, peaks, leaf, root))
+            })
+    }
+
+    /// Returns the retained `(end, root)` pairs, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (u64, B256)> + '_ {
+        self.order.iter().map(|end| (*end, self.roots[end]))
+    }
+
+    /// Returns the number of retained roots
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns true if no roots are retained
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+/// An MMR that records its root in a [`RootHistory`] after every append and merge, and
+/// optionally its checkpoints in a [`PeakIndex`] after every append.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoricalMMR {
+    mmr: MMR,
+    history: RootHistory,
+    peak_index: Option<PeakIndex>,
+}
+
+impl HistoricalMMR {
+    /// Wraps `mmr`, retaining up to `capacity` roots including the current one
+    pub fn new(mmr: MMR, capacity: usize) -> Self {
+        let mut history = RootHistory::new(capacity);
+        history.record(mmr.end(), mmr.get_root());
+        Self {
+            mmr,
+            history,
+            peak_index: None,
+        }
+    }
+
+    /// Maintains `peak_index` on every subsequent append
+    pub fn with_peak_index(mut self, peak_index: PeakIndex) -> Self {
+        self.peak_index = Some(peak_index);
+        self
+    }
+
+    pub fn append(&mut self, element: B256) {
+        if let Some(peak_index) = &mut self.peak_index {
+            let trace = self.mmr.append_traced(element);
+            peak_index.record(&self.mmr, &trace);
+        } else {
+            self.mmr.append(element);
+        }
+        self.history.record(self.mmr.end(), self.mmr.get_root());
+    }
+
+    /// Merges a bordering MMR into this one.
+    ///
+    /// The peak index is not updated, since a merge doesn't reveal the subtrees it
+    /// completes: consistency proofs can't span ends reached by merging.
+    pub fn merge(&mut self, other: &MMR) -> Result<(), MMRError> {
+        self.mmr = self.mmr.merge(other)?;
+        self.history.record(self.mmr.end(), self.mmr.get_root());
+        Ok(())
+    }
+
+    /// Returns the current MMR
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+
+    /// Returns the recorded roots
+    pub fn history(&self) -> &RootHistory {
+        &self.history
+    }
+
+    /// Returns the root the MMR had when it ended at `end`, if still retained
+    pub fn root_at_end(&self, end: u64) -> Option<B256> {
+        self.history.root_at_end(end)
+    }
+
+    /// Returns the peak index, if one is maintained
+    pub fn peak_index(&self) -> Option<&PeakIndex> {
+        self.peak_index.as_ref()
+    }
+
+    /// Generates a proof that the MMR as of `new_end` extends the one as of `old_end`, from
+    /// the peak index
+    pub fn prove_consistency(&self, old_end: u64, new_end: u64) -> Option<ConsistencyProof> {
+        self.peak_index
+            .as_ref()?
+            .prove_consistency(old_end, new_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_root_at_end() {
+        let leaves: Vec<B256> = (0..10).map(|_| get_random_hash()).collect();
+        let mut mmr = HistoricalMMR::new(MMR::new(), 4);
+        for leaf in &leaves {
+            mmr.append(*leaf);
+        }
+        for end in 7..=10 {
+            assert_eq!(
+                mmr.root_at_end(end),
+                Some(MMR::from_leaves(&leaves[..end as usize]).get_root())
+            );
+        }
+        // Evicted.
+        assert_eq!(mmr.root_at_end(6), None);
+        assert_eq!(mmr.history().len(), 4);
+    }
+
+    #[test]
+    fn test_prove_consistency() {
+        let leaves: Vec<B256> = (0..24).map(|_| get_random_hash()).collect();
+        let mut mmr = HistoricalMMR::new(MMR::new(), 4).with_peak_index(PeakIndex::new(2, 8));
+        for leaf in &leaves {
+            mmr.append(*leaf);
+        }
+        let proof = mmr.prove_consistency(12, 24).unwrap();
+        let old = mmr.peak_index().unwrap().mmr_at(12).unwrap();
+        assert_eq!(old, MMR::from_leaves(&leaves[..12]));
+        assert!(proof.verify(&old, mmr.mmr().get_root()));
+        assert!(HistoricalMMR::new(MMR::new(), 4)
+            .prove_consistency(0, 0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_merge_records_root() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mut mmr = HistoricalMMR::new(MMR::from_leaves(&leaves[..3]), 8);
+        let mut other = MMR::from_params(3, 3, vec![]).unwrap();
+        for leaf in &leaves[3..] {
+            other.append(*leaf);
+        }
+        mmr.merge(&other).unwrap();
+
+        let ends: Vec<u64> = mmr.history().iter().map(|(end, _)| end).collect();
+        assert_eq!(ends, vec![3, 8]);
+        assert_eq!(
+            mmr.root_at_end(8),
+            Some(MMR::from_leaves(&leaves).get_root())
+        );
+    }
+
+    #[test]
+    fn test_verify_historical_proof() {
+        let leaves: Vec<B256> = (0..12).map(|_| get_random_hash()).collect();
+        let mut mmr = HistoricalMMR::new(MMR::new(), 8);
+        for leaf in &leaves[..9] {
+            mmr.append(*leaf);
+        }
+        // The client holds a proof generated at end 9.
+        let old = mmr.mmr().clone();
+        let proof = prove(&old, &leaves[..9], 4).unwrap();
+        for leaf in &leaves[9..] {
+            mmr.append(*leaf);
+        }
+
+        let history = mmr.history();
+        assert_eq!(
+            history.verify(&proof, 0, old.peaks(), leaves[4], 9),
+            Some(9)
+        );
+        // A stale hint still finds the matching root.
+        assert_eq!(
+            history.verify(&proof, 0, old.peaks(), leaves[4], 12),
+            Some(9)
+        );
+        assert_eq!(history.verify(&proof, 0, old.peaks(), leaves[5], 9), None);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut history = RootHistory::new(0);
+        history.record(1, get_random_hash());
+        assert!(history.is_empty());
+        assert_eq!(history.root_at_end(1), None);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use alloy_primitives::B256;
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Csv(csv::Error),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::errors::ParquetError),
+    Mmr(MMRError),
+    MissingColumn(String),
+    MalformedRow {
+        line: u64,
+        value: String,
+    },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "I/O error: {}", err),
+            ImportError::Csv(err) => write!(f, "CSV error: {}", err),
+            #[cfg(feature = "parquet")]
+            ImportError::Parquet(err) => write!(f, "Parquet error: {}", err),
+            ImportError::Mmr(err) => write!(f, "MMR error: {}", err),
+            ImportError::MissingColumn(column) => write!(f, "Column `{}` not found", column),
+            ImportError::MalformedRow { line, value } => {
+                write!(f, "Malformed leaf `{}` on line {}", value, line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
+
+impl From<csv::Error> for ImportError {
+    fn from(err: csv::Error) -> Self {
+        ImportError::Csv(err)
+    }
+}
+
+impl From<MMRError> for ImportError {
+    fn from(err: MMRError) -> Self {
+        ImportError::Mmr(err)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for ImportError {
+    fn from(err: parquet::errors::ParquetError) -> Self {
+        ImportError::Parquet(err)
+    }
+}
+
+/// Parses a leaf given as 32 bytes of hex, with or without a `0x` prefix.
+fn parse_hex_leaf(value: &str, line: u64) -> Result<B256, ImportError> {
+    value
+        .trim()
+        .parse::<B256>()
+        .map_err(|_| ImportError::MalformedRow {
+            line,
+            value: value.to_string(),
+        })
+}
+
+/// Builds a zero-starting MMR from one column of a CSV file with a header row.
+///
+/// Each cell of `column` must hold a 32 byte hex-encoded leaf. Rows are streamed, so the file
+/// is never held in memory.
+pub fn from_csv<R: io::Read>(reader: R, column: &str) -> Result<MMR, ImportError> {
+    let mut mmr = MMR::new();
+    append_csv(&mut mmr, reader, column)?;
+    Ok(mmr)
+}
+
+/// Appends the leaves in one column of a CSV file to an existing MMR.
+///
+/// Returns the number of appended leaves. On error, e.g. a malformed row or a leaf the MMR
+/// rejects, the leaves before it have already been appended.
+pub fn append_csv<R: io::Read>(mmr: &mut MMR, reader: R, column: &str) -> Result<u64, ImportError> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let column_idx = reader
+        .headers()?
+        .iter()
+        .position(|header| header == column)
+        .ok_or_else(|| ImportError::MissingColumn(column.to_string()))?;
+
+    let mut appended = 0;
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+        let value = record
+            .get(column_idx)
+            .ok_or_else(|| ImportError::MalformedRow {
+                line,
+                value: String::new(),
+            })?;
+        mmr.try_append(parse_hex_leaf(value, line)?)?;
+        appended += 1;
+    }
+    Ok(appended)
+}
+
+/// Builds a zero-starting MMR from one column of a Parquet file.
+///
+/// The column may hold raw 32 byte values or hex-encoded strings. Errors report the 1-based
+/// row number in place of a line number.
+#[cfg(feature = "parquet")]
+pub fn from_parquet<P: AsRef<std::path::Path>>(path: P, column: &str) -> Result<MMR, ImportError> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    let reader = SerializedFileReader::new(std::fs::File::open(path)?)?;
+    let mut mmr = MMR::new();
+    for (row, record) in (1..).zip(reader.get_row_iter(None)?) {
+        let record = record?;
+        let (_, field) = record
+            .get_column_iter()
+            .find(|(name, _)| name.as_str() == column)
+            .ok_or_else(|| ImportError::MissingColumn(column.to_string()))?;
+        let leaf = match field {
+            Field::Bytes(bytes) if bytes.len() == 32 => B256::from_slice(bytes.data()),
+            Field::Str(value) => parse_hex_leaf(value, row)?,
+            other => {
+                return Err(ImportError::MalformedRow {
+                    line: row,
+                    value: other.to_string(),
+                })
+            }
+        };
+        mmr.try_append(leaf)?;
+    }
+    Ok(mmr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_from_csv() {
+        let leaves: Vec<B256> = (0..5).map(|_| get_random_hash()).collect();
+        let mut csv = String::from("block,leaf\n");
+        for (i, leaf) in leaves.iter().enumerate() {
+            // Accept both prefixed and bare hex.
+            if i % 2 == 0 {
+                csv.push_str(&format!("{},{}\n", i, leaf));
+            } else {
+                csv.push_str(&format!("{},{}\n", i, &leaf.to_string()[2..]));
+            }
+        }
+        let mmr = from_csv(csv.as_bytes(), "leaf").unwrap();
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_from_csv_malformed_row() {
+        let csv = format!("leaf\n{}\n0x1234\n", get_random_hash());
+        assert!(matches!(
+            from_csv(csv.as_bytes(), "leaf"),
+            Err(ImportError::MalformedRow { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_csv_missing_column() {
+        let csv = format!("leaf\n{}\n", get_random_hash());
+        assert!(matches!(
+            from_csv(csv.as_bytes(), "hash"),
+            Err(ImportError::MissingColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_append_csv() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::from_leaves(&leaves[..1]);
+        let csv = format!("leaf\n{}\n{}\n{}\n", leaves[1], leaves[2], leaves[3]);
+        assert_eq!(append_csv(&mut mmr, csv.as_bytes(), "leaf").unwrap(), 3);
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_append_csv_rejected_leaf() {
+        let leaf = get_random_hash();
+        let mut mmr = MMR::new().with_strict();
+        let csv = format!("leaf\n{}\n{}\n", leaf, B256::ZERO);
+        assert!(matches!(
+            append_csv(&mut mmr, csv.as_bytes(), "leaf"),
+            Err(ImportError::Mmr(MMRError::ZeroLeaf))
+        ));
+        assert_eq!(mmr, MMR::from_leaves(&[leaf]));
+
+        let mut mmr = MMR::new().with_max_end(1);
+        assert!(matches!(
+            append_csv(&mut mmr, csv.as_bytes(), "leaf"),
+            Err(ImportError::Mmr(MMRError::RangeLimitReached))
+        ));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_from_parquet() {
+        use parquet::data_type::{ByteArray, ByteArrayType};
+        use parquet::file::{properties::WriterProperties, writer::SerializedFileWriter};
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let leaves: Vec<B256> = (0..7).map(|_| get_random_hash()).collect();
+        let path = std::env::temp_dir().join(format!("rust-mmr-{}.parquet", get_random_hash()));
+        let schema =
+            Arc::new(parse_message_type("message schema { REQUIRED BYTE_ARRAY leaf; }").unwrap());
+        let file = std::fs::File::create(&path).unwrap();
+        let mut writer =
+            SerializedFileWriter::new(file, schema, Arc::new(WriterProperties::default())).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        let values: Vec<ByteArray> = leaves.iter().map(|leaf| leaf.to_vec().into()).collect();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&values, None, None)
+            .unwrap();
+        column.close().unwrap();
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        let mmr = from_parquet(&path, "leaf").unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+    }
+}
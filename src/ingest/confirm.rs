@@ -0,0 +1,261 @@
+use super::eth::{event_filter, fetch_logs, log_leaf, Checkpoint, IngestError};
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use alloy_primitives::{Address, B256};
+use alloy_provider::Provider;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+struct StagedBlock {
+    hash: B256,
+    leaves: Vec<B256>,
+}
+
+/// Leaves of recent blocks, held back until they have enough confirmations.
+///
+/// A block is confirmed once `depth` blocks were mined on top of it. Until then its leaves
+/// stay staged, and are discarded if the block is reorged out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagingBuffer {
+    depth: u64,
+    blocks: BTreeMap<u64, StagedBlock>,
+}
+
+impl StagingBuffer {
+    /// Creates an empty buffer confirming blocks after `depth` further blocks
+    pub fn new(depth: u64) -> Self {
+        Self {
+            depth,
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    /// Stages the leaves of a block.
+    ///
+    /// If the block was already staged under a different hash, the chain was reorganized:
+    /// the block and every later staged block are unwound before staging the new one, and
+    /// the block number is returned.
+    pub fn stage(&mut self, block: u64, hash: B256, leaves: Vec<B256>) -> Option<u64> {
+        let reorged = match self.blocks.get(&block) {
+            Some(staged) if staged.hash == hash => return None,
+            Some(_) => {
+                self.unwind_from(block);
+                Some(block)
+            }
+            None => None,
+        };
+        self.blocks.insert(block, StagedBlock { hash, leaves });
+        reorged
+    }
+
+    /// Discards every staged block from `block` onwards, returning how many were dropped
+    pub fn unwind_from(&mut self, block: u64) -> usize {
+        let dropped = self.blocks.split_off(&block);
+        dropped.len()
+    }
+
+    /// Appends the leaves of every block confirmed at `head` to `mmr`, in block order.
+    ///
+    /// # Returns
+    ///
+    /// The number of leaves appended.
+    pub fn confirm(&mut self, head: u64, mmr: &mut MMR) -> Result<u64, MMRError> {
+        let mut appended = 0;
+        while let Some(entry) = self.blocks.first_entry() {
+            if entry.key().saturating_add(self.depth) > head {
+                break;
+            }
+            for leaf in &entry.get().leaves {
+                mmr.try_append(*leaf)?;
+                appended += 1;
+            }
+            entry.remove();
+        }
+        Ok(appended)
+    }
+
+    /// Returns the staged block numbers, in order
+    pub fn blocks(&self) -> impl Iterator<Item = u64> + '_ {
+        self.blocks.keys().copied()
+    }
+
+    /// Returns the number of staged blocks
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns true if no blocks are staged
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+/// Result of one [`poll_confirmed`] round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollOutcome {
+    /// The chain head seen by the round.
+    pub head: u64,
+    /// The number of leaves appended to the MMR.
+    pub appended: u64,
+    /// The first block unwound from the staging buffer, if a reorg was detected.
+    pub reorged_from: Option<u64>,
+}
+
+/// Ingests one round of logs, appending only those with enough confirmations.
+///
+/// Every unconfirmed block from `checkpoint.next_block` to the head is re-requested, so a
+/// staged block whose hash changed or whose logs disappeared is unwound instead of being
+/// appended. `checkpoint.next_block` only advances past confirmed blocks.
+///
+/// # Arguments
+///
+/// * `provider` - The provider to request logs from.
+/// * `address` - The contract emitting the event.
+/// * `event` - The event signature, e.g. `Transfer(address,address,uint256)`.
+/// * `checkpoint` - The confirmed state, updated in place.
+/// * `staging` - The unconfirmed blocks, carried between rounds.
+pub async fn poll_confirmed<P: Provider>(
+    provider: &P,
+    address: Address,
+    event: &str,
+    checkpoint: &mut Checkpoint,
+    staging: &mut StagingBuffer,
+) -> Result<PollOutcome, IngestError> {
+    let head = provider.get_block_number().await?;
+    let mut outcome = PollOutcome {
+        head,
+        appended: 0,
+        reorged_from: None,
+    };
+    if head < checkpoint.next_block {
+        return Ok(outcome);
+    }
+
+    let filter = event_filter(address, event);
+    let mut fetched: BTreeMap<u64, StagedBlock> = BTreeMap::new();
+    for log in fetch_logs(provider, &filter, checkpoint.next_block, head).await? {
+        let (Some(block), Some(hash)) = (log.block_number, log.block_hash) else {
+            return Err(IngestError::MissingPosition);
+        };
+        fetched
+            .entry(block)
+            .or_insert_with(|| StagedBlock {
+                hash: B256::from(hash.0),
+                leaves: vec![],
+            })
+            .leaves
+            .push(log_leaf(&log));
+    }
+
+    // A staged block without logs in the new response was reorged out.
+    let vanished = staging.blocks().find(|block| !fetched.contains_key(block));
+    if let Some(block) = vanished {
+        staging.unwind_from(block);
+        outcome.reorged_from = Some(block);
+    }
+    for (block, StagedBlock { hash, leaves }) in fetched {
+        if let Some(reorged) = staging.stage(block, hash, leaves) {
+            outcome.reorged_from = Some(outcome.reorged_from.map_or(reorged, |b| b.min(reorged)));
+        }
+    }
+
+    outcome.appended = staging.confirm(head, &mut checkpoint.mmr)?;
+    if let Some(confirmed) = head.checked_sub(staging.depth) {
+        checkpoint.next_block = checkpoint.next_block.max(confirmed + 1);
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+    use alloy_provider::{mock::Asserter, ProviderBuilder};
+    use serde_json::json;
+
+    #[test]
+    fn test_confirm_after_depth() {
+        let leaves: Vec<B256> = (0..3).map(|_| get_random_hash()).collect();
+        let mut staging = StagingBuffer::new(2);
+        let mut mmr = MMR::new();
+        staging.stage(5, get_random_hash(), leaves[..2].to_vec());
+        staging.stage(6, get_random_hash(), leaves[2..].to_vec());
+
+        assert_eq!(staging.confirm(6, &mut mmr).unwrap(), 0);
+        assert_eq!(staging.confirm(7, &mut mmr).unwrap(), 2);
+        assert_eq!(staging.blocks().collect::<Vec<_>>(), vec![6]);
+        assert_eq!(staging.confirm(8, &mut mmr).unwrap(), 1);
+        assert!(staging.is_empty());
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_stage_unwinds_on_new_hash() {
+        let mut staging = StagingBuffer::new(3);
+        let hash = get_random_hash();
+        for block in 10..14 {
+            staging.stage(block, get_random_hash(), vec![get_random_hash()]);
+        }
+        staging.stage(14, hash, vec![]);
+        assert_eq!(staging.stage(14, hash, vec![]), None);
+
+        assert_eq!(staging.stage(12, get_random_hash(), vec![]), Some(12));
+        assert_eq!(staging.blocks().collect::<Vec<_>>(), vec![10, 11, 12]);
+    }
+
+    fn log(block: u64, hash: u8, value: u8) -> serde_json::Value {
+        json!({
+            "address": "0x1111111111111111111111111111111111111111",
+            "topics": [format!("0x{}", "22".repeat(32))],
+            "data": format!("0x{:02x}", value),
+            "blockNumber": format!("0x{:x}", block),
+            "blockHash": format!("0x{}", format!("{:02x}", hash).repeat(32)),
+            "logIndex": "0x0",
+            "transactionHash": null,
+            "transactionIndex": null,
+            "removed": false,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_poll_unwinds_reorg() {
+        let asserter = Asserter::new();
+        // Head 3: blocks 1 and 3 are staged, block 1 is confirmed.
+        asserter.push_success(&"0x3");
+        asserter.push_success(&vec![log(1, 0xa1, 1), log(3, 0xa3, 3)]);
+        // Head 4: block 3 was reorged out and replaced by a block 4 log.
+        asserter.push_success(&"0x4");
+        asserter.push_success(&vec![log(4, 0xb4, 4)]);
+        // Head 6: block 4 is confirmed.
+        asserter.push_success(&"0x6");
+        asserter.push_success(&vec![log(4, 0xb4, 4)]);
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let mut checkpoint = Checkpoint {
+            next_block: 1,
+            mmr: MMR::new(),
+        };
+        let mut staging = StagingBuffer::new(2);
+        let address = Address::repeat_byte(0x11);
+
+        let outcome = poll_confirmed(&provider, address, "Event()", &mut checkpoint, &mut staging)
+            .await
+            .unwrap();
+        assert_eq!((outcome.appended, outcome.reorged_from), (1, None));
+        assert_eq!(checkpoint.next_block, 2);
+
+        let outcome = poll_confirmed(&provider, address, "Event()", &mut checkpoint, &mut staging)
+            .await
+            .unwrap();
+        assert_eq!((outcome.appended, outcome.reorged_from), (0, Some(3)));
+        assert_eq!(staging.blocks().collect::<Vec<_>>(), vec![4]);
+
+        let outcome = poll_confirmed(&provider, address, "Event()", &mut checkpoint, &mut staging)
+            .await
+            .unwrap();
+        assert_eq!((outcome.appended, outcome.reorged_from), (1, None));
+        assert_eq!(checkpoint.next_block, 5);
+        assert_eq!(checkpoint.mmr.end(), 2);
+        assert!(staging.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use alloy_primitives::{Address, Keccak256, B256};
+use alloy_provider::transport::TransportError;
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::{Filter, Log, ValueOrArray};
+use std::fmt;
+
+/// Error returned while ingesting logs.
+#[derive(Debug)]
+pub enum IngestError {
+    Transport(TransportError),
+    Mmr(MMRError),
+    /// A log was returned without its block number, block hash or log index.
+    MissingPosition,
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngestError::Transport(err) => write!(f, "Transport error: {}", err),
+            IngestError::Mmr(err) => write!(f, "MMR error: {}", err),
+            IngestError::MissingPosition => write!(f, "Log is missing its block position"),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+impl From<TransportError> for IngestError {
+    fn from(err: TransportError) -> Self {
+        IngestError::Transport(err)
+    }
+}
+
+impl From<MMRError> for IngestError {
+    fn from(err: MMRError) -> Self {
+        IngestError::Mmr(err)
+    }
+}
+
+/// The state of an ingestion after every log up to (excluding) `next_block` was appended.
+///
+/// Persist it to resume ingestion after a restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub next_block: u64,
+    pub mmr: MMR,
+}
+
+/// Computes the leaf for a log: `keccak256(address || topics || data)`.
+pub fn log_leaf(log: &Log) -> B256 {
+    let mut hasher = Keccak256::new();
+    hasher.update(log.address().as_slice());
+    for topic in log.topics() {
+        hasher.update(topic.as_slice());
+    }
+    hasher.update(&log.data().data);
+    hasher.finalize()
+}
+
+/// Builds the filter matching one event emitted by a contract
+pub(super) fn event_filter(address: Address, event: &str) -> Filter {
+    Filter::new()
+        .address(ValueOrArray::Value(address.0 .0.into()))
+        .event(event)
+}
+
+/// Requests the logs in `[from_block, to_block]`, dropping removed logs and sorting the rest
+/// in chain order
+pub(super) async fn fetch_logs<P: Provider>(
+    provider: &P,
+    filter: &Filter,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<Log>, IngestError> {
+    let mut logs = provider
+        .get_logs(&filter.clone().from_block(from_block).to_block(to_block))
+        .await?;
+    logs.retain(|log| !log.removed);
+    let mut positioned = logs
+        .into_iter()
+        .map(|log| match (log.block_number, log.log_index) {
+            (Some(block), Some(index)) => Ok(((block, index), log)),
+            _ => Err(IngestError::MissingPosition),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    positioned.sort_by_key(|(position, _)| *position);
+    Ok(positioned.into_iter().map(|(_, log)| log).collect())
+}
+
+/// Streams the logs of one event emitted by a contract into an MMR.
+///
+/// Logs are requested `blocks_per_request` blocks at a time and appended in chain order.
+/// After every request `on_checkpoint` receives the state covering all blocks so far, so a
+/// restarted ingestion can pass the last checkpoint back in.
+///
+/// # Arguments
+///
+/// * `provider` - The provider to request logs from.
+/// * `address` - The contract emitting the event.
+/// * `event` - The event signature, e.g. `Transfer(address,address,uint256)`.
+/// * `checkpoint` - The state to resume from.
+/// * `to_block` - The last block to ingest (inclusive).
+/// * `blocks_per_request` - The maximum number of blocks covered by one `eth_getLogs` call.
+/// * `on_checkpoint` - Called after every request with the state so far.
+///
+/// # Returns
+///
+/// The final checkpoint, with `next_block` set past `to_block`.
+pub async fn ingest_logs<P, F>(
+    provider: &P,
+    address: Address,
+    event: &str,
+    checkpoint: Checkpoint,
+    to_block: u64,
+    blocks_per_request: u64,
+    mut on_checkpoint: F,
+) -> Result<Checkpoint, IngestError>
+where
+    P: Provider,
+    F: FnMut(&Checkpoint),
+{
+    let Checkpoint {
+        mut next_block,
+        mut mmr,
+    } = checkpoint;
+    let filter = event_filter(address, event);
+    while next_block <= to_block {
+        let last_block = next_block
+            .saturating_add(blocks_per_request.max(1) - 1)
+            .min(to_block);
+        for log in fetch_logs(provider, &filter, next_block, last_block).await? {
+            mmr.try_append(log_leaf(&log))?;
+        }
+
+        next_block = last_block + 1;
+        on_checkpoint(&Checkpoint {
+            next_block,
+            mmr: mmr.clone(),
+        });
+    }
+    Ok(Checkpoint { next_block, mmr })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_provider::{mock::Asserter, ProviderBuilder};
+    use serde_json::json;
+
+    fn log(block: u64, index: u64, value: u8) -> serde_json::Value {
+        json!({
+            "address": "0x1111111111111111111111111111111111111111",
+            "topics": [format!("0x{}", "22".repeat(32))],
+            "data": format!("0x{:02x}", value),
+            "blockNumber": format!("0x{:x}", block),
+            "logIndex": format!("0x{:x}", index),
+            "blockHash": null,
+            "transactionHash": null,
+            "transactionIndex": null,
+            "removed": false,
+        })
+    }
+
+    fn leaf(value: u8) -> B256 {
+        let mut hasher = Keccak256::new();
+        hasher.update([0x11; 20]);
+        hasher.update([0x22; 32]);
+        hasher.update([value]);
+        hasher.finalize()
+    }
+
+    #[tokio::test]
+    async fn test_ingest_with_checkpoints() {
+        let asserter = Asserter::new();
+        // Blocks 10-11, out of order within the response.
+        asserter.push_success(&vec![log(11, 0, 3), log(10, 1, 2), log(10, 0, 1)]);
+        // Blocks 12-13.
+        asserter.push_success(&Vec::<serde_json::Value>::new());
+        // Block 14.
+        asserter.push_success(&vec![log(14, 5, 4)]);
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let start = Checkpoint {
+            next_block: 10,
+            mmr: MMR::new(),
+        };
+        let mut checkpoints = vec![];
+        let end = ingest_logs(
+            &provider,
+            Address::repeat_byte(0x11),
+            "Event()",
+            start,
+            14,
+            2,
+            |checkpoint| checkpoints.push((checkpoint.next_block, checkpoint.mmr.end())),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(checkpoints, vec![(12, 3), (14, 3), (15, 4)]);
+        assert_eq!(end.next_block, 15);
+        assert_eq!(
+            end.mmr,
+            MMR::from_leaves(&[leaf(1), leaf(2), leaf(3), leaf(4)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_position() {
+        let asserter = Asserter::new();
+        let mut unmined = log(1, 0, 1);
+        unmined["blockNumber"] = serde_json::Value::Null;
+        asserter.push_success(&vec![unmined]);
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter);
+
+        let start = Checkpoint {
+            next_block: 1,
+            mmr: MMR::new(),
+        };
+        let result = ingest_logs(&provider, Address::ZERO, "Event()", start, 1, 10, |_| {}).await;
+        assert!(matches!(result, Err(IngestError::MissingPosition)));
+    }
+}
@@ -0,0 +1,239 @@
+/*
+Experimental k-ary MMR. Reference layout with arity 4 and 6 leaves:
+
+Level 1 |          [0]
+        |      /  /   \  \
+Level 0 |   [0] [1]  [2] [3]  [4] [5]
+
+Peaks: [1, 0], [0, 4], [0, 5]
+
+The number of peaks equals the digit sum of `end` written in base `arity`, and
+each internal node hashes `arity` children at once. This trades wider sibling
+sets for shallower trees, which is what we want to benchmark inside SP1.
+*/
+
+use crate::error::MMRError;
+use crate::utils::hash::{hash_children, hash_to_parent};
+use alloy_primitives::B256;
+
+/// Experimental zero-starting Merkle Mountain Range whose internal nodes have
+/// `arity` children instead of two.
+#[derive(Debug, PartialEq)]
+pub struct KaryMMR {
+    arity: u64,
+    end: u64,
+    peaks: Vec<B256>,
+}
+
+impl KaryMMR {
+    /// Creates a new empty k-ary MMR, rejecting arities below two
+    pub fn new(arity: u64) -> Result<Self, MMRError> {
+        if arity < 2 {
+            return Err(MMRError::InvalidArity);
+        }
+        Ok(Self {
+            arity,
+            end: 0,
+            peaks: vec![],
+        })
+    }
+
+    /// Builds a k-ary MMR from leaves, rejecting arities below two
+    pub fn from_leaves(arity: u64, leaves: &[B256]) -> Result<Self, MMRError> {
+        let mut mmr = Self::new(arity)?;
+        for leaf in leaves {
+            mmr.append(*leaf)?;
+        }
+        Ok(mmr)
+    }
+
+    /// Returns the number of children hashed into each internal node
+    pub fn arity(&self) -> u64 {
+        self.arity
+    }
+
+    /// Returns the end index of the MMR
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns a reference to the peaks of the MMR
+    pub fn peaks(&self) -> &[B256] {
+        &self.peaks
+    }
+
+    /// Appends an element, or returns `MMRError::IndexOverflow` if the MMR ends at `u64::MAX`
+    pub fn append(&mut self, element: B256) -> Result<(), MMRError> {
+        let end = self.end.checked_add(1).ok_or(MMRError::IndexOverflow)?;
+        // Every trailing `arity - 1` digit of `end` in base `arity` completes a node:
+        // the last `arity - 1` peaks are hashed together with the carried node.
+        let siblings = (self.arity - 1) as usize;
+        let mut node = element;
+        let mut index = self.end;
+        while index % self.arity == self.arity - 1 {
+            let split = self.peaks.len() - siblings;
+            self.peaks.push(node);
+            node = hash_children(&self.peaks[split..]);
+            self.peaks.truncate(split);
+            index /= self.arity;
+        }
+        self.peaks.push(node);
+        self.end = end;
+        Ok(())
+    }
+
+    /// Returns the root of the MMR, or `B256::ZERO` if it has no leaves
+    pub fn get_root(&self) -> B256 {
+        // Bag the peaks right to left, exactly like a zero-starting binary MMR.
+        self.peaks
+            .iter()
+            .rev()
+            .copied()
+            .reduce(|acc, peak| hash_to_parent(&peak, &acc))
+            .unwrap_or(B256::ZERO)
+    }
+}
+
+/// Calculates the expected number of peaks for a zero-starting k-ary MMR.
+///
+/// # Arguments
+///
+/// * `end` - The end of the interval (exclusive)
+/// * `arity` - The number of children per internal node
+///
+/// # Returns
+///
+/// The number of peaks, i.e. the digit sum of `end` in base `arity`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::kary::get_expected_num_peaks;
+///
+/// assert_eq!(get_expected_num_peaks(6, 4), 3);
+/// assert_eq!(get_expected_num_peaks(6, 2), 2);
+/// ```
+pub fn get_expected_num_peaks(end: u64, arity: u64) -> u64 {
+    let mut remaining = end;
+    let mut num_peaks = 0;
+    while remaining > 0 {
+        num_peaks += remaining % arity;
+        remaining /= arity;
+    }
+    num_peaks
+}
+
+/// Calculates the number of sibling hashes in an inclusion path to a peak of the given height.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::kary::get_path_length;
+///
+/// // A 64 leaf peak needs 6 siblings with arity 2, but 14 with arity 8.
+/// assert_eq!(get_path_length(6, 2), 6);
+/// assert_eq!(get_path_length(2, 8), 14);
+/// ```
+pub fn get_path_length(height: u32, arity: u64) -> u64 {
+    height as u64 * (arity - 1)
+}
+
+/// Hashes a full k-ary subtree from its leaves. The number of leaves must be a power of `arity`.
+pub fn subtree_root(arity: u64, leaves: &[B256]) -> B256 {
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let chunk = leaves.len() / arity as usize;
+    let children: Vec<B256> = leaves
+        .chunks(chunk)
+        .map(|part| subtree_root(arity, part))
+        .collect();
+    hash_children(&children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+    use crate::MMR;
+
+    #[test]
+    fn test_invalid_arity() {
+        assert!(matches!(KaryMMR::new(0), Err(MMRError::InvalidArity)));
+        assert!(matches!(KaryMMR::new(1), Err(MMRError::InvalidArity)));
+        assert!(KaryMMR::new(2).is_ok());
+    }
+
+    #[test]
+    fn test_binary_arity_matches_mmr() {
+        let leaves: Vec<B256> = (0..37).map(|_| get_random_hash()).collect();
+        let kary = KaryMMR::from_leaves(2, &leaves).unwrap();
+        let mmr = MMR::from_leaves(&leaves);
+        assert_eq!(kary.peaks(), mmr.peaks());
+        assert_eq!(kary.get_root(), mmr.get_root());
+    }
+
+    #[test]
+    fn test_append_arity_4() {
+        let leaves: Vec<B256> = (0..6).map(|_| get_random_hash()).collect();
+        let mmr = KaryMMR::from_leaves(4, &leaves).unwrap();
+        let node_1_0 = hash_children(&leaves[..4]);
+        assert_eq!(mmr.peaks(), &[node_1_0, leaves[4], leaves[5]]);
+        assert_eq!(
+            mmr.get_root(),
+            hash_to_parent(&node_1_0, &hash_to_parent(&leaves[4], &leaves[5]))
+        );
+    }
+
+    #[test]
+    fn test_peaks_match_subtrees() {
+        for arity in [4, 8] {
+            let leaves: Vec<B256> = (0..(arity * arity + 3))
+                .map(|_| get_random_hash())
+                .collect();
+            let mmr = KaryMMR::from_leaves(arity, &leaves).unwrap();
+            assert_eq!(
+                mmr.peaks()[0],
+                subtree_root(arity, &leaves[..(arity * arity) as usize])
+            );
+            assert_eq!(&mmr.peaks()[1..], &leaves[(arity * arity) as usize..]);
+        }
+    }
+
+    #[test]
+    fn test_get_expected_num_peaks() {
+        for arity in [2, 4, 8] {
+            let mut mmr = KaryMMR::new(arity).unwrap();
+            for _ in 0..200 {
+                mmr.append(get_random_hash()).unwrap();
+                assert_eq!(
+                    get_expected_num_peaks(mmr.end(), arity),
+                    mmr.peaks().len() as u64
+                );
+            }
+        }
+        assert_eq!(get_expected_num_peaks(0, 8), 0);
+        assert_eq!(get_expected_num_peaks(63, 8), 14);
+        assert_eq!(get_expected_num_peaks(64, 8), 1);
+    }
+
+    #[test]
+    fn test_empty_root() {
+        assert_eq!(KaryMMR::new(8).unwrap().get_root(), B256::ZERO);
+    }
+
+    #[test]
+    fn test_append_at_u64_max() {
+        let mut mmr = KaryMMR {
+            arity: 4,
+            end: u64::MAX,
+            peaks: vec![],
+        };
+        assert!(matches!(
+            mmr.append(get_random_hash()),
+            Err(MMRError::IndexOverflow)
+        ));
+        assert_eq!(mmr.end(), u64::MAX);
+        assert!(mmr.peaks().is_empty());
+    }
+}
@@ -1,6 +1,91 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod annotated;
+#[cfg(feature = "std")]
+pub mod cache;
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod compact;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(feature = "std")]
+pub mod consistency;
+#[cfg(feature = "std")]
+pub mod dedup;
+#[cfg(feature = "std")]
+pub mod delta;
+pub mod dual;
+#[cfg(feature = "std")]
+pub mod epoch;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod full;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "import")]
+pub mod import;
+#[cfg(feature = "eth")]
+pub mod ingest;
+#[cfg(feature = "experimental")]
+pub mod kary;
+pub mod limits;
+#[cfg(feature = "std")]
+mod lru;
+#[cfg(feature = "std")]
+pub mod merge;
 pub mod mmr;
+#[cfg(feature = "std")]
+pub mod mmr_proof;
+pub mod peaks;
+#[cfg(feature = "std")]
+pub mod pipeline;
+/// Re-exports of the commonly used types, so downstream code can depend on a single path
+/// while modules move around.
+///
+/// ```
+/// use rust_mmr::prelude::*;
+///
+/// let leaves: Vec<B256> = (0..5).map(B256::repeat_byte).collect();
+/// let mmr = MMR::from_leaves(&leaves);
+/// let proof: InclusionProof = prove(&mmr, &leaves, 3).unwrap();
+/// assert!(proof.verify(&mmr, leaves[3]));
+/// ```
+#[cfg(feature = "std")]
+pub mod prelude;
+#[cfg(feature = "std")]
+pub mod presets;
+pub mod progress;
+pub mod proof;
+#[cfg(feature = "prover")]
+pub mod prover;
+#[cfg(feature = "queue")]
+pub mod queue;
+#[cfg(feature = "std")]
+pub mod range_proof;
+#[cfg(feature = "rlp")]
+pub mod rlp;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod scheme;
+#[cfg(feature = "std")]
+pub mod sequence;
+pub mod sorted_pair;
+#[cfg(feature = "ssz")]
+pub mod ssz;
+#[cfg(feature = "storage")]
+pub mod store;
+#[cfg(feature = "std")]
+pub mod trace;
 pub mod utils;
+#[cfg(feature = "std")]
+pub mod writer;
 
 pub use error::MMRError;
 pub use mmr::MMR;
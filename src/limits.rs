@@ -0,0 +1,54 @@
+use crate::error::MMRError;
+use crate::utils::range::MAX_PEAKS;
+
+/// Upper bounds enforced while decoding untrusted input, checked against length prefixes
+/// before anything is allocated for the decoded items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of peaks of a compact range or consistency proof
+    pub max_peaks: usize,
+    /// Maximum number of siblings in an inclusion proof path
+    pub max_path_len: usize,
+    /// Maximum number of leaves in a batch, e.g. of an append proof
+    pub max_leaves: usize,
+}
+
+impl DecodeLimits {
+    /// Limits admitting every well-formed peak list and path of a `u64` range, and batches
+    /// of up to 2^20 leaves
+    pub const DEFAULT: Self = Self {
+        max_peaks: MAX_PEAKS,
+        max_path_len: u64::BITS as usize,
+        max_leaves: 1 << 20,
+    };
+
+    /// Returns `MMRError::TooManyPeaks` if `len` exceeds `max_peaks`
+    pub fn check_peaks(&self, len: usize) -> Result<(), MMRError> {
+        if len > self.max_peaks {
+            return Err(MMRError::TooManyPeaks);
+        }
+        Ok(())
+    }
+
+    /// Returns `MMRError::PathTooLong` if `len` exceeds `max_path_len`
+    pub fn check_path(&self, len: usize) -> Result<(), MMRError> {
+        if len > self.max_path_len {
+            return Err(MMRError::PathTooLong);
+        }
+        Ok(())
+    }
+
+    /// Returns `MMRError::TooManyLeaves` if `len` exceeds `max_leaves`
+    pub fn check_leaves(&self, len: usize) -> Result<(), MMRError> {
+        if len > self.max_leaves {
+            return Err(MMRError::TooManyLeaves);
+        }
+        Ok(())
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Least recently used map holding at most `capacity` entries, shared by the proof, node and
+/// page caches.
+///
+/// Every access takes the next tick, and `recency` maps ticks back to keys, so the least
+/// recently used key is the first entry of `recency`.
+#[derive(Debug)]
+pub(crate) struct Lru<K, V> {
+    capacity: usize,
+    entries: HashMap<K, (u64, V)>,
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K: Hash + Eq + Copy, V> Lru<K, V> {
+    /// Creates an empty map holding up to `capacity` entries; zero disables caching
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Returns the number of entries held
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no entries are held
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the value of `key`, marking it as the most recently used
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V> {
+        let (last_used, value) = self.entries.get_mut(key)?;
+        self.tick += 1;
+        self.recency.remove(last_used);
+        self.recency.insert(self.tick, *key);
+        *last_used = self.tick;
+        Some(value)
+    }
+
+    /// Inserts or replaces the value of `key` as the most recently used, returning true if
+    /// another entry was evicted to make room
+    pub(crate) fn insert(&mut self, key: K, value: V) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        self.tick += 1;
+        let mut evicted = false;
+        if let Some((last_used, _)) = self.entries.insert(key, (self.tick, value)) {
+            self.recency.remove(&last_used);
+        } else if self.entries.len() > self.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+                evicted = true;
+            }
+        }
+        self.recency.insert(self.tick, key);
+        evicted
+    }
+
+    /// Removes every entry
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+        assert!(!lru.insert(0, "a"));
+        assert!(!lru.insert(1, "b"));
+        assert_eq!(lru.get(&0), Some(&"a"));
+        assert!(lru.insert(2, "c"));
+        assert_eq!(lru.get(&1), None);
+        assert_eq!(lru.get(&0), Some(&"a"));
+        assert_eq!(lru.len(), 2);
+
+        // Replacing a value evicts nothing.
+        assert!(!lru.insert(2, "d"));
+        assert_eq!(lru.get(&2), Some(&"d"));
+        lru.clear();
+        assert_eq!(lru.len(), 0);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut lru = Lru::new(0);
+        assert!(!lru.insert(0, ()));
+        assert_eq!(lru.len(), 0);
+    }
+}
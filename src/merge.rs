@@ -0,0 +1,109 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use alloy_primitives::B256;
+
+/// Evidence that a root is the merge of two bordering MMRs with known roots.
+///
+/// The proof carries the ranges and peaks of both sides. A verifier holding only the three
+/// roots checks that each side's peaks bag to its root and that merging them yields the
+/// claimed merged root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeProof {
+    left: MMR,
+    right: MMR,
+}
+
+impl MergeProof {
+    /// Creates a proof from the two sides of a merge
+    pub fn new(left: MMR, right: MMR) -> Self {
+        Self { left, right }
+    }
+
+    /// Returns the left side of the merge
+    pub fn left(&self) -> &MMR {
+        &self.left
+    }
+
+    /// Returns the right side of the merge
+    pub fn right(&self) -> &MMR {
+        &self.right
+    }
+
+    /// Checks that `merged_root` is the merge of the MMRs with roots `left_root` and
+    /// `right_root`
+    pub fn verify(&self, left_root: B256, right_root: B256, merged_root: B256) -> bool {
+        self.left.get_root() == left_root
+            && self.right.get_root() == right_root
+            && self
+                .left
+                .merge(&self.right)
+                .is_ok_and(|merged| merged.get_root() == merged_root)
+    }
+}
+
+impl MMR {
+    /// Merges a bordering MMR into this one, also returning a proof of the merge
+    pub fn merge_with_proof(&self, other: &MMR) -> Result<(Self, MergeProof), MMRError> {
+        let merged = self.merge(other)?;
+        let proof = MergeProof::new(self.clone(), other.clone());
+        Ok((merged, proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    fn split(leaves: &[B256], at: usize) -> (MMR, MMR) {
+        let left = MMR::from_leaves(&leaves[..at]);
+        let mut right = MMR::from_params(at as u64, at as u64, vec![]).unwrap();
+        for leaf in &leaves[at..] {
+            right.append(*leaf);
+        }
+        (left, right)
+    }
+
+    #[test]
+    fn test_verify_merge() {
+        let leaves: Vec<B256> = (0..21).map(|_| get_random_hash()).collect();
+        let (left, right) = split(&leaves, 9);
+        let (merged, proof) = left.merge_with_proof(&right).unwrap();
+        assert_eq!(merged, MMR::from_leaves(&leaves));
+
+        let (a, b, c) = (left.get_root(), right.get_root(), merged.get_root());
+        assert!(proof.verify(a, b, c));
+        assert!(!proof.verify(a, b, get_random_hash()));
+        assert!(!proof.verify(b, a, c));
+        assert!(!proof.verify(a, get_random_hash(), c));
+    }
+
+    #[test]
+    fn test_tampered_peaks() {
+        let leaves: Vec<B256> = (0..12).map(|_| get_random_hash()).collect();
+        let (left, right) = split(&leaves, 5);
+        let (merged, proof) = left.merge_with_proof(&right).unwrap();
+
+        let mut peaks = right.peaks().to_vec();
+        peaks[0] = get_random_hash();
+        let tampered = MergeProof::new(
+            left.clone(),
+            MMR::from_params(right.start(), right.end(), peaks).unwrap(),
+        );
+        assert!(!tampered.verify(left.get_root(), right.get_root(), merged.get_root()));
+        assert_eq!(proof.left(), &left);
+        assert_eq!(proof.right(), &right);
+    }
+
+    #[test]
+    fn test_non_bordering() {
+        let left = MMR::from_leaves(&[get_random_hash(); 4]);
+        let right = MMR::from_params(5, 5, vec![]).unwrap();
+        assert!(matches!(
+            left.merge_with_proof(&right),
+            Err(MMRError::MergeError)
+        ));
+        let proof = MergeProof::new(left.clone(), right);
+        assert!(!proof.verify(left.get_root(), B256::ZERO, left.get_root()));
+    }
+}
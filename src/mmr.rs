@@ -1,139 +1,564 @@
+use crate::clock::Clock;
+#[cfg(feature = "std")]
+use crate::clock::SystemClock;
 use crate::error::MMRError;
+use crate::limits::DecodeLimits;
+use crate::peaks::Peaks;
+use crate::progress::{BuildProgress, ProgressTracker};
+use crate::scheme::{HasherId, SchemeDescriptor};
 use crate::utils::{
-    hash::hash_to_parent,
-    range::{decompose, get_expected_num_peaks},
+    hash::{hash_subtree_by, hash_subtree_in, HashKey, Hasher, Keccak256Hasher, TryHasher},
+    hint::{verify_decomposition, verify_merges, AppendHint},
+    merge_path::{MergePath, MergeStep},
+    range::{get_expected_num_peaks, get_peak_heights, locate_leaf, Decomposition},
 };
+use alloc::vec::Vec;
 use alloy_primitives::B256;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
 
-/// Implementation of a stateless Merkle Mountain Range (MMR)
-#[derive(Debug)]
-pub struct MMR {
-    start: u64,
-    end: u64,
-    peaks: Vec<B256>,
+/// Indices assigned to a batch of appended leaves, and the root after the batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppendedRange {
+    /// The index of the first leaf of the batch
+    pub first_index: u64,
+    /// The index of the last leaf of the batch (inclusive)
+    pub last_index: u64,
+    /// The root of the MMR after appending the batch
+    pub new_root: B256,
 }
 
-impl PartialEq for MMR {
+/// Order in which `MMR::peaks_ordered` lists peaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeakOrder {
+    /// The order of the leaves the peaks cover, as returned by `MMR::peaks`.
+    LeafOrder,
+    /// Decreasing height, strictly so for zero-starting MMRs. Otherwise a left and a right
+    /// peak can share a height, and the left one comes first.
+    HeightDescending,
+}
+
+/// A peak together with its height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Peak {
+    pub hash: B256,
+    /// The base-2 logarithm of the number of leaves the peak covers
+    pub height: u32,
+}
+
+/// The peak covering a leaf, as returned by `MMR::peak_for_leaf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeakInfo {
+    /// The position of the peak in `MMR::peaks`
+    pub peak_index: usize,
+    pub peak_hash: B256,
+    /// The height of the peak, which is also the length of the leaf's inclusion path
+    pub height: u32,
+    /// The offset of the leaf within the peak's subtree
+    pub local_offset: u64,
+}
+
+/// The peaks of an MMR looked up by height, as returned by `MMR::height_indexed`.
+///
+/// A height has at most one peak on each side of the decomposition. Both sides can only be
+/// occupied at once in MMRs not starting at zero, whose left side is otherwise empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeightIndexedPeaks<'a> {
+    left: u64,
+    right: u64,
+    peaks: &'a [B256],
+}
+
+impl<'a> HeightIndexedPeaks<'a> {
+    /// Returns the peak of height `height` in the left part of the decomposition
+    pub fn left(&self, height: u32) -> Option<&'a B256> {
+        let bit = 1u64.checked_shl(height)?;
+        (self.left & bit != 0).then(|| &self.peaks[(self.left & (bit - 1)).count_ones() as usize])
+    }
+
+    /// Returns the peak of height `height` in the right part of the decomposition
+    pub fn right(&self, height: u32) -> Option<&'a B256> {
+        let bit = 1u64.checked_shl(height)?;
+        // Right peaks follow the left ones, largest first.
+        let index = self.left.count_ones() + (self.right & !(bit | (bit - 1))).count_ones();
+        (self.right & bit != 0).then(|| &self.peaks[index as usize])
+    }
+
+    /// Returns the peak of height `height`, the left one if both sides have one
+    pub fn get(&self, height: u32) -> Option<&'a B256> {
+        self.left(height).or_else(|| self.right(height))
+    }
+}
+
+/// Implementation of a stateless Merkle Mountain Range (MMR), hashing with `H`
+#[derive(Debug, Clone)]
+pub struct MMR<H = Keccak256Hasher> {
+    peaks: Peaks,
+    /// The decomposition of the range, kept in step with `peaks` on every append
+    decomposition: Decomposition,
+    scheme: SchemeDescriptor,
+    max_end: Option<u64>,
+    strict: bool,
+    key: Option<HashKey>,
+    hasher: PhantomData<H>,
+}
+
+impl<H: Hasher> PartialEq for MMR<H> {
     fn eq(&self, other: &Self) -> bool {
-        self.start() == other.start() && self.end() == other.end() && self.peaks() == other.peaks()
+        self.start() == other.start()
+            && self.end() == other.end()
+            && self.peaks() == other.peaks()
+            && self.scheme() == other.scheme()
+            && self.key == other.key
     }
 }
 
-impl Default for MMR {
+impl<H: Hasher> Default for MMR<H> {
     fn default() -> Self {
-        Self::new()
+        Self::with_hasher()
+    }
+}
+
+/// Appends the leaves one by one as they are yielded, panicking like `append`.
+impl<H: Hasher> Extend<B256> for MMR<H> {
+    fn extend<I: IntoIterator<Item = B256>>(&mut self, leaves: I) {
+        for leaf in leaves {
+            self.append(leaf);
+        }
+    }
+}
+
+impl<'a, H: Hasher> Extend<&'a B256> for MMR<H> {
+    fn extend<I: IntoIterator<Item = &'a B256>>(&mut self, leaves: I) {
+        self.extend(leaves.into_iter().copied());
+    }
+}
+
+/// Builds a zero-starting MMR without collecting the leaves first.
+impl<H: Hasher> FromIterator<B256> for MMR<H> {
+    fn from_iter<I: IntoIterator<Item = B256>>(leaves: I) -> Self {
+        let mut mmr = Self::default();
+        mmr.extend(leaves);
+        mmr
     }
 }
 
 impl MMR {
+    /// Version byte leading the encoding of `to_bytes`
+    pub const ENCODING_VERSION: u8 = 1;
+
     /// Creates a new empty MMR
     pub fn new() -> Self {
-        Self {
-            start: 0,
-            end: 0,
-            peaks: vec![],
-        }
+        Self::with_hasher()
     }
 
-    pub fn from_leaves(leaves: &Vec<B256>) -> Self {
+    pub fn from_leaves(leaves: &[B256]) -> Self {
         let mut mmr = Self::new();
         // TODO(sina) update with a better implementation
         // Can merklize each "perfect" subtree in parallel
         // Each subtree's merklization can be further parallelized
-        for leaf in leaves {
-            mmr.append(*leaf);
-        }
+        mmr.append_all(leaves);
         mmr
     }
 
+    /// Builds a zero-starting MMR from leaves, also returning the indices they were assigned
+    /// (`None` if `leaves` is empty)
+    pub fn from_leaves_with_range(leaves: &[B256]) -> (Self, Option<AppendedRange>) {
+        let mut mmr = Self::new();
+        let range = mmr.append_batch(leaves);
+        (mmr, range)
+    }
+
     /// Creates a new MMR from the given parameters, validating the input
     pub fn from_params(start: u64, end: u64, peaks: Vec<B256>) -> Result<Self, MMRError> {
+        Ok(Self::from_peaks(Peaks::new(start, end, peaks)?))
+    }
+
+    /// Creates a new MMR from already validated peaks
+    pub fn from_peaks(peaks: Peaks) -> Self {
+        Self::from_peaks_with_hasher(peaks)
+    }
+
+    /// Decodes an MMR committed with `to_commit_bytes`, e.g. from a proof's public values.
+    /// See `from_commit_bytes_with_hasher` for other hashers.
+    pub fn from_commit_bytes(bytes: &[u8]) -> Result<Self, MMRError> {
+        Self::from_commit_bytes_with_hasher(bytes)
+    }
+
+    /// Decodes an MMR encoded by `to_bytes`, rejecting unknown versions, more peaks than
+    /// `DecodeLimits::DEFAULT` allows and peaks that don't match the range. Use `into_strict`
+    /// to also reject suspicious peaks, and `from_bytes_with_hasher` for other hashers.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MMRError> {
+        Self::from_bytes_with_hasher(bytes)
+    }
+
+    /// Creates a zero-starting MMR from leaves, reporting progress every `interval` leaves.
+    #[cfg(feature = "std")]
+    pub fn from_leaves_with_progress<F: FnMut(BuildProgress)>(
+        leaves: &[B256],
+        interval: u64,
+        on_progress: F,
+    ) -> Self {
+        let mut mmr = Self::new();
+        mmr.append_with_progress(leaves, interval, on_progress);
+        mmr
+    }
+
+    /// Builds a zero-starting MMR from leaves and one host-computed hint per leaf.
+    pub fn from_leaves_hinted(leaves: &[B256], hints: &[AppendHint]) -> Result<Self, MMRError> {
+        if leaves.len() != hints.len() {
+            return Err(MMRError::InvalidHint);
+        }
+        let mut mmr = Self::new();
+        for (leaf, hint) in leaves.iter().zip(hints) {
+            mmr.append_hinted(*leaf, hint)?;
+        }
+        Ok(mmr)
+    }
+
+    /// Hashes parents as `keccak256(key || left || right)`, so that peaks and roots can't be
+    /// recomputed, or proofs checked, without the key. Leaves added with `append_bytes` are
+    /// keyed as well. Also tags the scheme with `HasherId::KeyedKeccak256`.
+    ///
+    /// Set the key before appending: peaks hashed under another key are not rehashed.
+    /// `get_root`, `merge`, `split`, `rotate`, `delta`, `prove` and `InclusionProof::verify`
+    /// honour the key, as do range and consistency proofs, `PeakIndex`, `FullMMR::with_key`,
+    /// `Peaks::root_keyed` and `AppendTrace::replay_keyed`. So do the stores, through
+    /// `store::append_leaves`, `StoredMMR::restore_keyed` and the template MMR given to
+    /// `commit::recover`, and the shards and deltas built with `IndexRange::shard_like` and
+    /// `MMRDelta::for_mmr`. The free functions in `utils` and the verifiers given only a root
+    /// still hash with plain Keccak256.
+    pub fn with_key(mut self, key: B256) -> Self {
+        self.key = Some(HashKey::new(key));
+        self.scheme.hasher = HasherId::KeyedKeccak256;
+        self
+    }
+}
+
+impl<H: Hasher> MMR<H> {
+    /// Creates a new empty MMR hashing with `H`
+    pub fn with_hasher() -> Self {
+        Self::from_peaks_with_hasher(Peaks::empty(0))
+    }
+
+    /// Like `from_commit_bytes`, for an MMR hashing with `H`
+    pub fn from_commit_bytes_with_hasher(bytes: &[u8]) -> Result<Self, MMRError> {
+        let (start, rest) = bytes
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (end, peaks) = rest
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (start, end) = (u64::from_be_bytes(*start), u64::from_be_bytes(*end));
         if start > end {
             return Err(MMRError::StartGreaterThanEnd);
         }
-        if get_expected_num_peaks(start, end) != peaks.len() as u64 {
-            return Err(MMRError::InvalidNumberOfPeaks);
+        if peaks.len() != 32 * get_expected_num_peaks(start, end) as usize {
+            return Err(MMRError::InvalidEncoding);
+        }
+        Ok(Self::from_peaks_with_hasher(Peaks::new(
+            start,
+            end,
+            peaks.chunks_exact(32).map(B256::from_slice).collect(),
+        )?))
+    }
+
+    /// Like `from_bytes`, for an MMR hashing with `H`
+    pub fn from_bytes_with_hasher(bytes: &[u8]) -> Result<Self, MMRError> {
+        let (&version, rest) = bytes.split_first().ok_or(MMRError::InvalidEncoding)?;
+        if version != MMR::ENCODING_VERSION {
+            return Err(MMRError::InvalidEncoding);
+        }
+        let (start, rest) = rest
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (end, rest) = rest
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (len, peaks) = rest
+            .split_first_chunk::<4>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let len = u32::from_le_bytes(*len) as usize;
+        DecodeLimits::DEFAULT.check_peaks(len)?;
+        if peaks.len() != 32 * len {
+            return Err(MMRError::InvalidEncoding);
+        }
+        Ok(Self::from_peaks_with_hasher(Peaks::new(
+            u64::from_le_bytes(*start),
+            u64::from_le_bytes(*end),
+            peaks.chunks_exact(32).map(B256::from_slice).collect(),
+        )?))
+    }
+
+    /// Creates a new MMR hashing with `H` from already validated peaks
+    pub fn from_peaks_with_hasher(peaks: Peaks) -> Self {
+        Self {
+            decomposition: Decomposition::new(peaks.start(), peaks.end()),
+            peaks,
+            scheme: SchemeDescriptor {
+                hasher: H::ID,
+                ..SchemeDescriptor::DEFAULT
+            },
+            max_end: None,
+            strict: false,
+            key: None,
+            hasher: PhantomData,
+        }
+    }
+
+    /// Returns an MMR over `peaks` with the scheme, limit, strictness and key of this one
+    pub(crate) fn with_peaks(&self, peaks: Peaks) -> Self {
+        Self {
+            scheme: self.scheme,
+            max_end: self.max_end,
+            strict: self.strict,
+            key: self.key,
+            ..Self::from_peaks_with_hasher(peaks)
         }
+    }
 
-        Ok(Self { start, end, peaks })
+    /// Checks the invariants of the MMR that its `Peaks` don't already guarantee: in strict
+    /// mode, no peak may be zero and no two adjacent peaks of equal height may be identical.
+    ///
+    /// Identical sibling-height peaks need identical subtrees, which is far more likely to
+    /// be a duplicated write than real data.
+    pub fn validate(&self) -> Result<(), MMRError> {
+        if !self.strict {
+            return Ok(());
+        }
+        if self.peaks.contains(&B256::ZERO) {
+            return Err(MMRError::ZeroPeak);
+        }
+        let heights = get_peak_heights(self.start(), self.end());
+        if self
+            .peaks
+            .windows(2)
+            .zip(heights.windows(2))
+            .any(|(peaks, heights)| heights[0] == heights[1] && peaks[0] == peaks[1])
+        {
+            return Err(MMRError::DuplicatePeaks);
+        }
+        Ok(())
     }
 
     pub fn size(&self) -> u64 {
-        self.end - self.start
+        self.end() - self.start()
     }
 
     pub fn get_root(&self) -> B256 {
         if self.peaks.is_empty() {
-            return B256::ZERO;
+            return H::empty_root();
+        }
+        self.peaks.root_by(self.parent_hasher())
+    }
+
+    /// Appends an element.
+    ///
+    /// Panics if the MMR has reached its `max_end` or `u64::MAX`, if it is strict and
+    /// `element` is zero, or if the hasher doesn't accept `element`; use `try_append` to
+    /// handle these.
+    pub fn append(&mut self, element: B256) {
+        self.append_merged(element, self.next_merges());
+    }
+
+    /// Appends an element, or returns `MMRError::RangeLimitReached` if the MMR has reached
+    /// its `max_end`, `MMRError::IndexOverflow` if it ends at `u64::MAX`,
+    /// `MMRError::ZeroLeaf` if it is strict and `element` is zero and
+    /// `MMRError::LeafOutOfDomain` if the hasher doesn't accept `element`
+    pub fn try_append(&mut self, element: B256) -> Result<(), MMRError> {
+        self.check_append(element)?;
+        self.append(element);
+        Ok(())
+    }
+
+    /// Appends an element, hashing parents with a fallible `hasher` instead of the MMR's own,
+    /// e.g. the same hash function backed by a device.
+    ///
+    /// Fails like `try_append` before hashing, or with the hasher's first error, in which
+    /// case the MMR is left unchanged and the append can be retried.
+    pub fn try_append_with<T: TryHasher>(
+        &mut self,
+        hasher: &mut T,
+        element: B256,
+    ) -> Result<(), T::Error>
+    where
+        T::Error: From<MMRError>,
+    {
+        self.check_append(element)?;
+        let merges = self.next_merges();
+        self.peaks
+            .try_append_merged(element, merges, |left, right| {
+                hasher.try_hash_to_parent(left, right)
+            })?;
+        self.decomposition.increment();
+        Ok(())
+    }
+
+    /// Appends leaves, reporting progress every `interval` leaves and after the last one.
+    #[cfg(feature = "std")]
+    pub fn append_with_progress<F: FnMut(BuildProgress)>(
+        &mut self,
+        leaves: &[B256],
+        interval: u64,
+        on_progress: F,
+    ) {
+        self.append_with_clock(leaves, interval, &SystemClock, on_progress);
+    }
+
+    /// Like `append_with_progress`, timing the build with `clock`
+    pub fn append_with_clock<F: FnMut(BuildProgress)>(
+        &mut self,
+        leaves: &[B256],
+        interval: u64,
+        clock: &dyn Clock,
+        mut on_progress: F,
+    ) {
+        let mut tracker = ProgressTracker::new(leaves.len() as u64, interval, clock);
+        for leaf in leaves {
+            let hashes = self.append_merged(*leaf, self.next_merges());
+            if let Some(progress) = tracker.record(hashes) {
+                on_progress(progress);
+            }
         }
+    }
 
-        let (left, _) = decompose(self.start, self.end);
+    /// Appends leaves until done or until `cancel` is set.
+    ///
+    /// Cancellation is checked before each leaf. On `MMRError::Cancelled` the MMR holds every
+    /// leaf appended so far, so the build resumes by passing the leaves from `self.end()` onwards.
+    pub fn append_cancellable(
+        &mut self,
+        leaves: &[B256],
+        cancel: &AtomicBool,
+    ) -> Result<(), MMRError> {
+        for leaf in leaves {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(MMRError::Cancelled);
+            }
+            self.try_append(*leaf)?;
+        }
+        Ok(())
+    }
 
-        // Bag the peaks for the left side
-        let left_root = self.peaks[..left.count_ones() as usize]
-            .iter()
-            .fold(None, |acc, &peak| match acc {
-                None => Some(peak),
-                Some(prev) => Some(hash_to_parent(&prev, &peak)),
-            })
-            .unwrap_or(B256::ZERO);
+    /// Appends a variable-length record as the leaf `H::hash_leaf(data)`, keyed like the
+    /// parents if the MMR is.
+    ///
+    /// MMRs committing records this way should be tagged with `LeafPolicy::LengthPrefixed`.
+    pub fn append_bytes(&mut self, data: &[u8]) {
+        self.append(self.hash_leaf_bytes(data));
+    }
 
-        // Bag the peaks for the right side
-        let right_root = self.peaks[left.count_ones() as usize..]
+    /// Appends variable-length records in order, see `append_bytes` and `append_batch`
+    pub fn append_bytes_batch<T: AsRef<[u8]>>(&mut self, records: &[T]) -> Option<AppendedRange> {
+        let leaves: Vec<B256> = records
             .iter()
-            .rfold(None, |acc, &peak| match acc {
-                None => Some(peak),
-                Some(prev) => Some(hash_to_parent(&peak, &prev)),
-            })
-            .unwrap_or(B256::ZERO);
+            .map(|record| self.hash_leaf_bytes(record.as_ref()))
+            .collect();
+        self.append_batch(&leaves)
+    }
 
-        // Combine the left and right roots
-        if left_root == B256::ZERO {
-            right_root
-        } else if right_root == B256::ZERO {
-            left_root
-        } else {
-            hash_to_parent(&left_root, &right_root)
+    /// Appends leaves in order, returning the indices they were assigned (`None` if `leaves`
+    /// is empty).
+    ///
+    /// Panics under the same conditions as `append`.
+    pub fn append_batch(&mut self, leaves: &[B256]) -> Option<AppendedRange> {
+        if leaves.is_empty() {
+            return None;
         }
+        let first_index = self.end();
+        self.append_all(leaves);
+        Some(AppendedRange {
+            first_index,
+            last_index: self.end() - 1,
+            new_root: self.get_root(),
+        })
     }
 
-    pub fn append(&mut self, element: B256) {
-        // Leaf is being inserted at index `self.end`.
+    /// Appends leaves in order, merklizing each aligned power-of-two chunk as a whole
+    /// subtree before folding its root into the peaks.
+    ///
+    /// Panics before appending anything under the same conditions as `append`.
+    fn append_all(&mut self, leaves: &[B256]) {
+        if let Err(err) = self.check_room(leaves.len() as u64) {
+            panic!("{}", err);
+        }
+        if self.strict && leaves.contains(&B256::ZERO) {
+            panic!("{}", MMRError::ZeroLeaf);
+        }
+        if !leaves.iter().all(H::accepts_leaf) {
+            panic!("{}", MMRError::LeafOutOfDomain);
+        }
+        let mut context = H::Context::default();
+        let mut rest = leaves;
+        while !rest.is_empty() {
+            // The largest subtree starting at the end that is aligned and fits in `rest`.
+            let height = self.end().trailing_zeros().min(rest.len().ilog2());
+            let (chunk, tail) = rest.split_at(1 << height);
+            let new_end = self.end() + chunk.len() as u64;
+            let merges =
+                self.peaks.len() + 1 - get_expected_num_peaks(self.start(), new_end) as usize;
+            let root = self.hash_subtree(&mut context, chunk);
+            self.peaks
+                .append_subtree(root, chunk.len() as u64, merges, self.parent_hasher());
+            rest = tail;
+        }
+        self.decomposition = Decomposition::new(self.start(), self.end());
+    }
+
+    /// Returns the number of peaks the next appended leaf merges with.
+    pub(crate) fn next_merges(&self) -> usize {
+        // Leaf is being inserted at index `self.end()`.
         // Knowing this, we can follow its merge path from the leaf along the range for as long as it left-merges.
         // Once we encounter a right-merge, we know to stop, and insert the current node as a peak.
 
         // First, we calculate where the first right-merge will happen, via finding the least-significant unset bit in the new leaf's merge path.
         // We use the right component of the decomposed representation of the tree
         // to account for any offset that may be caused by a non-zero start.
-        let (_, right) = decompose(self.start, self.end);
-        let least_significant_unset_bit_idx = (!right).trailing_zeros() as usize;
+        (!self.decomposition.right()).trailing_zeros() as usize
+    }
 
-        // Calculate the number of peaks to keep
-        let peaks_to_keep = self
-            .peaks
-            .len()
-            .saturating_sub(least_significant_unset_bit_idx);
+    /// Returns the `(left, right)` decomposition of the range, as `decompose` would compute it
+    pub fn current_decomposition(&self) -> Decomposition {
+        self.decomposition
+    }
 
-        // Fold the new element into the peaks that need to be merged
-        let new_peak = self.peaks[peaks_to_keep..]
-            .iter()
-            .rfold(element, |acc, &peak| hash_to_parent(&peak, &acc));
+    /// Appends an element using a host-computed hint instead of recomputing the merge path.
+    ///
+    /// The hint is untrusted: it is checked against the current range before use, and
+    /// `MMRError::InvalidHint` is returned if it does not match.
+    pub fn append_hinted(&mut self, element: B256, hint: &AppendHint) -> Result<(), MMRError> {
+        let left = self.size().checked_sub(hint.right);
+        match left {
+            Some(left)
+                if verify_decomposition(self.start(), self.end(), left, hint.right)
+                    && verify_merges(hint.right, hint.merges) =>
+            {
+                self.check_append(element)?;
+                self.append_merged(element, hint.merges as usize);
+                Ok(())
+            }
+            _ => Err(MMRError::InvalidHint),
+        }
+    }
 
-        // Truncate the peaks array to keep only the unmerged peaks
-        self.peaks.truncate(peaks_to_keep);
-        // Add the new peak
-        self.peaks.push(new_peak);
-        self.end += 1;
+    /// Appends an element that merges with the last `merges` peaks, returning the number of hashes.
+    pub(crate) fn append_merged(&mut self, element: B256, merges: usize) -> u64 {
+        if let Err(err) = self.check_append(element) {
+            panic!("{}", err);
+        }
+        self.decomposition.increment();
+        let hash = self.parent_hasher();
+        self.peaks.append_merged(element, merges, hash)
     }
 
     /// Returns the start index of the MMR
     pub fn start(&self) -> u64 {
-        self.start
+        self.peaks.start()
     }
 
     /// Returns the end index of the MMR
     pub fn end(&self) -> u64 {
-        self.end
+        self.peaks.end()
     }
 
     /// Returns a reference to the peaks of the MMR
@@ -141,71 +566,319 @@ impl MMR {
         &self.peaks
     }
 
-    pub fn merge(&self, other: &MMR) -> Result<Self, MMRError> {
+    /// Encodes the MMR as `version || start || end || peak count || peaks`, with the
+    /// indices as 8 and the peak count as 4 little-endian bytes and the peaks concatenated
+    /// left to right. The scheme and configuration are not encoded.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(21 + 32 * self.peaks.len());
+        bytes.push(MMR::ENCODING_VERSION);
+        bytes.extend_from_slice(&self.start().to_le_bytes());
+        bytes.extend_from_slice(&self.end().to_le_bytes());
+        bytes.extend_from_slice(&(self.peaks.len() as u32).to_le_bytes());
+        for peak in self.peaks.iter() {
+            bytes.extend_from_slice(peak.as_slice());
+        }
+        bytes
+    }
+
+    /// Encodes the MMR for committing from a zkVM guest, as `start || end || peaks` with the
+    /// indices as 8 big-endian bytes. The peak count follows from the range, so the layout is
+    /// fixed for a given range and canonical.
+    pub fn to_commit_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + 32 * self.peaks.len());
+        bytes.extend_from_slice(&self.start().to_be_bytes());
+        bytes.extend_from_slice(&self.end().to_be_bytes());
+        for peak in self.peaks.iter() {
+            bytes.extend_from_slice(peak.as_slice());
+        }
+        bytes
+    }
+
+    /// Returns the peaks of the MMR together with the range they cover
+    pub fn as_peaks(&self) -> &Peaks {
+        &self.peaks
+    }
+
+    /// Returns the end past which the MMR refuses to grow, if any
+    pub fn max_end(&self) -> Option<u64> {
+        self.max_end
+    }
+
+    /// Stops the MMR from growing past `max_end`, so it can be rotated at a fixed boundary
+    /// instead of growing unboundedly. The limit is kept by MMRs merged into this one.
+    pub fn with_max_end(mut self, max_end: u64) -> Self {
+        self.max_end = Some(max_end);
+        self
+    }
+
+    /// Returns true if the MMR rejects zero leaves and peaks
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Rejects `B256::ZERO` leaves and peaks, which are ambiguous with the empty root.
+    ///
+    /// `append` panics on a zero leaf, while `try_append` and the other fallible appends
+    /// return `MMRError::ZeroLeaf`. `validate` and `merge` return `MMRError::ZeroPeak`.
+    pub fn with_strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Switches to strict mode and validates the MMR, so state loaded from storage or
+    /// deserialized can opt into rejecting corrupt-looking peaks, e.g.
+    /// `MMR::from_bytes(&bytes)?.into_strict()?`
+    pub fn into_strict(self) -> Result<Self, MMRError> {
+        let mmr = self.with_strict();
+        mmr.validate()?;
+        Ok(mmr)
+    }
+
+    /// Returns true if the MMR hashes with a secret key, see `with_key`
+    pub fn is_keyed(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Returns true if this MMR and `other` hash alike, with the same scheme and key
+    pub(crate) fn hashes_like(&self, other: &Self) -> bool {
+        self.scheme == other.scheme && self.key == other.key
+    }
+
+    /// Hashes two children into their parent, keyed if the MMR is
+    pub(crate) fn hash_to_parent(&self, left: &B256, right: &B256) -> B256 {
+        self.parent_hasher()(left, right)
+    }
+
+    /// Returns the function hashing two children into their parent, keyed if the MMR is. It
+    /// doesn't borrow the MMR, so it can hash into the MMR's own peaks.
+    fn parent_hasher(&self) -> impl Fn(&B256, &B256) -> B256 {
+        let key = self.key;
+        move |left, right| match &key {
+            Some(key) => key.hash_to_parent(left, right),
+            None => H::hash_parent(left, right),
+        }
+    }
+
+    /// Hashes the perfect subtree over `leaves`, keyed if the MMR is and through `context`
+    /// otherwise
+    pub(crate) fn hash_subtree(&self, context: &mut H::Context, leaves: &[B256]) -> B256 {
+        match &self.key {
+            Some(key) => hash_subtree_by(leaves, &|left, right| key.hash_to_parent(left, right)),
+            None => hash_subtree_in::<H>(context, leaves),
+        }
+    }
+
+    /// Hashes a variable-length record into a leaf, keyed if the MMR is
+    pub(crate) fn hash_leaf_bytes(&self, data: &[u8]) -> B256 {
+        match &self.key {
+            Some(key) => key.hash_leaf_bytes(data),
+            None => H::hash_leaf(data),
+        }
+    }
+
+    /// Checks that `element` can be appended under the configured limit, strictness and hasher
+    pub(crate) fn check_append(&self, element: B256) -> Result<(), MMRError> {
+        self.check_room(1)?;
+        if self.strict && element == B256::ZERO {
+            return Err(MMRError::ZeroLeaf);
+        }
+        if !H::accepts_leaf(&element) {
+            return Err(MMRError::LeafOutOfDomain);
+        }
+        Ok(())
+    }
+
+    /// Returns `MMRError::IndexOverflow` if `count` more leaves would end past `u64::MAX`, and
+    /// `MMRError::RangeLimitReached` if they would pass `max_end`
+    fn check_room(&self, count: u64) -> Result<(), MMRError> {
+        let end = self
+            .end()
+            .checked_add(count)
+            .ok_or(MMRError::IndexOverflow)?;
+        match self.max_end {
+            Some(max_end) if end > max_end => Err(MMRError::RangeLimitReached),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the peaks with their heights, in the given order
+    pub fn peaks_ordered(&self, order: PeakOrder) -> Vec<Peak> {
+        let mut peaks: Vec<Peak> = self
+            .peaks
+            .iter()
+            .zip(get_peak_heights(self.start(), self.end()))
+            .map(|(hash, height)| Peak {
+                hash: *hash,
+                height,
+            })
+            .collect();
+        if order == PeakOrder::HeightDescending {
+            // The sort is stable, so equal heights keep their leaf order.
+            peaks.sort_by_key(|peak| core::cmp::Reverse(peak.height));
+        }
+        peaks
+    }
+
+    /// Returns a view of the peaks indexed by height, derived from the decomposition
+    pub fn height_indexed(&self) -> HeightIndexedPeaks<'_> {
+        HeightIndexedPeaks {
+            left: self.decomposition.left(),
+            right: self.decomposition.right(),
+            peaks: self.peaks(),
+        }
+    }
+
+    /// Returns the peak of height `h`, or `None` if there is none. See `HeightIndexedPeaks::get`
+    /// for MMRs with two peaks of that height.
+    pub fn peak_at_height(&self, h: u32) -> Option<&B256> {
+        self.height_indexed().get(h)
+    }
+
+    /// Returns the peak covering the leaf at `index`, or `None` if it is outside the MMR
+    pub fn peak_for_leaf(&self, index: u64) -> Option<PeakInfo> {
+        let (peak_index, height, peak_begin) = locate_leaf(self.start(), self.end(), index)?;
+        Some(PeakInfo {
+            peak_index,
+            peak_hash: self.peaks[peak_index],
+            height,
+            local_offset: index - peak_begin,
+        })
+    }
+
+    /// Returns the hashing and bagging scheme the MMR was built with
+    pub fn scheme(&self) -> SchemeDescriptor {
+        self.scheme
+    }
+
+    /// Tags the MMR with the scheme it was built with
+    pub fn with_scheme(mut self, scheme: SchemeDescriptor) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Merges the bordering MMR `other`, which starts where this one ends, into one over
+    /// `[start, other.end)`. Either side may start anywhere, like transparency-dev's compact
+    /// ranges.
+    pub fn merge(&self, other: &Self) -> Result<Self, MMRError> {
+        // Ensure the MMRs were built with the same scheme.
+        if !self.hashes_like(other) {
+            return Err(MMRError::SchemeMismatch);
+        }
         // Ensure the MMRs are bordering.
-        if self.end != other.start {
+        if self.end() != other.start() {
             return Err(MMRError::MergeError);
         }
-        // Currently only works for 0-starting MMRs.
-        if self.start != 0 {
-            return Err(MMRError::MergeError);
+        // Ensure the merged range stays within the limit.
+        self.check_room(other.size())?;
+        // Merging with an empty range is a no-op.
+        if self.peaks.is_empty() {
+            let merged = Self {
+                max_end: self.max_end,
+                strict: self.strict,
+                ..other.clone()
+            };
+            if merged.strict {
+                merged.validate()?;
+            }
+            return Ok(merged);
+        }
+        if other.peaks.is_empty() {
+            return Ok(self.clone());
         }
-        // Start with the rightmost peak of the left MMR as the seed.
+        // Start with the rightmost peak of the left MMR as the seed, and zip it up with left
+        // and right peaks along its merge path.
         let mut seed = *self.peaks.last().unwrap();
-        // Seed height is equal to the index of the lsb of end.
-        let mut seed_height = self.end.trailing_zeros();
-        let mut seed_index = (self.end - 1) >> seed_height;
-        let seed_range_start = seed_index * (1 << seed_height);
-        // Zip seed up with left and right along its merge path.
         let mut left_cursor = self.peaks.len() - 1;
         let mut right_cursor = 0;
-        while seed_height < 255 {
-            let layer_coverage = 1 << seed_height;
-            if seed_index & 1 == 0 {
-                // Right merge, or break if not possible.
-                let merged_range_end = seed_range_start + (layer_coverage << 1);
-                if merged_range_end > other.end {
-                    break;
+        for step in MergePath::with_start(self.start(), self.end(), other.end()) {
+            match step {
+                MergeStep::Left { .. } => {
+                    left_cursor -= 1;
+                    seed = self.hash_to_parent(&self.peaks[left_cursor], &seed);
                 }
-                seed = hash_to_parent(&seed, &other.peaks[right_cursor]);
-                right_cursor += 1;
-            } else {
-                // Left merge, or break if not possible.
-                if layer_coverage > seed_range_start {
-                    break;
+                MergeStep::Right { .. } => {
+                    seed = self.hash_to_parent(&seed, &other.peaks[right_cursor]);
+                    right_cursor += 1;
                 }
-                left_cursor -= 1;
-                seed = hash_to_parent(&self.peaks[left_cursor], &seed);
             }
-            seed_index >>= 1;
-            seed_height += 1;
-        }
-
-        return Ok(Self {
-            start: self.start,
-            end: other.end,
-            peaks: self.peaks[..left_cursor]
-                .iter()
-                .chain(std::iter::once(&seed))
-                .chain(other.peaks[right_cursor..].iter())
-                .cloned()
-                .collect(),
-        });
+        }
+
+        let merged = Self {
+            decomposition: Decomposition::new(self.start(), other.end()),
+            peaks: Peaks::new_unchecked(
+                self.start(),
+                other.end(),
+                self.peaks[..left_cursor]
+                    .iter()
+                    .chain(core::iter::once(&seed))
+                    .chain(other.peaks[right_cursor..].iter())
+                    .cloned()
+                    .collect(),
+            ),
+            scheme: self.scheme,
+            max_end: self.max_end,
+            strict: self.strict,
+            key: self.key,
+            hasher: PhantomData,
+        };
+        if merged.strict {
+            merged.validate()?;
+        }
+        Ok(merged)
+    }
+
+    /// Splits the MMR at the leaf `at` into the bordering MMRs over `[start, at)` and
+    /// `[at, end)`, the inverse of `merge`. Both halves keep the scheme, limit, strictness
+    /// and key.
+    ///
+    /// The peaks of the halves are nodes below the peaks of the MMR, which a stateless MMR
+    /// doesn't hold, so the caller passes them as `witness`: the peaks of `[start, at)`
+    /// followed by those of `[at, end)`, e.g. from `FullMMR::split_witness`. They are
+    /// checked by merging the halves back.
+    ///
+    /// # Returns
+    ///
+    /// `MMRError::LeafIndexOutOfRange` if `at` is outside `[start, end]`, and
+    /// `MMRError::InvalidWitness` if the witness doesn't merge back into the MMR.
+    pub fn split(&self, at: u64, witness: &[B256]) -> Result<(Self, Self), MMRError> {
+        if at < self.start() || at > self.end() {
+            return Err(MMRError::LeafIndexOutOfRange);
+        }
+        let num_left = get_expected_num_peaks(self.start(), at) as usize;
+        if witness.len() != num_left + get_expected_num_peaks(at, self.end()) as usize {
+            return Err(MMRError::InvalidWitness);
+        }
+        let (left, right) = witness.split_at(num_left);
+        let left = self.with_peaks(Peaks::new(self.start(), at, left.to_vec())?);
+        let right = self.with_peaks(Peaks::new(at, self.end(), right.to_vec())?);
+        if left.merge(&right)?.peaks != self.peaks {
+            return Err(MMRError::InvalidWitness);
+        }
+        Ok((left, right))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::hash::get_random_hash;
+    use crate::clock::ManualClock;
+    #[cfg(feature = "sha2")]
+    use crate::scheme::HasherId;
+    use crate::utils::{
+        bag::bag_peaks,
+        hash::{get_random_hash, hash_leaf_bytes, hash_to_parent},
+        hint::get_append_hints,
+        range::decompose,
+    };
     use alloy_primitives::{b256, U256};
+    use std::time::Duration;
 
     #[test]
     fn test_empty_mmr_creation() {
         let mmr = MMR::new();
-        assert_eq!(mmr.start, 0);
-        assert_eq!(mmr.end, 0);
-        assert_eq!(mmr.peaks.len(), 0);
+        assert_eq!(mmr.start(), 0);
+        assert_eq!(mmr.end(), 0);
+        assert_eq!(mmr.peaks().len(), 0);
         assert_eq!(mmr.size(), 0);
         // Empty MMR's root returns a zero hash.
         assert_eq!(mmr.get_root(), B256::ZERO);
@@ -222,6 +895,13 @@ mod tests {
         assert!(matches!(mmr.err().unwrap(), MMRError::InvalidNumberOfPeaks));
     }
 
+    #[test]
+    fn test_from_peaks() {
+        let mmr = MMR::from_leaves(&[get_random_hash(); 5]);
+        assert_eq!(MMR::from_peaks(mmr.as_peaks().clone()), mmr);
+        assert_eq!(mmr.as_peaks().root(), mmr.get_root());
+    }
+
     #[test]
     fn test_get_root() {
         let element = get_random_hash();
@@ -248,6 +928,166 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_current_decomposition() {
+        for start in [0, 1, 3, 8, 13] {
+            let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+            for _ in 0..40 {
+                let decomposition = mmr.current_decomposition();
+                assert_eq!(
+                    (decomposition.left(), decomposition.right()),
+                    decompose(start, mmr.end())
+                );
+                mmr.append(get_random_hash());
+            }
+        }
+        let left = MMR::from_leaves(&[get_random_hash(); 6]);
+        let right = MMR::from_params(6, 11, vec![get_random_hash(); 3]).unwrap();
+        let merged = left.merge(&right).unwrap();
+        assert_eq!(merged.current_decomposition(), Decomposition::new(0, 11));
+    }
+
+    #[test]
+    fn test_commit_bytes_roundtrip() {
+        let mmr = MMR::from_params(4, 7, vec![B256::repeat_byte(1), B256::repeat_byte(2)]).unwrap();
+        let bytes = mmr.to_commit_bytes();
+        assert_eq!(
+            &bytes[..16],
+            &[0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 7]
+        );
+        assert_eq!(bytes.len(), 16 + 64);
+        assert_eq!(MMR::from_commit_bytes(&bytes).unwrap(), mmr);
+
+        for start in [0, 5] {
+            let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+            for _ in 0..20 {
+                assert_eq!(MMR::from_commit_bytes(&mmr.to_commit_bytes()).unwrap(), mmr);
+                mmr.append(get_random_hash());
+            }
+        }
+
+        for len in [0, 15, 16, 48, 79, 81] {
+            let mut truncated = bytes.clone();
+            truncated.resize(len, 0);
+            assert!(matches!(
+                MMR::from_commit_bytes(&truncated),
+                Err(MMRError::InvalidEncoding)
+            ));
+        }
+        let mut reversed = bytes.clone();
+        reversed[7] = 8;
+        assert!(matches!(
+            MMR::from_commit_bytes(&reversed),
+            Err(MMRError::StartGreaterThanEnd)
+        ));
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let mmr = MMR::from_params(4, 7, vec![B256::repeat_byte(1), B256::repeat_byte(2)]).unwrap();
+        let bytes = mmr.to_bytes();
+        assert_eq!(
+            &bytes[..21],
+            &[1, 4, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]
+        );
+        assert_eq!(bytes.len(), 21 + 64);
+        assert_eq!(MMR::from_bytes(&bytes).unwrap(), mmr);
+
+        for start in [0, 5] {
+            let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+            for _ in 0..20 {
+                assert_eq!(MMR::from_bytes(&mmr.to_bytes()).unwrap(), mmr);
+                mmr.append(get_random_hash());
+            }
+        }
+
+        for len in [0, 1, 20, 21, 84] {
+            assert!(matches!(
+                MMR::from_bytes(&bytes[..len]),
+                Err(MMRError::InvalidEncoding)
+            ));
+        }
+        let mut unknown = bytes.clone();
+        unknown[0] = 2;
+        assert!(matches!(
+            MMR::from_bytes(&unknown),
+            Err(MMRError::InvalidEncoding)
+        ));
+        let mut wrong_count = bytes[..21].to_vec();
+        wrong_count[17] = 1;
+        wrong_count.extend_from_slice(B256::ZERO.as_slice());
+        assert!(matches!(
+            MMR::from_bytes(&wrong_count),
+            Err(MMRError::InvalidNumberOfPeaks)
+        ));
+        let mut huge = bytes[..17].to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            MMR::from_bytes(&huge),
+            Err(MMRError::TooManyPeaks)
+        ));
+    }
+
+    #[test]
+    fn test_bytes_roundtrip_with_hasher() {
+        use crate::utils::hash::Bn254Keccak256Hasher;
+
+        let leaves: Vec<B256> = (0..13).map(B256::repeat_byte).collect();
+        let mut mmr: MMR<Bn254Keccak256Hasher> = MMR::with_hasher();
+        mmr.append_batch(&leaves);
+        let decoded = MMR::<Bn254Keccak256Hasher>::from_bytes_with_hasher(&mmr.to_bytes()).unwrap();
+        assert_eq!(decoded, mmr);
+        assert_eq!(decoded.get_root(), mmr.get_root());
+        let decoded =
+            MMR::<Bn254Keccak256Hasher>::from_commit_bytes_with_hasher(&mmr.to_commit_bytes())
+                .unwrap();
+        assert_eq!(decoded, mmr);
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_sha256_mmr() {
+        use crate::proof::prove;
+        use crate::utils::hash::{compute_root_with, Sha256Hasher};
+
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mut mmr: MMR<Sha256Hasher> = MMR::with_hasher();
+        mmr.append_batch(&leaves);
+        assert_eq!(mmr.scheme().hasher, HasherId::Sha256);
+        assert_eq!(mmr.get_root(), compute_root_with::<Sha256Hasher>(&leaves));
+        assert_ne!(mmr.get_root(), MMR::from_leaves(&leaves).get_root());
+
+        let proof = prove(&mmr, &leaves, 6).unwrap();
+        assert!(proof.verify(&mmr, leaves[6]));
+        assert!(proof.verify_peaks_with::<Sha256Hasher, _>(
+            mmr.as_peaks(),
+            leaves[6],
+            mmr.get_root()
+        ));
+
+        let mut left: MMR<Sha256Hasher> = MMR::with_hasher();
+        left.append_batch(&leaves[..5]);
+        let mut right: MMR<Sha256Hasher> = MMR::from_peaks_with_hasher(Peaks::empty(5));
+        right.append_batch(&leaves[5..]);
+        assert_eq!(left.merge(&right).unwrap(), mmr);
+    }
+
+    #[test]
+    fn test_get_root_fast_path_matches_bagging() {
+        for (start, end) in [(0, 1), (0, 3), (0, 6), (1, 3), (1, 4), (3, 5), (4, 12)] {
+            let (left, right) = decompose(start, end);
+            let num_peaks = (left.count_ones() + right.count_ones()) as usize;
+            let mut peaks = vec![get_random_hash(); num_peaks];
+            let mmr = MMR::from_params(start, end, peaks.clone()).unwrap();
+            assert_eq!(mmr.get_root(), bag_peaks(left, right, &peaks));
+
+            // A zero peak is skipped when it is alone on its side.
+            peaks[0] = B256::ZERO;
+            let mmr = MMR::from_params(start, end, peaks.clone()).unwrap();
+            assert_eq!(mmr.get_root(), bag_peaks(left, right, &peaks));
+        }
+    }
+
     #[test]
     fn test_append_from_empty() {
         let mut mmr = MMR::new();
@@ -357,34 +1197,153 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_append_hinted() {
+        let leaves: Vec<B256> = (0..100).map(|_| get_random_hash()).collect();
+        let hints = get_append_hints(0, 0, leaves.len() as u64);
+        assert_eq!(
+            MMR::from_leaves_hinted(&leaves, &hints).unwrap(),
+            MMR::from_leaves(&leaves)
+        );
+
+        // Non-zero start.
+        let mut mmr = MMR::from_params(5, 5, vec![]).unwrap();
+        let mut expected = MMR::from_params(5, 5, vec![]).unwrap();
+        for (leaf, hint) in leaves
+            .iter()
+            .zip(get_append_hints(5, 5, leaves.len() as u64))
+        {
+            mmr.append_hinted(*leaf, &hint).unwrap();
+            expected.append(*leaf);
+        }
+        assert_eq!(mmr, expected);
+    }
+
+    #[test]
+    fn test_append_hinted_rejects_bad_hints() {
+        let mut mmr = MMR::from_leaves(&[get_random_hash(); 3]);
+        let bad_right = AppendHint {
+            right: 2,
+            merges: 0,
+        };
+        assert!(matches!(
+            mmr.append_hinted(get_random_hash(), &bad_right),
+            Err(MMRError::InvalidHint)
+        ));
+        let bad_merges = AppendHint {
+            right: 3,
+            merges: 1,
+        };
+        assert!(matches!(
+            mmr.append_hinted(get_random_hash(), &bad_merges),
+            Err(MMRError::InvalidHint)
+        ));
+        assert_eq!(mmr.end(), 3);
+
+        // Hints must line up with leaves.
+        assert!(matches!(
+            MMR::from_leaves_hinted(&[get_random_hash()], &[]),
+            Err(MMRError::InvalidHint)
+        ));
+    }
+
+    #[test]
+    fn test_from_leaves_with_progress() {
+        let leaves: Vec<B256> = (0..10).map(|_| get_random_hash()).collect();
+        let mut reports = vec![];
+        let mmr = MMR::from_leaves_with_progress(&leaves, 4, |progress| reports.push(progress));
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+
+        // Reported every 4 leaves, plus the final leaf.
+        let processed: Vec<u64> = reports.iter().map(|p| p.leaves_processed).collect();
+        assert_eq!(processed, vec![4, 8, 10]);
+        assert!(reports.iter().all(|p| p.total_leaves == 10));
+        // 10 leaves = 8 + 2, which takes 7 + 1 parent hashes.
+        assert_eq!(reports.last().unwrap().hashes, 8);
+        assert_eq!(reports.last().unwrap().fraction(), 1.0);
+        assert_eq!(reports.last().unwrap().eta, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_append_with_clock() {
+        let leaves: Vec<B256> = (0..10).map(|_| get_random_hash()).collect();
+        let clock = ManualClock::new(Duration::from_secs(100));
+        let mut reports = vec![];
+        MMR::new().append_with_clock(&leaves, 2, &clock, |progress| {
+            reports.push(progress);
+            clock.advance(Duration::from_secs(1));
+        });
+        // One second passes between reports, so two leaves take a second.
+        let elapsed: Vec<u64> = reports.iter().map(|p| p.elapsed.as_secs()).collect();
+        assert_eq!(elapsed, vec![0, 1, 2, 3, 4]);
+        // 4 leaves remain after 6 took 2 seconds.
+        assert_eq!(reports[2].eta.unwrap().as_millis(), 1333);
+    }
+
+    #[test]
+    fn test_append_cancellable() {
+        let leaves: Vec<B256> = (0..10).map(|_| get_random_hash()).collect();
+        let cancel = AtomicBool::new(false);
+        let mut mmr = MMR::new();
+        mmr.append_cancellable(&leaves[..3], &cancel).unwrap();
+
+        // A cancelled build keeps its progress and can be resumed.
+        cancel.store(true, Ordering::Relaxed);
+        assert!(matches!(
+            mmr.append_cancellable(&leaves[3..], &cancel),
+            Err(MMRError::Cancelled)
+        ));
+        assert_eq!(mmr.end(), 3);
+
+        cancel.store(false, Ordering::Relaxed);
+        let processed = mmr.end() as usize;
+        mmr.append_cancellable(&leaves[processed..], &cancel)
+            .unwrap();
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+    }
+
     #[test]
     fn test_merge_errors() {
         // Non-bordering MMRs error.
         let mmr1 = MMR::from_params(0, 1, vec![get_random_hash()]).unwrap();
         let mmr2 = MMR::from_params(2, 4, vec![get_random_hash()]).unwrap();
         assert!(matches!(mmr1.merge(&mmr2), Err(MMRError::MergeError)));
+    }
 
-        // Non-zero start MMRs error.
-        let mmr1 = MMR::from_params(1, 2, vec![get_random_hash()]).unwrap();
-        let mmr2 = MMR::from_params(2, 4, vec![get_random_hash()]).unwrap();
-        assert!(matches!(mmr1.merge(&mmr2), Err(MMRError::MergeError)));
+    #[test]
+    fn test_merge_non_zero_start() {
+        let leaves: Vec<B256> = (0..40).map(|_| get_random_hash()).collect();
+        let build = |start: usize, end: usize| {
+            let mut mmr = MMR::from_params(start as u64, start as u64, vec![]).unwrap();
+            mmr.append_batch(&leaves[start..end]);
+            mmr
+        };
+        for start in 0..20 {
+            for mid in start..30 {
+                for end in mid..40 {
+                    let merged = build(start, mid).merge(&build(mid, end)).unwrap();
+                    assert_eq!(merged, build(start, end));
+                    assert_eq!(
+                        merged.current_decomposition(),
+                        Decomposition::new(start as u64, end as u64)
+                    );
+                }
+            }
+        }
+
+        // Middle shards merge with each other before reaching the first one.
+        let middle = build(7, 13).merge(&build(13, 29)).unwrap();
+        let all = build(0, 7).merge(&middle).unwrap().merge(&build(29, 40));
+        assert_eq!(all.unwrap(), MMR::from_leaves(&leaves));
     }
 
     #[test]
     fn test_merge() {
         let element_1 = get_random_hash();
-        let mmr1 = MMR {
-            start: 0,
-            end: 4,
-            peaks: vec![element_1],
-        };
+        let mmr1 = MMR::from_peaks(Peaks::new(0, 4, vec![element_1]).unwrap());
 
         let element_2 = get_random_hash();
-        let mmr2 = MMR {
-            start: 4,
-            end: 8,
-            peaks: vec![element_2],
-        };
+        let mmr2 = MMR::from_peaks(Peaks::new(4, 8, vec![element_2]).unwrap());
 
         assert_eq!(
             mmr1.merge(&mmr2).unwrap(),
@@ -392,17 +1351,548 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merge_matches_from_leaves() {
+        // Empty sides.
+        let leaves: Vec<B256> = (0..5).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        assert_eq!(MMR::new().merge(&mmr).unwrap(), mmr);
+        let empty = MMR::from_params(5, 5, vec![]).unwrap();
+        assert_eq!(mmr.merge(&empty).unwrap(), mmr);
+
+        // Left merges move the seed's range start, which later right merges depend on.
+        for end in 1..40 {
+            let leaves: Vec<B256> = (0..end).map(|_| get_random_hash()).collect();
+            for split in 1..end {
+                let left = MMR::from_leaves(&leaves[..split]);
+                let mut right = MMR::from_params(split as u64, split as u64, vec![]).unwrap();
+                for leaf in &leaves[split..] {
+                    right.append(*leaf);
+                }
+                assert_eq!(left.merge(&right).unwrap(), MMR::from_leaves(&leaves));
+            }
+        }
+    }
+
     #[test]
     fn test_from_leaves() {
         let leaves = vec![get_random_hash(), get_random_hash(), get_random_hash()];
         let mmr = MMR::from_leaves(&leaves);
         assert_eq!(
             mmr,
-            MMR {
-                start: 0,
-                end: 3,
-                peaks: vec![hash_to_parent(&leaves[0], &leaves[1]), leaves[2]],
+            MMR::from_peaks(
+                Peaks::new(
+                    0,
+                    3,
+                    vec![hash_to_parent(&leaves[0], &leaves[1]), leaves[2]]
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_max_end() {
+        let mut mmr = MMR::new().with_max_end(3);
+        for _ in 0..3 {
+            mmr.try_append(get_random_hash()).unwrap();
+        }
+        assert!(matches!(
+            mmr.try_append(get_random_hash()),
+            Err(MMRError::RangeLimitReached)
+        ));
+        assert_eq!(mmr.end(), 3);
+
+        let cancel = AtomicBool::new(false);
+        let mut mmr = MMR::new().with_max_end(2);
+        assert!(matches!(
+            mmr.append_cancellable(&[get_random_hash(); 3], &cancel),
+            Err(MMRError::RangeLimitReached)
+        ));
+        assert_eq!(mmr.end(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "MMR has reached its maximum end")]
+    fn test_append_past_max_end_panics() {
+        let mut mmr = MMR::new().with_max_end(0);
+        mmr.append(get_random_hash());
+    }
+
+    #[test]
+    fn test_append_at_u64_max() {
+        let mut mmr = MMR::from_params(u64::MAX - 1, u64::MAX - 1, vec![]).unwrap();
+        mmr.try_append(get_random_hash()).unwrap();
+        assert_eq!(mmr.end(), u64::MAX);
+        assert!(matches!(
+            mmr.try_append(get_random_hash()),
+            Err(MMRError::IndexOverflow)
+        ));
+        assert_eq!(mmr.end(), u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Leaf index would overflow u64")]
+    fn test_append_past_u64_max_panics() {
+        let mut mmr = MMR::from_params(u64::MAX, u64::MAX, vec![]).unwrap();
+        mmr.append(get_random_hash());
+    }
+
+    #[test]
+    fn test_merge_max_end() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mut right = MMR::from_params(3, 3, vec![]).unwrap();
+        for leaf in &leaves[3..] {
+            right.append(*leaf);
+        }
+
+        let left = MMR::from_leaves(&leaves[..3]).with_max_end(7);
+        assert!(matches!(
+            left.merge(&right),
+            Err(MMRError::RangeLimitReached)
+        ));
+
+        let merged = left.with_max_end(8).merge(&right).unwrap();
+        assert_eq!(merged, MMR::from_leaves(&leaves));
+        assert_eq!(merged.max_end(), Some(8));
+        assert_eq!(
+            MMR::new()
+                .with_max_end(8)
+                .merge(&MMR::new())
+                .unwrap()
+                .max_end(),
+            Some(8)
+        );
+    }
+
+    #[test]
+    fn test_strict_rejects_zero_leaves() {
+        let mut mmr = MMR::new().with_strict();
+        mmr.try_append(get_random_hash()).unwrap();
+        assert!(matches!(
+            mmr.try_append(B256::ZERO),
+            Err(MMRError::ZeroLeaf)
+        ));
+        let cancel = AtomicBool::new(false);
+        assert!(matches!(
+            mmr.append_cancellable(&[get_random_hash(), B256::ZERO], &cancel),
+            Err(MMRError::ZeroLeaf)
+        ));
+        assert_eq!(mmr.end(), 2);
+
+        // Zero leaves are still accepted by default.
+        MMR::new().try_append(B256::ZERO).unwrap();
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum DeviceError {
+        Unavailable,
+        Mmr(MMRError),
+    }
+
+    impl From<MMRError> for DeviceError {
+        fn from(err: MMRError) -> Self {
+            DeviceError::Mmr(err)
+        }
+    }
+
+    /// Keccak256 on a device that goes away for the `fail_at`-th hash only.
+    struct FlakyDevice {
+        hashed: usize,
+        fail_at: usize,
+    }
+
+    impl TryHasher for FlakyDevice {
+        type Error = DeviceError;
+
+        fn try_hash_to_parent(&mut self, left: &B256, right: &B256) -> Result<B256, DeviceError> {
+            self.hashed += 1;
+            if self.hashed == self.fail_at {
+                return Err(DeviceError::Unavailable);
             }
+            Ok(hash_to_parent(left, right))
+        }
+    }
+
+    #[test]
+    fn test_try_append_with() {
+        let leaves: Vec<B256> = (0..20).map(|_| get_random_hash()).collect();
+        let mut device = FlakyDevice {
+            hashed: 0,
+            fail_at: 10,
+        };
+        let mut mmr = MMR::new().with_strict();
+        for leaf in &leaves {
+            let before = mmr.clone();
+            match mmr.try_append_with(&mut device, *leaf) {
+                Ok(()) => {}
+                Err(err) => {
+                    // A failed hash leaves the MMR as it was, so the append can be retried.
+                    assert_eq!(err, DeviceError::Unavailable);
+                    assert_eq!(mmr, before);
+                    mmr.try_append_with(&mut device, *leaf).unwrap();
+                }
+            }
+        }
+        assert!(device.hashed >= device.fail_at);
+        assert_eq!(mmr.get_root(), MMR::from_leaves(&leaves).get_root());
+
+        // Leaves are checked before any hashing.
+        assert_eq!(
+            mmr.try_append_with(&mut device, B256::ZERO),
+            Err(DeviceError::Mmr(MMRError::ZeroLeaf))
+        );
+        assert_eq!(mmr.end(), 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "Zero leaves are rejected in strict mode")]
+    fn test_strict_append_panics() {
+        MMR::new().with_strict().append(B256::ZERO);
+    }
+
+    #[test]
+    fn test_strict_rejects_zero_peaks() {
+        let mmr = MMR::from_params(0, 3, vec![get_random_hash(), B256::ZERO]).unwrap();
+        assert!(mmr.validate().is_ok());
+        assert!(matches!(
+            mmr.clone().with_strict().validate(),
+            Err(MMRError::ZeroPeak)
+        ));
+
+        // [0, 3) + [3, 5) keeps the zero peak of leaf 4 as is.
+        let right = MMR::from_params(3, 5, vec![get_random_hash(), B256::ZERO]).unwrap();
+        let left = MMR::from_leaves(&[get_random_hash(); 3]).with_strict();
+        assert!(matches!(left.merge(&right), Err(MMRError::ZeroPeak)));
+        assert!(matches!(
+            MMR::new().with_strict().merge(&mmr),
+            Err(MMRError::ZeroPeak)
+        ));
+    }
+
+    #[test]
+    fn test_strict_rejects_duplicate_peaks() {
+        // [3, 4) and [4, 5) are both single leaves.
+        let peak = get_random_hash();
+        let mmr = MMR::from_params(3, 5, vec![peak, peak]).unwrap();
+        assert!(mmr.validate().is_ok());
+        assert!(matches!(
+            mmr.clone().into_strict(),
+            Err(MMRError::DuplicatePeaks)
+        ));
+        assert!(matches!(
+            MMR::from_bytes(&mmr.to_bytes()).unwrap().into_strict(),
+            Err(MMRError::DuplicatePeaks)
+        ));
+        let zero = MMR::from_params(0, 3, vec![peak, B256::ZERO]).unwrap();
+        assert!(matches!(zero.into_strict(), Err(MMRError::ZeroPeak)));
+
+        // Equal peaks of different heights are left alone.
+        let mmr = MMR::from_params(2, 5, vec![peak, peak]).unwrap();
+        assert!(mmr.into_strict().unwrap().is_strict());
+    }
+
+    #[test]
+    fn test_append_batch_matches_appends() {
+        let leaves: Vec<B256> = (0..70).map(|_| get_random_hash()).collect();
+        for start in [0, 1, 3, 5, 8, 13] {
+            for len in 0..leaves.len() {
+                let mut appended = MMR::from_params(start, start, vec![]).unwrap();
+                for leaf in &leaves[..len] {
+                    appended.append(*leaf);
+                }
+                // Batches of every size, split anywhere.
+                let mut batched = MMR::from_params(start, start, vec![]).unwrap();
+                batched.append_batch(&leaves[..len / 3]);
+                batched.append_batch(&leaves[len / 3..len]);
+                assert_eq!(batched, appended);
+                assert_eq!(
+                    batched.current_decomposition(),
+                    appended.current_decomposition()
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "MMR has reached its maximum end")]
+    fn test_append_batch_past_max_end_panics() {
+        MMR::new()
+            .with_max_end(3)
+            .append_batch(&[get_random_hash(); 4]);
+    }
+
+    #[test]
+    fn test_extend_and_collect() {
+        let leaves: Vec<B256> = (0..19).map(|_| get_random_hash()).collect();
+        let collected: MMR = leaves.iter().copied().collect();
+        assert_eq!(collected, MMR::from_leaves(&leaves));
+
+        let mut extended = MMR::from_leaves(&leaves[..4]);
+        extended.extend(leaves[4..10].iter().copied());
+        extended.extend(&leaves[10..]);
+        assert_eq!(extended, MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_height_indexed_peaks() {
+        for start in 0..20 {
+            for end in start..40 {
+                let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+                mmr.append_batch(&(start..end).map(|_| get_random_hash()).collect::<Vec<_>>());
+                let view = mmr.height_indexed();
+                let peaks = mmr.peaks_ordered(PeakOrder::LeafOrder);
+                let (left, _) = decompose(start, end);
+                for height in 0..70 {
+                    let expected = |side: &[Peak]| {
+                        side.iter()
+                            .find(|peak| peak.height == height)
+                            .map(|peak| peak.hash)
+                    };
+                    let (left_peaks, right_peaks) = peaks.split_at(left.count_ones() as usize);
+                    assert_eq!(view.left(height).copied(), expected(left_peaks));
+                    assert_eq!(view.right(height).copied(), expected(right_peaks));
+                    assert_eq!(
+                        mmr.peak_at_height(height),
+                        view.left(height).or(view.right(height))
+                    );
+                }
+            }
+        }
+
+        // Zero-starting MMRs have one peak per set bit of their size.
+        let mmr = MMR::from_leaves(&(0..11).map(|_| get_random_hash()).collect::<Vec<_>>());
+        assert_eq!(mmr.peak_at_height(3), Some(&mmr.peaks()[0]));
+        assert_eq!(mmr.peak_at_height(2), None);
+        assert_eq!(mmr.peak_at_height(1), Some(&mmr.peaks()[1]));
+        assert_eq!(mmr.peak_at_height(0), Some(&mmr.peaks()[2]));
+    }
+
+    #[test]
+    fn test_split() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves).with_max_end(100).with_strict();
+        for at in 0..=23 {
+            let left = MMR::from_leaves(&leaves[..at]);
+            let mut right = MMR::from_params(at as u64, at as u64, vec![]).unwrap();
+            right.append_batch(&leaves[at..]);
+            let witness: Vec<B256> = left.peaks().iter().chain(right.peaks()).copied().collect();
+
+            let (split_left, split_right) = mmr.split(at as u64, &witness).unwrap();
+            assert_eq!(split_left, left);
+            assert_eq!(split_right, right);
+            // Equality ignores the limit and strictness, which both halves keep.
+            for half in [&split_left, &split_right] {
+                assert_eq!(half.max_end(), Some(100));
+                assert!(half.is_strict());
+            }
+            assert_eq!(split_left.merge(&split_right).unwrap(), mmr);
+
+            if !witness.is_empty() {
+                let mut tampered = witness.clone();
+                tampered[0] = get_random_hash();
+                assert!(matches!(
+                    mmr.split(at as u64, &tampered),
+                    Err(MMRError::InvalidWitness)
+                ));
+            }
+            assert!(matches!(
+                mmr.split(at as u64, &witness[1..]),
+                Err(MMRError::InvalidWitness)
+            ));
+        }
+        assert!(matches!(
+            mmr.split(24, &[]),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_append_batch_range() {
+        let leaves: Vec<B256> = (0..12).map(|_| get_random_hash()).collect();
+        let (mut mmr, range) = MMR::from_leaves_with_range(&leaves[..5]);
+        assert_eq!(
+            range,
+            Some(AppendedRange {
+                first_index: 0,
+                last_index: 4,
+                new_root: MMR::from_leaves(&leaves[..5]).get_root(),
+            })
+        );
+
+        let range = mmr.append_batch(&leaves[5..]).unwrap();
+        assert_eq!((range.first_index, range.last_index), (5, 11));
+        assert_eq!(range.new_root, MMR::from_leaves(&leaves).get_root());
+        assert_eq!(mmr.append_batch(&[]), None);
+        assert_eq!(mmr.end(), 12);
+    }
+
+    #[test]
+    fn test_peak_for_leaf() {
+        // [3, 13) has peaks covering [3], [4, 8), [8, 12) and [12].
+        let mut mmr = MMR::from_params(3, 3, vec![]).unwrap();
+        for _ in 3..13 {
+            mmr.append(get_random_hash());
+        }
+        assert_eq!(mmr.peak_for_leaf(2), None);
+        assert_eq!(
+            mmr.peak_for_leaf(3),
+            Some(PeakInfo {
+                peak_index: 0,
+                peak_hash: mmr.peaks()[0],
+                height: 0,
+                local_offset: 0
+            })
+        );
+        assert_eq!(
+            mmr.peak_for_leaf(10),
+            Some(PeakInfo {
+                peak_index: 2,
+                peak_hash: mmr.peaks()[2],
+                height: 2,
+                local_offset: 2
+            })
+        );
+        assert_eq!(mmr.peak_for_leaf(12).unwrap().peak_index, 3);
+        assert_eq!(mmr.peak_for_leaf(13), None);
+    }
+
+    #[test]
+    fn test_peaks_ordered() {
+        // [3, 13) has peaks covering [3], [4, 8), [8, 12) and [12].
+        let mut mmr = MMR::from_params(3, 3, vec![]).unwrap();
+        for _ in 3..13 {
+            mmr.append(get_random_hash());
+        }
+        let peaks = mmr.peaks();
+
+        let ordered = mmr.peaks_ordered(PeakOrder::LeafOrder);
+        assert!(ordered.iter().map(|peak| &peak.hash).eq(peaks.iter()));
+        assert_eq!(
+            ordered.iter().map(|peak| peak.height).collect::<Vec<_>>(),
+            vec![0, 2, 2, 0]
+        );
+
+        let ordered = mmr.peaks_ordered(PeakOrder::HeightDescending);
+        assert_eq!(
+            ordered,
+            vec![
+                Peak {
+                    hash: peaks[1],
+                    height: 2
+                },
+                Peak {
+                    hash: peaks[2],
+                    height: 2
+                },
+                Peak {
+                    hash: peaks[0],
+                    height: 0
+                },
+                Peak {
+                    hash: peaks[3],
+                    height: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_bytes() {
+        let records: [&[u8]; 3] = [b"ab", b"c", b""];
+        let mut mmr = MMR::new();
+        mmr.append_bytes(records[0]);
+        let range = mmr.append_bytes_batch(&records[1..]).unwrap();
+        assert_eq!((range.first_index, range.last_index), (1, 2));
+
+        let leaves: Vec<B256> = records
+            .iter()
+            .map(|record| hash_leaf_bytes(record))
+            .collect();
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+
+        // Regrouping the same bytes changes the root.
+        let mut regrouped = MMR::new();
+        regrouped.append_bytes_batch(&[&b"a"[..], b"bc", b""]);
+        assert_ne!(regrouped.get_root(), mmr.get_root());
+    }
+
+    #[test]
+    fn test_merge_scheme_mismatch() {
+        use crate::scheme::LeafPolicy;
+        let prefixed = SchemeDescriptor {
+            leaf_policy: LeafPolicy::LengthPrefixed,
+            ..SchemeDescriptor::DEFAULT
+        };
+        let left = MMR::from_leaves(&[get_random_hash(); 2]);
+        let mut right = MMR::from_params(2, 2, vec![])
+            .unwrap()
+            .with_scheme(prefixed);
+        right.append_bytes(b"record");
+        assert!(matches!(left.merge(&right), Err(MMRError::SchemeMismatch)));
+        assert!(left.with_scheme(prefixed).merge(&right).is_ok());
+    }
+
+    #[test]
+    fn test_keyed_hashing() {
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let key = get_random_hash();
+        let keyed = MMR::new().with_key(key);
+        assert!(keyed.is_keyed());
+        assert_eq!(keyed.scheme().hasher, HasherId::KeyedKeccak256);
+
+        let mut first = keyed.clone();
+        first.append_batch(&leaves);
+        let mut second = keyed;
+        for leaf in &leaves {
+            second.append(*leaf);
+        }
+        // The key, not the order of appends, determines the root.
+        assert_eq!(first.get_root(), second.get_root());
+        assert_ne!(first.get_root(), MMR::from_leaves(&leaves).get_root());
+
+        let mut other_key = MMR::new().with_key(get_random_hash());
+        other_key.append_batch(&leaves);
+        assert_ne!(first.get_root(), other_key.get_root());
+        assert_ne!(first, other_key);
+    }
+
+    #[test]
+    fn test_keyed_merge() {
+        let leaves: Vec<B256> = (0..9).map(|_| get_random_hash()).collect();
+        let key = get_random_hash();
+        let mut whole = MMR::new().with_key(key);
+        whole.append_batch(&leaves);
+
+        let mut left = MMR::new().with_key(key);
+        left.append_batch(&leaves[..5]);
+        let mut right = MMR::from_params(5, 5, vec![]).unwrap().with_key(key);
+        right.append_batch(&leaves[5..]);
+        assert_eq!(left.merge(&right).unwrap(), whole);
+        let witness = [left.peaks(), right.peaks()].concat();
+        assert_eq!(whole.split(5, &witness).unwrap(), (left.clone(), right));
+
+        // MMRs hashed under different keys don't merge.
+        let mut foreign = MMR::from_params(5, 5, vec![])
+            .unwrap()
+            .with_key(get_random_hash());
+        foreign.append_batch(&leaves[5..]);
+        assert!(matches!(
+            left.merge(&foreign),
+            Err(MMRError::SchemeMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_keyed_append_bytes() {
+        let key = get_random_hash();
+        let mut keyed = MMR::new().with_key(key);
+        keyed.append_bytes(b"record");
+        assert_eq!(
+            keyed.peaks(),
+            [HashKey::new(key).hash_leaf_bytes(b"record")]
         );
+        assert_ne!(keyed.peaks(), [hash_leaf_bytes(b"record")]);
+        assert_eq!(format!("{:?}", HashKey::new(key)), "HashKey(..)");
     }
 }
@@ -0,0 +1,425 @@
+use crate::compact::CompactRange;
+use crate::consistency::ConsistencyProof;
+use crate::error::MMRError;
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use crate::proof::InclusionProof;
+use crate::range_proof::RangeProof;
+use crate::sequence::verify_append_sequence;
+use alloy_primitives::B256;
+
+/// The trusted roots a [`MmrProof`] is verified against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofContext {
+    /// The root of the MMR the proof is about, or of the newer one for proofs relating two
+    pub root: B256,
+    /// The root of the older MMR, for proofs relating two
+    pub old_root: Option<B256>,
+}
+
+/// Any proof about an MMR, carrying the untrusted data it needs, so that transport layers
+/// handle a single format.
+///
+/// Every kind is verified against trusted roots only: the peaks of a proven MMR travel with
+/// the proof and are checked to bag to the trusted root.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MmrProof {
+    /// `leaf` is included in `range`, whose root is `root`.
+    Inclusion {
+        proof: InclusionProof,
+        leaf: B256,
+        range: CompactRange,
+    },
+    /// The MMR with root `root` extends `old`, whose root is `old_root`.
+    Consistency {
+        proof: ConsistencyProof,
+        old: CompactRange,
+    },
+    /// Appending `leaves` to `witness`, whose root is `old_root`, gives `root`.
+    Append {
+        witness: CompactRange,
+        leaves: Vec<B256>,
+    },
+    /// `leaves` are the contiguous leaves of `range` from `proof.range_start()`, and the
+    /// root of `range` is `root`.
+    Range {
+        proof: RangeProof,
+        leaves: Vec<B256>,
+        range: CompactRange,
+    },
+}
+
+impl MmrProof {
+    /// Discriminant of `MmrProof::Inclusion` in the encoding
+    pub const INCLUSION: u8 = 0;
+    /// Discriminant of `MmrProof::Consistency` in the encoding
+    pub const CONSISTENCY: u8 = 1;
+    /// Discriminant of `MmrProof::Append` in the encoding
+    pub const APPEND: u8 = 2;
+    /// Discriminant of `MmrProof::Range` in the encoding
+    pub const RANGE: u8 = 3;
+
+    /// Returns the stable discriminant of the proof kind
+    pub fn discriminant(&self) -> u8 {
+        match self {
+            MmrProof::Inclusion { .. } => Self::INCLUSION,
+            MmrProof::Consistency { .. } => Self::CONSISTENCY,
+            MmrProof::Append { .. } => Self::APPEND,
+            MmrProof::Range { .. } => Self::RANGE,
+        }
+    }
+
+    /// Verifies the proof against `context`; proofs relating two MMRs fail without an
+    /// `old_root`
+    pub fn verify(&self, context: &ProofContext) -> bool {
+        match self {
+            MmrProof::Inclusion { proof, leaf, range } => {
+                proof.verify_root(range.begin, range.end, &range.hashes, *leaf, context.root)
+            }
+            MmrProof::Consistency { proof, old } => {
+                let Ok(old) = MMR::try_from(old.clone()) else {
+                    return false;
+                };
+                context.old_root == Some(old.get_root()) && proof.verify(&old, context.root)
+            }
+            MmrProof::Append { witness, leaves } => context.old_root.is_some_and(|old_root| {
+                verify_append_sequence(old_root, leaves, context.root, witness).is_ok()
+            }),
+            MmrProof::Range {
+                proof,
+                leaves,
+                range,
+            } => {
+                let Ok(mmr) = MMR::try_from(range.clone()) else {
+                    return false;
+                };
+                mmr.get_root() == context.root && proof.verify(&mmr, leaves)
+            }
+        }
+    }
+
+    /// Returns an upper bound on the hashes `verify` computes, from the sizes of the proof's
+    /// parts only, so that oversized proofs can be rejected before any hashing
+    pub fn max_hashes(&self) -> u64 {
+        // Bagging n peaks takes n - 1 hashes. Merging or appending into them adds one peak
+        // per hash saved, so the new root takes at most one hash per input minus one.
+        let bag = |peaks: usize| peaks.saturating_sub(1) as u64;
+        match self {
+            MmrProof::Inclusion { proof, range, .. } => {
+                proof.path().count() as u64 + bag(range.hashes.len())
+            }
+            MmrProof::Consistency { proof, old } => {
+                bag(old.hashes.len()) + proof.max_hashes(old.hashes.len())
+            }
+            MmrProof::Append { witness, leaves } => {
+                bag(witness.hashes.len()) + bag(witness.hashes.len() + leaves.len())
+            }
+            // Rebuilding the peaks merges the given subtrees and leaves down to at least one.
+            MmrProof::Range {
+                proof,
+                leaves,
+                range,
+            } => {
+                bag(range.hashes.len())
+                    + bag(proof.left().len() + leaves.len() + proof.right().len())
+            }
+        }
+    }
+
+    /// Verifies like `verify`, unless it could take more than `max_hashes` hashes, in which
+    /// case it fails with `MMRError::BudgetExceeded` before hashing
+    pub fn verify_with_budget(
+        &self,
+        context: &ProofContext,
+        max_hashes: u64,
+    ) -> Result<bool, MMRError> {
+        if self.max_hashes() > max_hashes {
+            return Err(MMRError::BudgetExceeded);
+        }
+        Ok(self.verify(context))
+    }
+
+    /// Encodes the proof as its discriminant followed by its payload, with integers as
+    /// big-endian bytes and the compact range last, in the layout of `CompactRange::to_bytes`:
+    ///
+    /// * Inclusion: leaf, leaf index (u64), path length (u32), path, range.
+    /// * Consistency: old end, new end (u64), peak count (u32), peaks, old range.
+    /// * Append: leaf count (u32), leaves, witness.
+    /// * Range: range start (u64), left and right subtree counts (u32) each followed by the
+    ///   subtrees, leaf count (u32), leaves, range.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.discriminant()];
+        let range = match self {
+            MmrProof::Inclusion { proof, leaf, range } => {
+                bytes.extend_from_slice(leaf.as_slice());
+                bytes.extend_from_slice(&proof.leaf_index().to_be_bytes());
+                put_hashes(&mut bytes, &proof.path().copied().collect::<Vec<_>>());
+                range
+            }
+            MmrProof::Consistency { proof, old } => {
+                bytes.extend_from_slice(&proof.old_end().to_be_bytes());
+                bytes.extend_from_slice(&proof.new_end().to_be_bytes());
+                put_hashes(&mut bytes, proof.peaks());
+                old
+            }
+            MmrProof::Append { witness, leaves } => {
+                put_hashes(&mut bytes, leaves);
+                witness
+            }
+            MmrProof::Range {
+                proof,
+                leaves,
+                range,
+            } => {
+                bytes.extend_from_slice(&proof.range_start().to_be_bytes());
+                put_hashes(&mut bytes, proof.left());
+                put_hashes(&mut bytes, proof.right());
+                put_hashes(&mut bytes, leaves);
+                range
+            }
+        };
+        bytes.extend_from_slice(&range.to_bytes());
+        bytes
+    }
+
+    /// Decodes a proof encoded by `to_bytes`, within `DecodeLimits::DEFAULT`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MMRError> {
+        Self::from_bytes_with_limits(bytes, &DecodeLimits::DEFAULT)
+    }
+
+    /// Decodes a proof encoded by `to_bytes`, rejecting paths, peak lists and leaf batches
+    /// over `limits` from their length prefixes; the subtrees of a range proof count as peaks
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, MMRError> {
+        let (&discriminant, mut rest) = bytes.split_first().ok_or(MMRError::InvalidEncoding)?;
+        let proof = match discriminant {
+            Self::INCLUSION => {
+                let leaf = take_hash(&mut rest)?;
+                let leaf_index = take_u64(&mut rest)?;
+                let path = take_hashes(&mut rest, |len| limits.check_path(len))?;
+                MmrProof::Inclusion {
+                    proof: InclusionProof::new(leaf_index, path),
+                    leaf,
+                    range: CompactRange::from_bytes_with_limits(rest, limits)?,
+                }
+            }
+            Self::CONSISTENCY => {
+                let old_end = take_u64(&mut rest)?;
+                let new_end = take_u64(&mut rest)?;
+                let peaks = take_hashes(&mut rest, |len| limits.check_peaks(len))?;
+                MmrProof::Consistency {
+                    proof: ConsistencyProof::new(old_end, new_end, peaks),
+                    old: CompactRange::from_bytes_with_limits(rest, limits)?,
+                }
+            }
+            Self::APPEND => {
+                let leaves = take_hashes(&mut rest, |len| limits.check_leaves(len))?;
+                MmrProof::Append {
+                    witness: CompactRange::from_bytes_with_limits(rest, limits)?,
+                    leaves,
+                }
+            }
+            Self::RANGE => {
+                let range_start = take_u64(&mut rest)?;
+                let left = take_hashes(&mut rest, |len| limits.check_peaks(len))?;
+                let right = take_hashes(&mut rest, |len| limits.check_peaks(len))?;
+                let leaves = take_hashes(&mut rest, |len| limits.check_leaves(len))?;
+                MmrProof::Range {
+                    proof: RangeProof::new(range_start, left, right),
+                    leaves,
+                    range: CompactRange::from_bytes_with_limits(rest, limits)?,
+                }
+            }
+            _ => return Err(MMRError::InvalidEncoding),
+        };
+        Ok(proof)
+    }
+}
+
+fn put_hashes(bytes: &mut Vec<u8>, hashes: &[B256]) {
+    bytes.extend_from_slice(&(hashes.len() as u32).to_be_bytes());
+    for hash in hashes {
+        bytes.extend_from_slice(hash.as_slice());
+    }
+}
+
+fn take_u64(bytes: &mut &[u8]) -> Result<u64, MMRError> {
+    let (value, rest) = bytes
+        .split_first_chunk::<8>()
+        .ok_or(MMRError::InvalidEncoding)?;
+    *bytes = rest;
+    Ok(u64::from_be_bytes(*value))
+}
+
+fn take_hash(bytes: &mut &[u8]) -> Result<B256, MMRError> {
+    let (hash, rest) = bytes
+        .split_first_chunk::<32>()
+        .ok_or(MMRError::InvalidEncoding)?;
+    *bytes = rest;
+    Ok(B256::from(*hash))
+}
+
+/// Takes a length-prefixed list of hashes, checking the length with `check` before taking any
+fn take_hashes(
+    bytes: &mut &[u8],
+    check: impl FnOnce(usize) -> Result<(), MMRError>,
+) -> Result<Vec<B256>, MMRError> {
+    let (len, rest) = bytes
+        .split_first_chunk::<4>()
+        .ok_or(MMRError::InvalidEncoding)?;
+    *bytes = rest;
+    let len = u32::from_be_bytes(*len) as usize;
+    check(len)?;
+    if bytes.len() / 32 < len {
+        return Err(MMRError::InvalidEncoding);
+    }
+    (0..len).map(|_| take_hash(bytes)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consistency::PeakIndex;
+    use crate::proof::prove;
+    use crate::range_proof::prove_range;
+    use crate::utils::hash::get_random_hash;
+
+    fn proofs() -> (Vec<B256>, Vec<(MmrProof, ProofContext)>) {
+        let leaves: Vec<B256> = (0..16).map(|_| get_random_hash()).collect();
+        let old = MMR::from_leaves(&leaves[..8]);
+        let new = MMR::from_leaves(&leaves);
+        let context = ProofContext {
+            root: new.get_root(),
+            old_root: Some(old.get_root()),
+        };
+
+        let mut index = PeakIndex::new(2, 4);
+        let mut mmr = MMR::new();
+        for leaf in &leaves {
+            let trace = mmr.append_traced(*leaf);
+            index.record(&mmr, &trace);
+        }
+
+        let proofs = vec![
+            MmrProof::Inclusion {
+                proof: prove(&new, &leaves, 5).unwrap(),
+                leaf: leaves[5],
+                range: CompactRange::from(&new),
+            },
+            MmrProof::Consistency {
+                proof: index.prove_consistency(8, 16).unwrap(),
+                old: CompactRange::from(&old),
+            },
+            MmrProof::Append {
+                witness: CompactRange::from(&old),
+                leaves: leaves[8..].to_vec(),
+            },
+            MmrProof::Range {
+                proof: prove_range(&new, &leaves, 3, 9).unwrap(),
+                leaves: leaves[3..9].to_vec(),
+                range: CompactRange::from(&new),
+            },
+        ];
+        (
+            leaves,
+            proofs.into_iter().map(|proof| (proof, context)).collect(),
+        )
+    }
+
+    #[test]
+    fn test_verify() {
+        let (_, proofs) = proofs();
+        for (proof, context) in &proofs {
+            assert!(proof.verify(context));
+            let wrong_root = ProofContext {
+                root: get_random_hash(),
+                ..*context
+            };
+            assert!(!proof.verify(&wrong_root));
+        }
+        // Only inclusion and range proofs are about a single MMR.
+        for (proof, context) in &proofs {
+            let no_old_root = ProofContext {
+                old_root: None,
+                ..*context
+            };
+            assert_eq!(
+                proof.verify(&no_old_root),
+                matches!(proof.discriminant(), MmrProof::INCLUSION | MmrProof::RANGE)
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_with_budget() {
+        let (_, proofs) = proofs();
+        // A path of 4 into a single peak; a single merge; 8 leaves onto a perfect tree of 8;
+        // 6 leaves between subtrees of 2 and 1 leaves on the left and 1, 2 and 4 on the right.
+        let bounds: Vec<u64> = proofs.iter().map(|(proof, _)| proof.max_hashes()).collect();
+        assert_eq!(bounds, vec![4, 1, 8, 10]);
+        for (proof, context) in &proofs {
+            let max_hashes = proof.max_hashes();
+            assert!(proof.verify_with_budget(context, max_hashes).unwrap());
+            assert!(matches!(
+                proof.verify_with_budget(context, max_hashes - 1),
+                Err(MMRError::BudgetExceeded)
+            ));
+        }
+
+        // Padding a proof raises its bound even though it would fail anyway.
+        let MmrProof::Inclusion { proof, leaf, range } = &proofs[0].0 else {
+            unreachable!()
+        };
+        let mut path: Vec<B256> = proof.path().copied().collect();
+        path.resize(1000, B256::ZERO);
+        let padded = MmrProof::Inclusion {
+            proof: InclusionProof::new(proof.leaf_index(), path),
+            leaf: *leaf,
+            range: range.clone(),
+        };
+        assert!(matches!(
+            padded.verify_with_budget(&proofs[0].1, 64),
+            Err(MMRError::BudgetExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_encoding_roundtrip() {
+        let (_, proofs) = proofs();
+        for (i, (proof, _)) in proofs.iter().enumerate() {
+            let bytes = proof.to_bytes();
+            assert_eq!(bytes[0] as usize, i);
+            assert_eq!(&MmrProof::from_bytes(&bytes).unwrap(), proof);
+            for len in [0, 1, 20, 40] {
+                assert!(MmrProof::from_bytes(&bytes[..len]).is_err());
+            }
+        }
+        assert!(MmrProof::from_bytes(&[4]).is_err());
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        // A 4G length prefix is rejected by the limit, not by running out of bytes.
+        let mut bytes = vec![MmrProof::APPEND];
+        bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            MmrProof::from_bytes(&bytes),
+            Err(MMRError::TooManyLeaves)
+        ));
+
+        let (_, proofs) = proofs();
+        let limits = DecodeLimits {
+            max_peaks: 0,
+            max_path_len: 1,
+            max_leaves: 1,
+        };
+        for ((proof, _), expected) in proofs.iter().zip([
+            MMRError::PathTooLong,
+            MMRError::TooManyPeaks,
+            MMRError::TooManyLeaves,
+            MMRError::TooManyPeaks,
+        ]) {
+            let err = MmrProof::from_bytes_with_limits(&proof.to_bytes(), &limits).unwrap_err();
+            assert_eq!(err.to_string(), expected.to_string());
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::error::MMRError;
+use crate::utils::{
+    bag::bag_peaks_by,
+    hash::{HashKey, Hasher, Keccak256Hasher},
+    range::{decompose, get_expected_num_peaks, MAX_PEAKS},
+};
+use alloc::{vec, vec::Vec};
+use alloy_primitives::B256;
+use core::{convert::Infallible, ops::Deref};
+
+/// The peaks of an MMR over `[start, end)`, in the order of the leaves they cover.
+///
+/// The number of peaks is checked against the decomposition of the range once, on
+/// construction, so code holding a `Peaks` never has to validate it again. Like
+/// `InclusionProof`, the hashes live in any `AsRef<[B256]>` container, so untrusted peaks
+/// can be checked in place without copying them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peaks<P = Vec<B256>> {
+    start: u64,
+    end: u64,
+    hashes: P,
+}
+
+impl<P: AsRef<[B256]>> Peaks<P> {
+    /// Wraps the peaks of `[start, end)`, checking that `start <= end` and that there is
+    /// one hash per peak of the range
+    pub fn new(start: u64, end: u64, hashes: P) -> Result<Self, MMRError> {
+        if start > end {
+            return Err(MMRError::StartGreaterThanEnd);
+        }
+        if get_expected_num_peaks(start, end) != hashes.as_ref().len() as u64 {
+            return Err(MMRError::InvalidNumberOfPeaks);
+        }
+        Ok(Self { start, end, hashes })
+    }
+
+    /// Returns the start index of the range
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the end index of the range
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns the peak hashes, in leaf order
+    pub fn as_slice(&self) -> &[B256] {
+        self.hashes.as_ref()
+    }
+
+    /// Bags the peaks into the root of the range with Keccak256, zero if it is empty
+    pub fn root(&self) -> B256 {
+        self.root_with::<Keccak256Hasher>()
+    }
+
+    /// Like `root`, hashing with `H`
+    pub fn root_with<H: Hasher>(&self) -> B256 {
+        if self.is_empty() {
+            return H::empty_root();
+        }
+        self.root_by(H::hash_parent)
+    }
+
+    /// Like `root`, for peaks hashed with `key`, see `MMR::with_key`
+    pub fn root_keyed(&self, key: &HashKey) -> B256 {
+        self.root_by(|left, right| key.hash_to_parent(left, right))
+    }
+
+    /// Like `root`, hashing parents with `hash`.
+    pub(crate) fn root_by(&self, hash: impl Fn(&B256, &B256) -> B256) -> B256 {
+        // Power-of-two sizes and their small neighbours dominate root queries, so one and two
+        // peaks skip the decomposition. Two peaks always bag into their parent hash, except
+        // that `bag_peaks` skips a zero root on either side.
+        match *self.as_slice() {
+            [] => return B256::ZERO,
+            [peak] => return peak,
+            [first, second] if first != B256::ZERO && second != B256::ZERO => {
+                return hash(&first, &second);
+            }
+            _ => {}
+        }
+
+        let (left, right) = decompose(self.start, self.end);
+        bag_peaks_by(left, right, self.as_slice(), hash)
+    }
+
+    /// Returns the hash container
+    pub fn into_hashes(self) -> P {
+        self.hashes
+    }
+
+    /// Copies the peaks into a fixed-capacity buffer, for callers that can't allocate
+    pub fn to_fixed(&self) -> Peaks<PeakBuf> {
+        Peaks {
+            start: self.start,
+            end: self.end,
+            hashes: PeakBuf::from_slice(self.as_slice())
+                .expect("a range never has more than MAX_PEAKS peaks"),
+        }
+    }
+}
+
+/// Inline storage for up to `MAX_PEAKS` peaks, enough for any range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeakBuf {
+    len: u8,
+    hashes: [B256; MAX_PEAKS],
+}
+
+impl PeakBuf {
+    /// Copies `hashes` into a buffer, or returns `None` if there are more than `MAX_PEAKS`
+    pub fn from_slice(hashes: &[B256]) -> Option<Self> {
+        if hashes.len() > MAX_PEAKS {
+            return None;
+        }
+        let mut buf = Self {
+            len: hashes.len() as u8,
+            hashes: [B256::ZERO; MAX_PEAKS],
+        };
+        buf.hashes[..hashes.len()].copy_from_slice(hashes);
+        Some(buf)
+    }
+}
+
+impl AsRef<[B256]> for PeakBuf {
+    fn as_ref(&self) -> &[B256] {
+        &self.hashes[..self.len as usize]
+    }
+}
+
+impl<P: AsRef<[B256]>> Deref for Peaks<P> {
+    type Target = [B256];
+
+    fn deref(&self) -> &[B256] {
+        self.as_slice()
+    }
+}
+
+impl Peaks<&[B256]> {
+    /// Copies borrowed peaks into ones that own their hashes
+    pub fn into_owned(self) -> Peaks {
+        Peaks {
+            start: self.start,
+            end: self.end,
+            hashes: self.hashes.to_vec(),
+        }
+    }
+}
+
+impl Peaks {
+    /// Creates the peaks of the empty range starting at `start`
+    pub fn empty(start: u64) -> Self {
+        Self {
+            start,
+            end: start,
+            hashes: vec![],
+        }
+    }
+
+    /// Folds `element` into the last `merges` peaks, hashing parents with `hash`, and moves
+    /// the end past it, returning the number of hashes computed.
+    ///
+    /// `merges` must be the merge count of an append at the current end, as given by the
+    /// range's decomposition; the peak count then stays consistent with the new range.
+    pub(crate) fn append_merged(
+        &mut self,
+        element: B256,
+        merges: usize,
+        hash: impl Fn(&B256, &B256) -> B256,
+    ) -> u64 {
+        self.append_subtree(element, 1, merges, hash)
+    }
+
+    /// Like `append_merged`, for the root of a perfect subtree of `size` leaves starting at
+    /// the current end
+    pub(crate) fn append_subtree(
+        &mut self,
+        root: B256,
+        size: u64,
+        merges: usize,
+        hash: impl Fn(&B256, &B256) -> B256,
+    ) -> u64 {
+        match self.try_append_subtree(root, size, merges, |left, right| {
+            Ok::<_, Infallible>(hash(left, right))
+        }) {
+            Ok(hashes) => hashes,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Like `append_merged`, with a fallible `hash`; on error the peaks are left unchanged
+    pub(crate) fn try_append_merged<E>(
+        &mut self,
+        element: B256,
+        merges: usize,
+        hash: impl FnMut(&B256, &B256) -> Result<B256, E>,
+    ) -> Result<u64, E> {
+        self.try_append_subtree(element, 1, merges, hash)
+    }
+
+    fn try_append_subtree<E>(
+        &mut self,
+        root: B256,
+        size: u64,
+        merges: usize,
+        mut hash: impl FnMut(&B256, &B256) -> Result<B256, E>,
+    ) -> Result<u64, E> {
+        let peaks_to_keep = self.hashes.len().saturating_sub(merges);
+        let new_peak = self.hashes[peaks_to_keep..]
+            .iter()
+            .try_rfold(root, |acc, &peak| hash(&peak, &acc))?;
+        let hashes = (self.hashes.len() - peaks_to_keep) as u64;
+
+        self.hashes.truncate(peaks_to_keep);
+        self.hashes.push(new_peak);
+        self.end += size;
+        debug_assert!(self.hashes.len() <= MAX_PEAKS);
+        debug_assert_eq!(
+            get_expected_num_peaks(self.start, self.end),
+            self.hashes.len() as u64,
+            "peak count diverged from the range decomposition"
+        );
+        Ok(hashes)
+    }
+
+    /// Assembles peaks whose count is known to match `[start, end)`, e.g. the result of
+    /// merging two valid ranges
+    pub(crate) fn new_unchecked(start: u64, end: u64, hashes: Vec<B256>) -> Self {
+        debug_assert_eq!(get_expected_num_peaks(start, end), hashes.len() as u64);
+        Self { start, end, hashes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::{get_random_hash, hash_to_parent};
+
+    #[test]
+    fn test_new_checks_range() {
+        let hash = get_random_hash();
+        assert!(matches!(
+            Peaks::new(1, 0, vec![]),
+            Err(MMRError::StartGreaterThanEnd)
+        ));
+        assert!(matches!(
+            Peaks::new(0, 5, vec![hash]),
+            Err(MMRError::InvalidNumberOfPeaks)
+        ));
+        assert!(matches!(
+            Peaks::new(0, 3, vec![hash; 3]),
+            Err(MMRError::InvalidNumberOfPeaks)
+        ));
+        let peaks = Peaks::new(0, 3, vec![hash; 2]).unwrap();
+        assert_eq!((peaks.start(), peaks.end(), peaks.len()), (0, 3, 2));
+    }
+
+    #[test]
+    fn test_borrowed_peaks() {
+        let hashes = [get_random_hash(), get_random_hash()];
+        let borrowed = Peaks::new(1, 3, &hashes[..]).unwrap();
+        assert_eq!(borrowed.root(), hash_to_parent(&hashes[0], &hashes[1]));
+        assert_eq!(
+            borrowed.into_owned(),
+            Peaks::new(1, 3, hashes.to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fixed_capacity() {
+        let hashes = vec![get_random_hash(); MAX_PEAKS];
+        let peaks = Peaks::new(1, u64::MAX, hashes).unwrap();
+        let fixed = peaks.to_fixed();
+        assert_eq!(fixed.as_slice(), peaks.as_slice());
+        assert_eq!(fixed.root(), peaks.root());
+        assert!(PeakBuf::from_slice(&[B256::ZERO; MAX_PEAKS + 1]).is_none());
+        assert_eq!(Peaks::empty(7).to_fixed().len(), 0);
+    }
+
+    #[test]
+    fn test_append_merged() {
+        let leaves: Vec<B256> = (0..3).map(|_| get_random_hash()).collect();
+        let mut peaks = Peaks::empty(0);
+        assert_eq!(peaks.root(), B256::ZERO);
+        assert_eq!(peaks.append_merged(leaves[0], 0, hash_to_parent), 0);
+        assert_eq!(peaks.append_merged(leaves[1], 1, hash_to_parent), 1);
+        assert_eq!(peaks.append_merged(leaves[2], 0, hash_to_parent), 0);
+        assert_eq!(
+            peaks,
+            Peaks::new(
+                0,
+                3,
+                vec![hash_to_parent(&leaves[0], &leaves[1]), leaves[2]]
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_root_keyed() {
+        let key = get_random_hash();
+        let mut mmr = crate::MMR::new().with_key(key);
+        mmr.append_batch(&(0..11).map(|_| get_random_hash()).collect::<Vec<_>>());
+        let peaks = mmr.as_peaks();
+        assert_eq!(peaks.root_keyed(&HashKey::new(key)), mmr.get_root());
+        assert_ne!(peaks.root(), mmr.get_root());
+    }
+}
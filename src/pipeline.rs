@@ -0,0 +1,201 @@
+use crate::compact::CompactRange;
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::sequence::verify_append_sequence;
+use alloy_primitives::B256;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+/// The claim proven for one chunk of a pipelined append: appending `leaves` to the MMR
+/// `witness`, whose root is `old_root`, gives `new_root`.
+///
+/// These are the inputs of the `verify_append` SP1 guest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkWitness {
+    /// The position of the chunk in the append
+    pub index: usize,
+    pub old_root: B256,
+    /// The MMR before the chunk
+    pub witness: CompactRange,
+    pub leaves: Vec<B256>,
+    pub new_root: B256,
+}
+
+impl ChunkWitness {
+    /// Checks the claim natively, as the guest does
+    pub fn verify(&self) -> Result<(), MMRError> {
+        verify_append_sequence(self.old_root, &self.leaves, self.new_root, &self.witness)
+    }
+}
+
+/// Sizing of [`append_and_prove`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineConfig {
+    /// The number of leaves per chunk
+    pub chunk_size: usize,
+    /// The number of prover threads
+    pub workers: usize,
+    /// The number of witnesses waiting for a worker before merklization blocks
+    pub queue_depth: usize,
+}
+
+/// Appends `leaves` to `mmr` chunk by chunk, proving each chunk while the next ones are
+/// merklized.
+///
+/// Witnesses go to a pool of `workers` threads through a queue of `queue_depth`, so that
+/// merklization never runs more than the queue and the busy workers ahead of proving. Once
+/// a chunk fails to prove, no further chunk is merklized or proven.
+///
+/// # Arguments
+///
+/// * `mmr` - The MMR to append to, updated only if every chunk is proven.
+/// * `leaves` - The leaves to append.
+/// * `config` - The chunk size, number of workers and queue depth; zeros are raised to one,
+///   except for the queue depth.
+/// * `prove` - Proves one chunk, called from the worker threads.
+///
+/// # Returns
+///
+/// The proofs in chunk order, or the error of the first failed chunk.
+pub fn append_and_prove<R, E, P>(
+    mmr: &mut MMR,
+    leaves: &[B256],
+    config: PipelineConfig,
+    prove: P,
+) -> Result<Vec<R>, E>
+where
+    R: Send,
+    E: Send,
+    P: Fn(ChunkWitness) -> Result<R, E> + Sync,
+{
+    let (witness_tx, witness_rx) = mpsc::sync_channel::<ChunkWitness>(config.queue_depth);
+    let (result_tx, result_rx) = mpsc::channel();
+    let witness_rx = Mutex::new(witness_rx);
+    let failed = AtomicBool::new(false);
+    let mut building = mmr.clone();
+
+    thread::scope(|scope| {
+        for _ in 0..config.workers.max(1) {
+            let result_tx = result_tx.clone();
+            let (witness_rx, failed, prove) = (&witness_rx, &failed, &prove);
+            scope.spawn(move || loop {
+                let Ok(witness) = witness_rx.lock().unwrap().recv() else {
+                    break;
+                };
+                // Keep draining after a failure, so a blocked producer can finish.
+                if failed.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let index = witness.index;
+                let result = prove(witness);
+                if result.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                let _ = result_tx.send((index, result));
+            });
+        }
+        drop(result_tx);
+
+        for (index, chunk) in leaves.chunks(config.chunk_size.max(1)).enumerate() {
+            if failed.load(Ordering::Relaxed) {
+                break;
+            }
+            let witness = CompactRange::from(&building);
+            let old_root = building.get_root();
+            building.append_batch(chunk);
+            let witness = ChunkWitness {
+                index,
+                old_root,
+                witness,
+                leaves: chunk.to_vec(),
+                new_root: building.get_root(),
+            };
+            // Blocks while the queue is full.
+            if witness_tx.send(witness).is_err() {
+                break;
+            }
+        }
+        drop(witness_tx);
+    });
+
+    let mut results: Vec<(usize, Result<R, E>)> = result_rx.into_iter().collect();
+    results.sort_by_key(|(index, _)| *index);
+    let proofs = results
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect::<Result<Vec<R>, E>>()?;
+    *mmr = building;
+    Ok(proofs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    const CONFIG: PipelineConfig = PipelineConfig {
+        chunk_size: 4,
+        workers: 3,
+        queue_depth: 2,
+    };
+
+    #[test]
+    fn test_append_and_prove() {
+        let leaves: Vec<B256> = (0..37).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::from_params(5, 5, vec![]).unwrap();
+        let witnesses = append_and_prove(&mut mmr, &leaves, CONFIG, |witness| {
+            witness.verify().map(|_| witness)
+        })
+        .unwrap();
+
+        assert_eq!(witnesses.len(), 10);
+        let mut expected = MMR::from_params(5, 5, vec![]).unwrap();
+        for (index, witness) in witnesses.iter().enumerate() {
+            assert_eq!(witness.index, index);
+            assert_eq!(witness.old_root, expected.get_root());
+            expected.append_batch(&leaves[4 * index..(4 * index + 4).min(37)]);
+            assert_eq!(witness.new_root, expected.get_root());
+        }
+        assert_eq!(mmr, expected);
+    }
+
+    #[test]
+    fn test_failed_chunk_stops_the_pipeline() {
+        let leaves: Vec<B256> = (0..400).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::new();
+        let proven = AtomicUsize::new(0);
+        let result = append_and_prove(&mut mmr, &leaves, CONFIG, |witness| {
+            proven.fetch_add(1, Ordering::Relaxed);
+            if witness.index == 2 {
+                return Err(witness.index);
+            }
+            thread::sleep(Duration::from_millis(1));
+            Ok(())
+        });
+        assert_eq!(result, Err(2));
+        assert_eq!(mmr, MMR::new());
+        // Chunks merklized before the failure was noticed are drained unproven.
+        assert!(proven.load(Ordering::Relaxed) < 100);
+    }
+
+    #[test]
+    fn test_workers_run_concurrently() {
+        let leaves: Vec<B256> = (0..64).map(|_| get_random_hash()).collect();
+        let (running, max_running) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let mut mmr = MMR::new();
+        append_and_prove(&mut mmr, &leaves, CONFIG, |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        let max_running = max_running.load(Ordering::SeqCst);
+        assert!((1..=CONFIG.workers).contains(&max_running));
+        assert_eq!(mmr, MMR::from_leaves(&leaves));
+    }
+}
@@ -0,0 +1,12 @@
+pub use crate::compact::CompactRange;
+pub use crate::consistency::ConsistencyProof;
+pub use crate::error::MMRError;
+pub use crate::full::FullMMR;
+pub use crate::mmr::{AppendedRange, MMR};
+pub use crate::mmr_proof::{MmrProof, ProofContext};
+pub use crate::peaks::Peaks;
+pub use crate::proof::{prove, InclusionProof};
+pub use crate::range_proof::{prove_range, RangeProof};
+pub use crate::scheme::SchemeDescriptor;
+pub use crate::utils::hash::{compute_root, Hasher, Keccak256Hasher};
+pub use alloy_primitives::B256;
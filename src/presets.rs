@@ -0,0 +1,235 @@
+use crate::error::MMRError;
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::proof::{prove, InclusionProof};
+use alloy_primitives::{keccak256, Keccak256, B256};
+
+/// Anchors application state roots, such as the state roots of a rollup's blocks, into an
+/// MMR.
+///
+/// Each leaf is `keccak256(domain || height || state_root)`, with the height as 8
+/// big-endian bytes and `domain` the keccak256 of an application tag. The domain keeps
+/// anchors of different applications from being confused when they share an MMR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateAnchor {
+    domain: B256,
+}
+
+impl StateAnchor {
+    /// Creates an anchor scheme for the application identified by `tag`
+    pub fn new(tag: &[u8]) -> Self {
+        Self {
+            domain: keccak256(tag),
+        }
+    }
+
+    /// Returns the domain separator hashed into every leaf
+    pub fn domain(&self) -> B256 {
+        self.domain
+    }
+
+    /// Returns the leaf anchoring `state_root` at `height`
+    pub fn leaf(&self, height: u64, state_root: B256) -> B256 {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.domain.as_slice());
+        hasher.update(height.to_be_bytes());
+        hasher.update(state_root.as_slice());
+        hasher.finalize()
+    }
+
+    /// Appends the anchor of `state_root` at `height`, returning its leaf index
+    pub fn append(&self, mmr: &mut MMR, height: u64, state_root: B256) -> u64 {
+        let index = mmr.end();
+        mmr.append(self.leaf(height, state_root));
+        index
+    }
+
+    /// Generates a proof that `state_root` is anchored at `height` by the leaf at
+    /// `leaf_index`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mmr` - The MMR the proof is generated against.
+    /// * `leaves` - All leaves of `mmr`, i.e. those at indices `mmr.start()..mmr.end()`.
+    /// * `leaf_index` - The index of the anchor's leaf.
+    /// * `height` - The anchored height.
+    /// * `state_root` - The anchored state root.
+    ///
+    /// # Returns
+    ///
+    /// The proof, or `MMRError::InvalidLeaves` if the leaf doesn't anchor `state_root` at
+    /// `height`.
+    pub fn prove(
+        &self,
+        mmr: &MMR,
+        leaves: &[B256],
+        leaf_index: u64,
+        height: u64,
+        state_root: B256,
+    ) -> Result<AnchorProof, MMRError> {
+        let proof = prove(mmr, leaves, leaf_index)?;
+        if leaves[(leaf_index - mmr.start()) as usize] != self.leaf(height, state_root) {
+            return Err(MMRError::InvalidLeaves);
+        }
+        Ok(AnchorProof {
+            height,
+            state_root,
+            proof,
+        })
+    }
+}
+
+/// Proof that a state root is anchored at a height, which decodes back to both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnchorProof {
+    pub height: u64,
+    pub state_root: B256,
+    pub proof: InclusionProof,
+}
+
+impl AnchorProof {
+    /// Verifies the anchor against `mmr`
+    pub fn verify(&self, anchor: &StateAnchor, mmr: &MMR) -> bool {
+        self.proof
+            .verify(mmr, anchor.leaf(self.height, self.state_root))
+    }
+
+    /// Verifies the anchor against untrusted `peaks` that must bag to the trusted `root`
+    pub fn verify_root<P: AsRef<[B256]>>(
+        &self,
+        anchor: &StateAnchor,
+        peaks: &Peaks<P>,
+        root: B256,
+    ) -> bool {
+        self.proof
+            .verify_peaks(peaks, anchor.leaf(self.height, self.state_root), root)
+    }
+
+    /// Encodes the proof as `height || state_root || leaf_index || path length || path`,
+    /// with integers as big-endian bytes and the path length as 4 bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let path: Vec<B256> = self.proof.path().copied().collect();
+        let mut bytes = Vec::with_capacity(52 + 32 * path.len());
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        bytes.extend_from_slice(self.state_root.as_slice());
+        bytes.extend_from_slice(&self.proof.leaf_index().to_be_bytes());
+        bytes.extend_from_slice(&(path.len() as u32).to_be_bytes());
+        for sibling in &path {
+            bytes.extend_from_slice(sibling.as_slice());
+        }
+        bytes
+    }
+
+    /// Decodes a proof encoded by `to_bytes`, within `DecodeLimits::DEFAULT`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MMRError> {
+        Self::from_bytes_with_limits(bytes, &DecodeLimits::DEFAULT)
+    }
+
+    /// Decodes a proof encoded by `to_bytes`, or returns `MMRError::PathTooLong` if its path
+    /// is longer than `limits.max_path_len`
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, MMRError> {
+        let (height, rest) = bytes
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (state_root, rest) = rest
+            .split_first_chunk::<32>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (leaf_index, rest) = rest
+            .split_first_chunk::<8>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let (len, path) = rest
+            .split_first_chunk::<4>()
+            .ok_or(MMRError::InvalidEncoding)?;
+        let len = u32::from_be_bytes(*len) as usize;
+        limits.check_path(len)?;
+        if path.len() != 32 * len {
+            return Err(MMRError::InvalidEncoding);
+        }
+        Ok(Self {
+            height: u64::from_be_bytes(*height),
+            state_root: B256::from(*state_root),
+            proof: InclusionProof::new(
+                u64::from_be_bytes(*leaf_index),
+                path.chunks_exact(32).map(B256::from_slice).collect(),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    fn anchored(anchor: &StateAnchor) -> (MMR, Vec<B256>, Vec<B256>) {
+        let state_roots: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::new();
+        for (height, state_root) in (100..).zip(&state_roots) {
+            anchor.append(&mut mmr, height, *state_root);
+        }
+        let leaves = (100..)
+            .zip(&state_roots)
+            .map(|(height, state_root)| anchor.leaf(height, *state_root))
+            .collect();
+        (mmr, leaves, state_roots)
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let anchor = StateAnchor::new(b"rollup");
+        let (mmr, leaves, state_roots) = anchored(&anchor);
+        for (index, state_root) in state_roots.iter().enumerate() {
+            let height = 100 + index as u64;
+            let proof = anchor
+                .prove(&mmr, &leaves, index as u64, height, *state_root)
+                .unwrap();
+            assert!(proof.verify(&anchor, &mmr));
+            assert!(proof.verify_root(&anchor, mmr.as_peaks(), mmr.get_root()));
+
+            let decoded = AnchorProof::from_bytes(&proof.to_bytes()).unwrap();
+            assert_eq!((decoded.height, decoded.state_root), (height, *state_root));
+            assert_eq!(decoded, proof);
+
+            // Another height, another root or another application don't verify.
+            let moved = AnchorProof {
+                height: height + 1,
+                ..proof.clone()
+            };
+            assert!(!moved.verify(&anchor, &mmr));
+            let swapped = AnchorProof {
+                state_root: get_random_hash(),
+                ..proof.clone()
+            };
+            assert!(!swapped.verify(&anchor, &mmr));
+            assert!(!proof.verify(&StateAnchor::new(b"other"), &mmr));
+        }
+    }
+
+    #[test]
+    fn test_prove_rejects_wrong_anchor() {
+        let anchor = StateAnchor::new(b"rollup");
+        let (mmr, leaves, state_roots) = anchored(&anchor);
+        assert!(matches!(
+            anchor.prove(&mmr, &leaves, 3, 104, state_roots[3]),
+            Err(MMRError::InvalidLeaves)
+        ));
+        assert!(matches!(
+            anchor.prove(&mmr, &leaves, 13, 113, state_roots[3]),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_truncation() {
+        let anchor = StateAnchor::new(b"rollup");
+        let (mmr, leaves, state_roots) = anchored(&anchor);
+        let bytes = anchor
+            .prove(&mmr, &leaves, 5, 105, state_roots[5])
+            .unwrap()
+            .to_bytes();
+        for len in [0, 8, 40, 48, bytes.len() - 1] {
+            assert!(AnchorProof::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+}
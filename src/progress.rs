@@ -0,0 +1,78 @@
+use crate::clock::Clock;
+use core::time::Duration;
+
+/// Snapshot of a long-running build, passed to progress callbacks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuildProgress {
+    /// Number of leaves appended so far.
+    pub leaves_processed: u64,
+    /// Number of leaves the build will append in total.
+    pub total_leaves: u64,
+    /// Number of parent hashes computed so far.
+    pub hashes: u64,
+    /// Time since the build started.
+    pub elapsed: Duration,
+    /// Estimated time until the build completes, extrapolated from the rate so far.
+    pub eta: Option<Duration>,
+}
+
+impl BuildProgress {
+    /// Returns the completed fraction of the build, between 0 and 1
+    pub fn fraction(&self) -> f64 {
+        if self.total_leaves == 0 {
+            return 1.0;
+        }
+        self.leaves_processed as f64 / self.total_leaves as f64
+    }
+}
+
+/// Accumulates counters for a build and decides when to report them.
+pub(crate) struct ProgressTracker<'a> {
+    clock: &'a dyn Clock,
+    started: Duration,
+    total_leaves: u64,
+    interval: u64,
+    leaves_processed: u64,
+    hashes: u64,
+}
+
+impl<'a> ProgressTracker<'a> {
+    /// Creates a tracker reporting every `interval` leaves, and always on the last one,
+    /// timing the build with `clock`
+    pub(crate) fn new(total_leaves: u64, interval: u64, clock: &'a dyn Clock) -> Self {
+        Self {
+            clock,
+            started: clock.now(),
+            total_leaves,
+            interval: interval.max(1),
+            leaves_processed: 0,
+            hashes: 0,
+        }
+    }
+
+    /// Records one appended leaf and the hashes it took, returning a snapshot when one is due
+    pub(crate) fn record(&mut self, hashes: u64) -> Option<BuildProgress> {
+        self.leaves_processed += 1;
+        self.hashes += hashes;
+        if self.leaves_processed % self.interval == 0 || self.leaves_processed == self.total_leaves
+        {
+            Some(self.snapshot())
+        } else {
+            None
+        }
+    }
+
+    fn snapshot(&self) -> BuildProgress {
+        let elapsed = self.clock.now().saturating_sub(self.started);
+        let remaining = self.total_leaves.saturating_sub(self.leaves_processed);
+        let eta = (self.leaves_processed > 0)
+            .then(|| elapsed.mul_f64(remaining as f64 / self.leaves_processed as f64));
+        BuildProgress {
+            leaves_processed: self.leaves_processed,
+            total_leaves: self.total_leaves,
+            hashes: self.hashes,
+            elapsed,
+            eta,
+        }
+    }
+}
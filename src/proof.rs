@@ -0,0 +1,467 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::utils::{
+    hash::{hash_subtree_by, Hasher, Keccak256Hasher},
+    range::locate_leaf,
+};
+use alloc::vec::Vec;
+use alloy_primitives::B256;
+
+/// Proof that a leaf is included under one of the peaks of an MMR.
+///
+/// The sibling path is stored in any `AsRef<[B256]>` container, so proofs can either own
+/// their path (`Vec<B256>`, the default) or borrow a caller-provided buffer, keeping
+/// verification allocation-free in the zkVM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InclusionProof<P = Vec<B256>> {
+    leaf_index: u64,
+    path: P,
+}
+
+impl<P: AsRef<[B256]>> InclusionProof<P> {
+    /// Creates a proof from a leaf index and its sibling path, ordered from the leaf upwards
+    pub fn new(leaf_index: u64, path: P) -> Self {
+        Self { leaf_index, path }
+    }
+
+    /// Returns the index of the proven leaf
+    pub fn leaf_index(&self) -> u64 {
+        self.leaf_index
+    }
+
+    /// Returns the sibling path, ordered from the leaf upwards
+    pub fn path(&self) -> impl Iterator<Item = &B256> + '_ {
+        self.path.as_ref().iter()
+    }
+
+    /// Computes the peak committing to `leaf` at this proof's index.
+    ///
+    /// `local_offset` is the leaf's offset within its peak's subtree; bit `i` tells whether
+    /// the node at height `i` is a right child. Past 64 siblings, nodes are left children.
+    pub fn compute_peak(&self, leaf: B256, local_offset: u64) -> B256 {
+        self.compute_peak_with::<Keccak256Hasher>(leaf, local_offset)
+    }
+
+    /// Like `compute_peak`, hashing with `H`
+    pub fn compute_peak_with<H: Hasher>(&self, leaf: B256, local_offset: u64) -> B256 {
+        self.compute_peak_by(leaf, local_offset, H::hash_parent)
+    }
+
+    /// Like `compute_peak`, hashing parents with `hash`.
+    fn compute_peak_by(
+        &self,
+        leaf: B256,
+        local_offset: u64,
+        hash: impl Fn(&B256, &B256) -> B256,
+    ) -> B256 {
+        self.path()
+            .enumerate()
+            .fold(leaf, |node, (height, sibling)| {
+                if local_offset.checked_shr(height as u32).unwrap_or(0) & 1 == 0 {
+                    hash(&node, sibling)
+                } else {
+                    hash(sibling, &node)
+                }
+            })
+    }
+
+    /// Verifies that `leaf` is included in `mmr` at this proof's index
+    pub fn verify<H: Hasher>(&self, mmr: &MMR<H>, leaf: B256) -> bool {
+        let Some(peak) = mmr.peak_for_leaf(self.leaf_index) else {
+            return false;
+        };
+        if self.path.as_ref().len() != peak.height as usize {
+            return false;
+        }
+        self.compute_peak_by(leaf, peak.local_offset, |left, right| {
+            mmr.hash_to_parent(left, right)
+        }) == peak.peak_hash
+    }
+
+    /// Verifies like `verify`, unless checking the path would take more than `max_hashes`
+    /// hashes, in which case it fails with `MMRError::BudgetExceeded` before hashing
+    pub fn verify_with_budget<H: Hasher>(
+        &self,
+        mmr: &MMR<H>,
+        leaf: B256,
+        max_hashes: u64,
+    ) -> Result<bool, MMRError> {
+        if self.path.as_ref().len() as u64 > max_hashes {
+            return Err(MMRError::BudgetExceeded);
+        }
+        Ok(self.verify(mmr, leaf))
+    }
+
+    /// Verifies that `leaf` is included in the MMR over `[start, end)` with the given root.
+    ///
+    /// `peaks` are the (untrusted) peaks of that MMR, as supplied by the prover: they must
+    /// bag to `root`, and the leaf's path must lead to the corresponding peak.
+    pub fn verify_root(
+        &self,
+        start: u64,
+        end: u64,
+        peaks: &[B256],
+        leaf: B256,
+        root: B256,
+    ) -> bool {
+        Peaks::new(start, end, peaks).is_ok_and(|peaks| self.verify_peaks(&peaks, leaf, root))
+    }
+
+    /// Verifies that `leaf` is included under `peaks`, and that they bag to `root`
+    pub fn verify_peaks<Q: AsRef<[B256]>>(&self, peaks: &Peaks<Q>, leaf: B256, root: B256) -> bool {
+        self.verify_peaks_with::<Keccak256Hasher, Q>(peaks, leaf, root)
+    }
+
+    /// Like `verify_peaks`, hashing with `H`
+    pub fn verify_peaks_with<H: Hasher, Q: AsRef<[B256]>>(
+        &self,
+        peaks: &Peaks<Q>,
+        leaf: B256,
+        root: B256,
+    ) -> bool {
+        let Some((peak_index, height, peak_begin)) =
+            locate_leaf(peaks.start(), peaks.end(), self.leaf_index)
+        else {
+            return false;
+        };
+        if self.path.as_ref().len() != height as usize
+            || self.compute_peak_with::<H>(leaf, self.leaf_index - peak_begin) != peaks[peak_index]
+        {
+            return false;
+        }
+        peaks.root_with::<H>() == root
+    }
+}
+
+impl InclusionProof<&[B256]> {
+    /// Copies a borrowed proof into one that owns its path
+    pub fn into_owned(self) -> InclusionProof {
+        InclusionProof::new(self.leaf_index, self.path.to_vec())
+    }
+}
+
+/// Generates an inclusion proof into a caller-provided buffer, without allocating.
+///
+/// # Arguments
+///
+/// * `mmr` - The MMR the proof is generated against.
+/// * `leaves` - All leaves of `mmr`, i.e. those at indices `mmr.start()..mmr.end()`.
+/// * `leaf_index` - The index of the leaf to prove.
+/// * `buf` - The buffer the sibling path is written into. 64 entries always suffice.
+///
+/// # Returns
+///
+/// A proof borrowing the used prefix of `buf`.
+pub fn prove_into<'a, H: Hasher>(
+    mmr: &MMR<H>,
+    leaves: &[B256],
+    leaf_index: u64,
+    buf: &'a mut [B256],
+) -> Result<InclusionProof<&'a [B256]>, MMRError> {
+    if leaves.len() as u64 != mmr.size() {
+        return Err(MMRError::InvalidLeaves);
+    }
+    let peak = mmr
+        .peak_for_leaf(leaf_index)
+        .ok_or(MMRError::LeafIndexOutOfRange)?;
+    let height = peak.height as usize;
+    if buf.len() < height {
+        return Err(MMRError::BufferTooSmall);
+    }
+
+    let local_offset = peak.local_offset as usize;
+    let subtree_offset = (leaf_index - mmr.start()) as usize - local_offset;
+    let subtree = &leaves[subtree_offset..subtree_offset + (1 << height)];
+    let mut context = H::Context::default();
+    for (level, sibling) in buf[..height].iter_mut().enumerate() {
+        // The sibling at `level` is the subtree of 2^level leaves next to the leaf's ancestor.
+        let sibling_offset = ((local_offset >> level) ^ 1) << level;
+        *sibling = mmr.hash_subtree(
+            &mut context,
+            &subtree[sibling_offset..sibling_offset + (1 << level)],
+        );
+    }
+    Ok(InclusionProof::new(leaf_index, &buf[..height]))
+}
+
+/// Generates an inclusion proof that owns its sibling path.
+///
+/// See [`prove_into`] for the arguments.
+pub fn prove<H: Hasher>(
+    mmr: &MMR<H>,
+    leaves: &[B256],
+    leaf_index: u64,
+) -> Result<InclusionProof, MMRError> {
+    let mut buf = [B256::ZERO; 64];
+    prove_into(mmr, leaves, leaf_index, &mut buf).map(|proof| proof.into_owned())
+}
+
+/// Inclusion proof that embeds the leaves of the small subtree around the proven leaf in
+/// place of the lowest sibling hashes.
+///
+/// Verifiers that already hold the leaf data can then check the neighbourhood of the leaf
+/// locally, at the cost of a larger proof; see `utils::cost::proof_cost` for the trade-off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlinedProof {
+    leaf_index: u64,
+    leaves: Vec<B256>,
+    path: Vec<B256>,
+}
+
+impl InlinedProof {
+    /// Creates a proof from a leaf index, the leaves of the inlined subtree and the sibling
+    /// path above it
+    pub fn new(leaf_index: u64, leaves: Vec<B256>, path: Vec<B256>) -> Self {
+        Self {
+            leaf_index,
+            leaves,
+            path,
+        }
+    }
+
+    /// Returns the index of the proven leaf
+    pub fn leaf_index(&self) -> u64 {
+        self.leaf_index
+    }
+
+    /// Returns the height of the inlined subtree
+    pub fn inline_height(&self) -> u32 {
+        self.leaves.len().trailing_zeros()
+    }
+
+    /// Returns the leaves of the inlined subtree, which contains the proven leaf
+    pub fn leaves(&self) -> &[B256] {
+        &self.leaves
+    }
+
+    /// Returns the sibling path above the inlined subtree, ordered upwards
+    pub fn path(&self) -> &[B256] {
+        &self.path
+    }
+
+    /// Verifies that `leaf` is included in `mmr` at this proof's index
+    pub fn verify<H: Hasher>(&self, mmr: &MMR<H>, leaf: B256) -> bool {
+        let Some(peak) = mmr.peak_for_leaf(self.leaf_index) else {
+            return false;
+        };
+        let inline_height = self.inline_height();
+        if !self.leaves.len().is_power_of_two()
+            || inline_height > peak.height
+            || self.path.len() != (peak.height - inline_height) as usize
+        {
+            return false;
+        }
+        let inline_offset = peak.local_offset & ((1 << inline_height) - 1);
+        if self.leaves[inline_offset as usize] != leaf {
+            return false;
+        }
+        let hash = |left: &B256, right: &B256| mmr.hash_to_parent(left, right);
+        let subtree = hash_subtree_by(&self.leaves, &hash);
+        InclusionProof::new(self.leaf_index, &self.path[..]).compute_peak_by(
+            subtree,
+            peak.local_offset >> inline_height,
+            hash,
+        ) == peak.peak_hash
+    }
+}
+
+/// Generates an inclusion proof that inlines the leaves of small subtrees.
+///
+/// # Arguments
+///
+/// * `mmr` - The MMR the proof is generated against.
+/// * `leaves` - All leaves of `mmr`, i.e. those at indices `mmr.start()..mmr.end()`.
+/// * `leaf_index` - The index of the leaf to prove.
+/// * `inline_height` - The height of the largest subtree whose leaves are embedded. The
+///   aligned subtree of that height around the leaf is inlined, or its whole peak if lower.
+///
+/// # Returns
+///
+/// The proof, whose path only holds the siblings above the inlined subtree.
+pub fn prove_inlined(
+    mmr: &MMR,
+    leaves: &[B256],
+    leaf_index: u64,
+    inline_height: u32,
+) -> Result<InlinedProof, MMRError> {
+    let mut buf = [B256::ZERO; 64];
+    let proof = prove_into(mmr, leaves, leaf_index, &mut buf)?;
+    let path = proof.path;
+    let inline_height = inline_height.min(path.len() as u32);
+    let first = ((leaf_index >> inline_height) << inline_height) - mmr.start();
+    Ok(InlinedProof {
+        leaf_index,
+        leaves: leaves[first as usize..(first + (1 << inline_height)) as usize].to_vec(),
+        path: path[inline_height as usize..].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_prove_and_verify_all_leaves() {
+        let leaves: Vec<B256> = (0..23).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = prove(&mmr, &leaves, index as u64).unwrap();
+            assert!(proof.verify(&mmr, *leaf));
+            assert!(!proof.verify(&mmr, get_random_hash()));
+        }
+    }
+
+    #[test]
+    fn test_prove_nonzero_start() {
+        let leaves: Vec<B256> = (0..14).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::from_params(3, 3, vec![]).unwrap();
+        for leaf in &leaves {
+            mmr.append(*leaf);
+        }
+        for (index, leaf) in (3..).zip(&leaves) {
+            let proof = prove(&mmr, &leaves, index).unwrap();
+            assert!(proof.verify(&mmr, *leaf));
+            // The proof is bound to its index.
+            let moved = InclusionProof::new(index + 1, proof.path().copied().collect::<Vec<_>>());
+            assert!(!moved.verify(&mmr, *leaf));
+        }
+    }
+
+    #[test]
+    fn test_prove_into_buffer() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+
+        let mut buf = [B256::ZERO; 3];
+        let proof = prove_into(&mmr, &leaves, 5, &mut buf).unwrap();
+        assert_eq!(proof.path().count(), 3);
+        assert_eq!(proof.path().next(), Some(&leaves[4]));
+        assert!(proof.verify(&mmr, leaves[5]));
+
+        let mut small = [B256::ZERO; 2];
+        assert!(matches!(
+            prove_into(&mmr, &leaves, 5, &mut small),
+            Err(MMRError::BufferTooSmall)
+        ));
+    }
+
+    #[test]
+    fn test_prove_errors() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        assert!(matches!(
+            prove(&mmr, &leaves, 4),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+        assert!(matches!(
+            prove(&mmr, &leaves[..3], 0),
+            Err(MMRError::InvalidLeaves)
+        ));
+    }
+
+    #[test]
+    fn test_verify_root() {
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let proof = prove(&mmr, &leaves, 9).unwrap();
+        let root = mmr.get_root();
+        assert!(proof.verify_root(0, 11, mmr.peaks(), leaves[9], root));
+        assert!(!proof.verify_root(0, 11, mmr.peaks(), leaves[8], root));
+        assert!(!proof.verify_root(0, 11, mmr.peaks(), leaves[9], get_random_hash()));
+        assert!(!proof.verify_root(0, 12, mmr.peaks(), leaves[9], root));
+
+        // Peaks that don't bag to the root are rejected.
+        let mut peaks = mmr.peaks().to_vec();
+        peaks[0] = get_random_hash();
+        assert!(!proof.verify_root(0, 11, &peaks, leaves[9], root));
+    }
+
+    #[test]
+    fn test_verify_peaks() {
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let proof = prove(&mmr, &leaves, 9).unwrap();
+        let root = mmr.get_root();
+        assert!(proof.verify_peaks(mmr.as_peaks(), leaves[9], root));
+        assert!(!proof.verify_peaks(mmr.as_peaks(), leaves[8], root));
+
+        let borrowed = Peaks::new(0, 11, mmr.peaks()).unwrap();
+        assert!(proof.verify_peaks(&borrowed, leaves[9], root));
+    }
+
+    #[test]
+    fn test_prove_inlined() {
+        let leaves: Vec<B256> = (0..21).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::from_params(3, 3, vec![]).unwrap();
+        for leaf in &leaves {
+            mmr.append(*leaf);
+        }
+        for inline_height in 0..5 {
+            for (index, leaf) in (3..).zip(&leaves) {
+                let proof = prove_inlined(&mmr, &leaves, index, inline_height).unwrap();
+                let height = mmr.peak_for_leaf(index).unwrap().height;
+                assert_eq!(proof.inline_height(), inline_height.min(height));
+                assert!(proof.verify(&mmr, *leaf));
+                assert!(!proof.verify(&mmr, get_random_hash()));
+            }
+        }
+        // Height zero is a plain proof.
+        let proof = prove_inlined(&mmr, &leaves, 9, 0).unwrap();
+        assert_eq!(proof.leaves(), &leaves[6..7]);
+        assert_eq!(proof.path(), &prove(&mmr, &leaves, 9).unwrap().path[..]);
+    }
+
+    #[test]
+    fn test_inlined_proof_rejects_tampered_leaves() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let mut proof = prove_inlined(&mmr, &leaves, 5, 2).unwrap();
+        assert_eq!(proof.leaves(), &leaves[4..8]);
+        // A neighbour the verifier doesn't check directly still changes the subtree root.
+        proof.leaves[3] = get_random_hash();
+        assert!(!proof.verify(&mmr, leaves[5]));
+        proof.leaves.pop();
+        assert!(!proof.verify(&mmr, leaves[5]));
+    }
+
+    #[test]
+    fn test_verify_with_budget() {
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let proof = prove(&mmr, &leaves, 2).unwrap();
+        assert!(proof.verify_with_budget(&mmr, leaves[2], 3).unwrap());
+        assert!(!proof.verify_with_budget(&mmr, leaves[3], 3).unwrap());
+        assert!(matches!(
+            proof.verify_with_budget(&mmr, leaves[2], 2),
+            Err(MMRError::BudgetExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_path_length() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let proof = prove(&mmr, &leaves, 0).unwrap();
+        let mut path: Vec<B256> = proof.path().copied().collect();
+        path.push(get_random_hash());
+        assert!(!InclusionProof::new(0, path).verify(&mmr, leaves[0]));
+    }
+
+    #[test]
+    fn test_prove_keyed() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::new().with_key(get_random_hash());
+        mmr.append_batch(&leaves);
+        let plain = MMR::from_leaves(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = prove(&mmr, &leaves, index as u64).unwrap();
+            assert!(proof.verify(&mmr, *leaf));
+            // Siblings are keyed, so the proof doesn't check against the plain MMR.
+            assert!(index == 12 || !proof.verify(&plain, *leaf));
+            let inlined = prove_inlined(&mmr, &leaves, index as u64, 2).unwrap();
+            assert!(inlined.verify(&mmr, *leaf));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+/// Cycle counts measured by the SP1 bench for the `merklize` guest.
+const MERKLIZE_TABLE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/sp1-programs/bench-results/merklize.md"
+));
+
+/// Total cycles of an SP1 guest by number of leaves, as measured by the bench.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleTable {
+    /// `(num_leaves, total_cycles)`, sorted by number of leaves
+    points: Vec<(u64, u64)>,
+}
+
+impl CycleTable {
+    /// Parses a bench results table, as written to `sp1-programs/bench-results`.
+    ///
+    /// Rows whose arguments hold more than the number of leaves, like the arity of the k-ary
+    /// bench, are skipped.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the table has no usable row.
+    pub fn parse(markdown: &str) -> Option<Self> {
+        let mut points: Vec<(u64, u64)> = markdown
+            .lines()
+            .filter_map(|line| {
+                let cells: Vec<&str> = line.split('|').map(str::trim).collect();
+                // "| iteration | args | total cycles | cycles per leaf |"
+                let [_, _, args, total, _, _] = cells[..] else {
+                    return None;
+                };
+                let num_leaves = args.strip_suffix(" leaves")?.rsplit_once("= ")?.1;
+                if args.contains(',') {
+                    return None;
+                }
+                Some((
+                    num_leaves.parse().ok()?,
+                    total.replace(',', "").parse().ok()?,
+                ))
+            })
+            .collect();
+        points.sort_unstable();
+        points.dedup_by_key(|(num_leaves, _)| *num_leaves);
+        (!points.is_empty()).then_some(Self { points })
+    }
+
+    /// Estimates the total cycles for `num_leaves` leaves.
+    ///
+    /// Counts are interpolated linearly between measured sizes and extrapolated past the
+    /// largest one with the marginal cost of its last interval. Sizes below the smallest
+    /// measured one are estimated at its count, as the fixed overhead dominates there.
+    pub fn estimate(&self, num_leaves: u64) -> u64 {
+        let position = self
+            .points
+            .partition_point(|(leaves, _)| *leaves < num_leaves);
+        if position == 0 || self.points.len() == 1 {
+            return self.points[0].1;
+        }
+        let high_index = position.min(self.points.len() - 1);
+        let (low, high) = (self.points[high_index - 1], self.points[high_index]);
+        let slope_num = high.1 as i128 - low.1 as i128;
+        let slope_den = (high.0 - low.0) as i128;
+        let estimate = low.1 as i128 + slope_num * (num_leaves - low.0) as i128 / slope_den;
+        estimate.clamp(0, u64::MAX as i128) as u64
+    }
+}
+
+/// Estimates the SP1 cycles `program` takes to merklize `num_leaves` leaves, without
+/// executing it, from the bench table committed for it.
+///
+/// Refresh the tables by running the `sp1_merklize` bench.
+///
+/// # Returns
+///
+/// `None` if no table is committed for `program`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::prover::estimate_cycles;
+///
+/// let cycles = estimate_cycles("merklize", 1000).unwrap();
+/// assert!(cycles > estimate_cycles("merklize", 512).unwrap());
+/// assert_eq!(estimate_cycles("unknown", 1000), None);
+/// ```
+pub fn estimate_cycles(program: &str, num_leaves: u64) -> Option<u64> {
+    let table = match program {
+        "merklize" => MERKLIZE_TABLE,
+        _ => return None,
+    };
+    Some(CycleTable::parse(table)?.estimate(num_leaves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "\
+## Merklize Bench Results
+| Iteration | Args | Total Cycles | Cycles Per Leaf |
+|-----------|------|--------------|----------------|
+| 0 | 2^0 = 1 leaves | 1,000 | 1,000 |
+| 1 | 2^1 = 2 leaves | 3,000 | 1,500 |
+| 2 | 2^2 = 4 leaves | 7,000 | 1,750 |
+| 3 | arity 4,2^2 = 4 leaves | 9,000 | 2,250 |
+";
+
+    #[test]
+    fn test_parse() {
+        let table = CycleTable::parse(TABLE).unwrap();
+        assert_eq!(table.points, vec![(1, 1_000), (2, 3_000), (4, 7_000)]);
+        assert_eq!(CycleTable::parse("| a | b |"), None);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let table = CycleTable::parse(TABLE).unwrap();
+        assert_eq!(table.estimate(0), 1_000);
+        assert_eq!(table.estimate(2), 3_000);
+        assert_eq!(table.estimate(3), 5_000);
+        // 2,000 cycles per leaf past the largest measured size.
+        assert_eq!(table.estimate(10), 19_000);
+    }
+
+    #[test]
+    fn test_committed_table() {
+        assert_eq!(estimate_cycles("merklize", 1024), Some(18_688_924));
+        let estimate = estimate_cycles("merklize", 1 << 16).unwrap();
+        assert!(estimate.abs_diff(2 * 598_397_853) < 1_000_000);
+    }
+}
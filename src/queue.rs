@@ -0,0 +1,257 @@
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use alloy_primitives::B256;
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+const LOG_FILE: &str = "queue.log";
+const STATE_FILE: &str = "state";
+const STATE_TMP_FILE: &str = "state.tmp";
+
+/// A file-backed queue of leaves waiting to be appended to an MMR, with exactly-once
+/// semantics across crashes.
+///
+/// Producers enqueue leaves under a client-supplied idempotency key; re-enqueueing a key
+/// that was already accepted is a no-op, so producers can safely retry after a restart.
+/// The consumer drains the queue into the MMR and persists the MMR together with the number
+/// of consumed entries in one atomic rename, so a crash mid-drain never appends an entry
+/// twice.
+///
+/// Layout of the directory:
+///
+/// * `queue.log` - Append-only entries: key length (u32 LE), key, leaf.
+/// * `state` - Consumed entry count, MMR start and end (u64 LE each), then the peaks.
+#[derive(Debug)]
+pub struct PendingQueue {
+    dir: PathBuf,
+    log: File,
+    keys: HashSet<Vec<u8>>,
+    leaves: Vec<B256>,
+    consumed: u64,
+    mmr: MMR,
+}
+
+impl PendingQueue {
+    /// Opens the queue in `dir`, creating it if needed.
+    ///
+    /// A partially written entry at the end of the log, left by a crash during `enqueue`,
+    /// is discarded.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(dir.join(LOG_FILE))?;
+        let mut bytes = vec![];
+        log.read_to_end(&mut bytes)?;
+        let (keys, leaves, valid_len) = parse_log(&bytes);
+        if valid_len < bytes.len() {
+            log.set_len(valid_len as u64)?;
+        }
+
+        let (consumed, mmr) = match fs::read(dir.join(STATE_FILE)) {
+            Ok(bytes) => parse_state(&bytes)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (0, MMR::new()),
+            Err(err) => return Err(err),
+        };
+        if consumed > leaves.len() as u64 {
+            return Err(invalid_data("state is ahead of the queue log"));
+        }
+
+        Ok(Self {
+            dir,
+            log,
+            keys,
+            leaves,
+            consumed,
+            mmr,
+        })
+    }
+
+    /// Durably enqueues `leaf` under `key`.
+    ///
+    /// # Returns
+    ///
+    /// `false` if an entry with `key` was already enqueued, in which case nothing is written.
+    pub fn enqueue(&mut self, key: &[u8], leaf: B256) -> io::Result<bool> {
+        if self.keys.contains(key) {
+            return Ok(false);
+        }
+        let key_len = u32::try_from(key.len()).map_err(|_| invalid_data("key is too long"))?;
+        let mut entry = Vec::with_capacity(4 + key.len() + 32);
+        entry.extend_from_slice(&key_len.to_le_bytes());
+        entry.extend_from_slice(key);
+        entry.extend_from_slice(leaf.as_slice());
+        self.log.write_all(&entry)?;
+        self.log.sync_data()?;
+
+        self.keys.insert(key.to_vec());
+        self.leaves.push(leaf);
+        Ok(true)
+    }
+
+    /// Appends every pending leaf to the MMR and persists the result.
+    ///
+    /// # Returns
+    ///
+    /// The number of leaves appended.
+    pub fn drain(&mut self) -> io::Result<u64> {
+        let pending = &self.leaves[self.consumed as usize..];
+        let mut mmr = self.mmr.clone();
+        for leaf in pending {
+            mmr.append(*leaf);
+        }
+        let consumed = self.leaves.len() as u64;
+        let drained = consumed - self.consumed;
+
+        let tmp = self.dir.join(STATE_TMP_FILE);
+        let mut file = File::create(&tmp)?;
+        file.write_all(&encode_state(consumed, &mmr))?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir.join(STATE_FILE))?;
+
+        self.consumed = consumed;
+        self.mmr = mmr;
+        Ok(drained)
+    }
+
+    /// Returns the number of enqueued leaves not yet drained
+    pub fn pending(&self) -> u64 {
+        self.leaves.len() as u64 - self.consumed
+    }
+
+    /// Returns the MMR as of the last drain
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parses the complete entries of the log, returning them and the length they span.
+fn parse_log(bytes: &[u8]) -> (HashSet<Vec<u8>>, Vec<B256>, usize) {
+    let (mut keys, mut leaves, mut offset) = (HashSet::new(), vec![], 0);
+    while let Some(len) = bytes[offset..].first_chunk::<4>() {
+        let key_start = offset + 4;
+        let leaf_start = key_start + u32::from_le_bytes(*len) as usize;
+        let Some(leaf) = bytes.get(leaf_start..leaf_start + 32) else {
+            break;
+        };
+        keys.insert(bytes[key_start..leaf_start].to_vec());
+        leaves.push(B256::from_slice(leaf));
+        offset = leaf_start + 32;
+    }
+    (keys, leaves, offset)
+}
+
+fn encode_state(consumed: u64, mmr: &MMR) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(24 + 32 * mmr.peaks().len());
+    bytes.extend_from_slice(&consumed.to_le_bytes());
+    bytes.extend_from_slice(&mmr.start().to_le_bytes());
+    bytes.extend_from_slice(&mmr.end().to_le_bytes());
+    for peak in mmr.peaks() {
+        bytes.extend_from_slice(peak.as_slice());
+    }
+    bytes
+}
+
+fn parse_state(bytes: &[u8]) -> io::Result<(u64, MMR)> {
+    let corrupt = || invalid_data("corrupt queue state");
+    let (header, peaks) = bytes.split_first_chunk::<24>().ok_or_else(corrupt)?;
+    if peaks.len() % 32 != 0 || DecodeLimits::DEFAULT.check_peaks(peaks.len() / 32).is_err() {
+        return Err(corrupt());
+    }
+    let field = |i: usize| u64::from_le_bytes(header[i * 8..i * 8 + 8].try_into().unwrap());
+    let peaks = peaks.chunks_exact(32).map(B256::from_slice).collect();
+    let mmr = MMR::from_params(field(1), field(2), peaks).map_err(|_| corrupt())?;
+    Ok((field(0), mmr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("rust-mmr-queue-{}", get_random_hash()))
+    }
+
+    #[test]
+    fn test_enqueue_and_drain() {
+        let dir = temp_dir();
+        let leaves: Vec<B256> = (0..5).map(|_| get_random_hash()).collect();
+        let mut queue = PendingQueue::open(&dir).unwrap();
+        for (i, leaf) in leaves[..3].iter().enumerate() {
+            assert!(queue.enqueue(&[i as u8], *leaf).unwrap());
+        }
+        // A retried enqueue is ignored.
+        assert!(!queue.enqueue(&[1], leaves[1]).unwrap());
+        assert_eq!(queue.pending(), 3);
+        assert_eq!(queue.drain().unwrap(), 3);
+
+        // Keys and progress survive a restart.
+        let mut queue = PendingQueue::open(&dir).unwrap();
+        assert!(!queue.enqueue(&[2], leaves[2]).unwrap());
+        for (i, leaf) in leaves.iter().enumerate().skip(3) {
+            queue.enqueue(&[i as u8], *leaf).unwrap();
+        }
+        assert_eq!(queue.drain().unwrap(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(queue.mmr(), &MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_crash_before_state_is_persisted() {
+        let dir = temp_dir();
+        let leaves: Vec<B256> = (0..6).map(|_| get_random_hash()).collect();
+        let mut queue = PendingQueue::open(&dir).unwrap();
+        for (i, leaf) in leaves[..2].iter().enumerate() {
+            queue.enqueue(&[i as u8], *leaf).unwrap();
+        }
+        queue.drain().unwrap();
+        let old_state = fs::read(dir.join(STATE_FILE)).unwrap();
+        for (i, leaf) in leaves.iter().enumerate().skip(2) {
+            queue.enqueue(&[i as u8], *leaf).unwrap();
+        }
+        queue.drain().unwrap();
+
+        // Crash before the rename: the previous state is still in place.
+        fs::write(dir.join(STATE_FILE), old_state).unwrap();
+        let mut queue = PendingQueue::open(&dir).unwrap();
+        assert_eq!(queue.pending(), 4);
+        queue.drain().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(queue.mmr(), &MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_torn_entry_is_discarded() {
+        let dir = temp_dir();
+        let leaf = get_random_hash();
+        let mut queue = PendingQueue::open(&dir).unwrap();
+        queue.enqueue(b"a", leaf).unwrap();
+        drop(queue);
+
+        // Crash in the middle of writing the second entry.
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.join(LOG_FILE))
+            .unwrap();
+        log.write_all(&[1, 0, 0, 0, b'b', 0xff]).unwrap();
+
+        let mut queue = PendingQueue::open(&dir).unwrap();
+        assert_eq!(queue.pending(), 1);
+        assert!(queue.enqueue(b"b", leaf).unwrap());
+        drop(queue);
+        let queue = PendingQueue::open(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(queue.pending(), 2);
+    }
+}
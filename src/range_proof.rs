@@ -0,0 +1,248 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::utils::{
+    hash::{hash_subtree_by, hash_to_parent},
+    range::get_peak_heights,
+};
+use alloy_primitives::B256;
+
+/// Proof that a contiguous range of leaves is included in an MMR.
+///
+/// Instead of one sibling path per leaf, the proof holds the subtrees covering the rest of
+/// the MMR: the ones left of the range and the ones right of it, each as the peaks an MMR
+/// over that side would have. Together with the proven leaves they tile the whole MMR, so
+/// the verifier rebuilds every peak from them, sharing all siblings between the leaves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeProof {
+    range_start: u64,
+    left: Vec<B256>,
+    right: Vec<B256>,
+}
+
+impl RangeProof {
+    /// Creates a proof for the range starting at `range_start` from the subtrees covering
+    /// the rest of the MMR
+    pub fn new(range_start: u64, left: Vec<B256>, right: Vec<B256>) -> Self {
+        Self {
+            range_start,
+            left,
+            right,
+        }
+    }
+
+    /// Returns the index of the first proven leaf
+    pub fn range_start(&self) -> u64 {
+        self.range_start
+    }
+
+    /// Returns the subtrees covering the leaves left of the range, in leaf order
+    pub fn left(&self) -> &[B256] {
+        &self.left
+    }
+
+    /// Returns the subtrees covering the leaves right of the range, in leaf order
+    pub fn right(&self) -> &[B256] {
+        &self.right
+    }
+
+    /// Verifies that `leaves` are the leaves of `mmr` starting at `range_start`, hashing
+    /// like `mmr`
+    pub fn verify(&self, mmr: &MMR, leaves: &[B256]) -> bool {
+        self.compute_peaks(mmr.start(), mmr.end(), leaves, |left, right| {
+            mmr.hash_to_parent(left, right)
+        })
+        .is_some_and(|peaks| peaks == mmr.peaks())
+    }
+
+    /// Verifies `leaves` against the MMR over `[start, end)` with `root`, all trusted.
+    ///
+    /// The proof determines every peak, so unlike single-leaf proofs no peaks are needed.
+    /// The range must be trusted like the root, as the root doesn't commit to it. Hashes
+    /// with plain Keccak256; use `verify` for keyed MMRs.
+    pub fn verify_root(&self, start: u64, end: u64, leaves: &[B256], root: B256) -> bool {
+        self.compute_peaks(start, end, leaves, hash_to_parent)
+            .and_then(|peaks| Peaks::new(start, end, peaks).ok())
+            .is_some_and(|peaks| peaks.root() == root)
+    }
+
+    /// Rebuilds the peaks of the MMR over `[start, end)` from the proof and `leaves`, hashing
+    /// parents with `hash`, or returns `None` if they don't fit the range
+    fn compute_peaks(
+        &self,
+        start: u64,
+        end: u64,
+        leaves: &[B256],
+        hash: impl Fn(&B256, &B256) -> B256,
+    ) -> Option<Vec<B256>> {
+        let range_end = self.range_start.checked_add(leaves.len() as u64)?;
+        if start > self.range_start || range_end > end {
+            return None;
+        }
+        let left_heights = get_peak_heights(start, self.range_start);
+        let right_heights = get_peak_heights(range_end, end);
+        if left_heights.len() != self.left.len() || right_heights.len() != self.right.len() {
+            return None;
+        }
+
+        // The subtrees tile the MMR in leaf order, so pushing them on a stack and merging
+        // the top two whenever they are siblings leaves exactly the peaks.
+        let nodes = left_heights
+            .into_iter()
+            .zip(&self.left)
+            .chain(leaves.iter().map(|leaf| (0, leaf)))
+            .chain(right_heights.into_iter().zip(&self.right));
+        let mut stack: Vec<(u32, u64, B256)> = Vec::new();
+        let mut begin = start;
+        for (height, subtree) in nodes {
+            let mut node = (height, begin >> height, *subtree);
+            begin += 1 << height;
+            while let Some(&(level, index, left)) = stack.last() {
+                if level != node.0 || index + 1 != node.1 || index % 2 != 0 {
+                    break;
+                }
+                stack.pop();
+                node = (level + 1, index / 2, hash(&left, &node.2));
+            }
+            stack.push(node);
+        }
+        Some(stack.into_iter().map(|(_, _, hash)| hash).collect())
+    }
+}
+
+/// Generates a proof for the contiguous leaves `[range_start, range_end)` of an MMR.
+///
+/// # Arguments
+///
+/// * `mmr` - The MMR the proof is generated against.
+/// * `leaves` - All leaves of `mmr`, i.e. those at indices `mmr.start()..mmr.end()`.
+/// * `range_start` - The index of the first leaf to prove.
+/// * `range_end` - The index past the last leaf to prove.
+///
+/// # Returns
+///
+/// A proof of at most two subtrees per level of the MMR, however many leaves it covers.
+///
+/// # Examples
+///
+/// ```
+/// use alloy_primitives::B256;
+/// use rust_mmr::{range_proof::prove_range, MMR};
+///
+/// let leaves: Vec<B256> = (0..11).map(B256::repeat_byte).collect();
+/// let mmr = MMR::from_leaves(&leaves);
+/// let proof = prove_range(&mmr, &leaves, 3, 9).unwrap();
+/// assert!(proof.verify(&mmr, &leaves[3..9]));
+/// ```
+pub fn prove_range(
+    mmr: &MMR,
+    leaves: &[B256],
+    range_start: u64,
+    range_end: u64,
+) -> Result<RangeProof, MMRError> {
+    if leaves.len() as u64 != mmr.size() {
+        return Err(MMRError::InvalidLeaves);
+    }
+    if range_start > range_end {
+        return Err(MMRError::StartGreaterThanEnd);
+    }
+    if range_start < mmr.start() || range_end > mmr.end() {
+        return Err(MMRError::LeafIndexOutOfRange);
+    }
+    let hash = |left: &B256, right: &B256| mmr.hash_to_parent(left, right);
+    let cover = |begin: u64, end: u64| {
+        let mut offset = (begin - mmr.start()) as usize;
+        get_peak_heights(begin, end)
+            .into_iter()
+            .map(|height| {
+                let subtree = hash_subtree_by(&leaves[offset..offset + (1 << height)], &hash);
+                offset += 1 << height;
+                subtree
+            })
+            .collect()
+    };
+    Ok(RangeProof::new(
+        range_start,
+        cover(mmr.start(), range_start),
+        cover(range_end, mmr.end()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_prove_range_all_ranges() {
+        for start in [0, 1, 5] {
+            let leaves: Vec<B256> = (0..19).map(|_| get_random_hash()).collect();
+            let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+            mmr.append_batch(&leaves);
+            let end = mmr.end();
+            for range_start in start..=end {
+                for range_end in range_start..=end {
+                    let range = (range_start - start) as usize..(range_end - start) as usize;
+                    let proof = prove_range(&mmr, &leaves, range_start, range_end).unwrap();
+                    assert!(proof.verify(&mmr, &leaves[range.clone()]));
+                    assert!(proof.verify_root(start, end, &leaves[range.clone()], mmr.get_root()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shares_siblings() {
+        let leaves: Vec<B256> = (0..1000).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let proof = prove_range(&mmr, &leaves, 100, 900).unwrap();
+        let single = prove(&mmr, &leaves, 100).unwrap();
+        assert!(proof.left().len() + proof.right().len() <= 2 * single.path().count() + 2);
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_leaves() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let proof = prove_range(&mmr, &leaves, 2, 7).unwrap();
+
+        let mut tampered = leaves[2..7].to_vec();
+        tampered[4] = get_random_hash();
+        assert!(!proof.verify(&mmr, &tampered));
+        // Shifted, shortened and extended ranges don't verify.
+        assert!(!proof.verify(&mmr, &leaves[3..8]));
+        assert!(!proof.verify(&mmr, &leaves[2..6]));
+        assert!(!proof.verify(&mmr, &leaves[2..8]));
+        assert!(!proof.verify_root(0, 13, &leaves[2..7], get_random_hash()));
+    }
+
+    #[test]
+    fn test_keyed() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::new().with_key(get_random_hash());
+        mmr.append_batch(&leaves);
+        let proof = prove_range(&mmr, &leaves, 2, 7).unwrap();
+        assert!(proof.verify(&mmr, &leaves[2..7]));
+        // The subtrees are keyed, so they don't rebuild the unkeyed MMR.
+        assert!(!proof.verify(&MMR::from_leaves(&leaves), &leaves[2..7]));
+    }
+
+    #[test]
+    fn test_prove_range_errors() {
+        let leaves: Vec<B256> = (0..5).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        assert!(matches!(
+            prove_range(&mmr, &leaves[..4], 0, 1),
+            Err(MMRError::InvalidLeaves)
+        ));
+        assert!(matches!(
+            prove_range(&mmr, &leaves, 3, 2),
+            Err(MMRError::StartGreaterThanEnd)
+        ));
+        assert!(matches!(
+            prove_range(&mmr, &leaves, 3, 6),
+            Err(MMRError::LeafIndexOutOfRange)
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::proof::InclusionProof;
+use crate::utils::hash::Hasher;
+use alloy_primitives::B256;
+use alloy_rlp::{encode_list, list_length, BufMut, Decodable, Encodable, Error, Header};
+
+/// Encodes the MMR as the RLP list `[start, end, [peaks...]]`.
+impl<H: Hasher> Encodable for MMR<H> {
+    fn encode(&self, out: &mut dyn BufMut) {
+        Header {
+            list: true,
+            payload_length: mmr_payload_length(self),
+        }
+        .encode(out);
+        self.start().encode(out);
+        self.end().encode(out);
+        encode_list::<_, B256>(self.peaks(), out);
+    }
+
+    fn length(&self) -> usize {
+        let payload_length = mmr_payload_length(self);
+        payload_length + alloy_rlp::length_of_length(payload_length)
+    }
+}
+
+/// Decodes an MMR encoded as `[start, end, [peaks...]]`, rejecting peaks that don't match
+/// the range.
+impl<H: Hasher> Decodable for MMR<H> {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut payload = Header::decode_bytes(buf, true)?;
+        let start = u64::decode(&mut payload)?;
+        let end = u64::decode(&mut payload)?;
+        let peaks = Vec::<B256>::decode(&mut payload)?;
+        if !payload.is_empty() {
+            return Err(Error::UnexpectedLength);
+        }
+        let peaks = Peaks::new(start, end, peaks)
+            .map_err(|_| Error::Custom("peaks do not match the MMR range"))?;
+        Ok(Self::from_peaks_with_hasher(peaks))
+    }
+}
+
+/// Encodes the proof as the RLP list `[leaf_index, [path...]]`.
+impl<P: AsRef<[B256]>> Encodable for InclusionProof<P> {
+    fn encode(&self, out: &mut dyn BufMut) {
+        Header {
+            list: true,
+            payload_length: proof_payload_length(self),
+        }
+        .encode(out);
+        self.leaf_index().encode(out);
+        encode_list::<_, B256>(&self.path().copied().collect::<Vec<_>>(), out);
+    }
+
+    fn length(&self) -> usize {
+        let payload_length = proof_payload_length(self);
+        payload_length + alloy_rlp::length_of_length(payload_length)
+    }
+}
+
+/// Decodes a proof encoded as `[leaf_index, [path...]]`, rejecting paths longer than
+/// `DecodeLimits::DEFAULT` allows.
+impl Decodable for InclusionProof {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut payload = Header::decode_bytes(buf, true)?;
+        let leaf_index = u64::decode(&mut payload)?;
+        let path = Vec::<B256>::decode(&mut payload)?;
+        if !payload.is_empty() {
+            return Err(Error::UnexpectedLength);
+        }
+        DecodeLimits::DEFAULT
+            .check_path(path.len())
+            .map_err(|_| Error::Custom("proof path is too long"))?;
+        Ok(Self::new(leaf_index, path))
+    }
+}
+
+fn mmr_payload_length<H: Hasher>(mmr: &MMR<H>) -> usize {
+    mmr.start().length() + mmr.end().length() + list_length::<_, B256>(mmr.peaks())
+}
+
+fn proof_payload_length<P: AsRef<[B256]>>(proof: &InclusionProof<P>) -> usize {
+    let path: Vec<B256> = proof.path().copied().collect();
+    proof.leaf_index().length() + list_length::<_, B256>(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_mmr_roundtrip() {
+        let mmr = MMR::from_params(4, 7, vec![B256::repeat_byte(1), B256::repeat_byte(2)]).unwrap();
+        let bytes = alloy_rlp::encode(&mmr);
+        assert_eq!(bytes.len(), mmr.length());
+        // A 70-byte list of 4, 7 and the 66-byte list of both peaks.
+        assert_eq!(&bytes[..6], &[0xf8, 70, 4, 7, 0xf8, 66]);
+        assert_eq!(<MMR>::decode(&mut &bytes[..]).unwrap(), mmr);
+
+        let mut mmr = MMR::new();
+        for _ in 0..20 {
+            let bytes = alloy_rlp::encode(&mmr);
+            assert_eq!(bytes.len(), mmr.length());
+            assert_eq!(<MMR>::decode(&mut &bytes[..]).unwrap(), mmr);
+            mmr.append(get_random_hash());
+        }
+    }
+
+    #[test]
+    fn test_proof_roundtrip() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        for index in 0..13 {
+            let proof = prove(&mmr, &leaves, index).unwrap();
+            let bytes = alloy_rlp::encode(&proof);
+            assert_eq!(bytes.len(), proof.length());
+            let decoded = InclusionProof::decode(&mut &bytes[..]).unwrap();
+            assert_eq!(decoded, proof);
+            assert!(decoded.verify(&mmr, leaves[index as usize]));
+        }
+    }
+
+    #[test]
+    fn test_invalid_encodings() {
+        // Three peaks don't match [4, 7).
+        let mut bytes = vec![];
+        Header {
+            list: true,
+            payload_length: 2 + list_length::<_, B256>(&[B256::ZERO; 3]),
+        }
+        .encode(&mut bytes);
+        4u64.encode(&mut bytes);
+        7u64.encode(&mut bytes);
+        encode_list::<_, B256>(&[B256::ZERO; 3], &mut bytes);
+        assert!(matches!(
+            <MMR>::decode(&mut &bytes[..]),
+            Err(Error::Custom(_))
+        ));
+
+        let proof = InclusionProof::new(0, vec![B256::ZERO; 65]);
+        assert!(matches!(
+            InclusionProof::decode(&mut &alloy_rlp::encode(&proof)[..]),
+            Err(Error::Custom(_))
+        ));
+
+        let bytes = alloy_rlp::encode(MMR::from_leaves(&[get_random_hash(); 3]));
+        assert!(<MMR>::decode(&mut &bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use crate::error::MMRError;
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use crate::proof::{prove, InclusionProof};
+use alloy_primitives::B256;
+use serde_json::{json, Value};
+
+/// Standard JSON-RPC 2.0 error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// JSON-RPC 2.0 handler exposing an MMR under Substrate-style `mmr_*` method names.
+///
+/// The handler is transport-agnostic: feed it request bodies from any HTTP or WebSocket
+/// server and send back the returned response bodies.
+///
+/// * `mmr_root` - No params. Returns the root as hex.
+/// * `mmr_generateProof` - Params `[leafIndex]`. Returns `{ leafIndex, leaf, path }`.
+/// * `mmr_verifyProof` - Params `[{ leafIndex, leaf, path }]`. Returns whether the proof is
+///   valid for the current MMR.
+///
+/// Proofs in requests are rejected before decoding if they exceed the handler's
+/// `DecodeLimits`. Params errors caused by an `MMRError` carry its code as
+/// `{ "data": { "code": code } }`.
+#[derive(Debug, Clone, Default)]
+pub struct RpcHandler {
+    mmr: MMR,
+    leaves: Vec<B256>,
+    limits: DecodeLimits,
+}
+
+impl RpcHandler {
+    /// Creates a handler serving an MMR built from `leaves`
+    pub fn new(leaves: Vec<B256>) -> Self {
+        Self {
+            mmr: MMR::from_leaves(&leaves),
+            leaves,
+            limits: DecodeLimits::DEFAULT,
+        }
+    }
+
+    /// Sets the limits enforced on proofs in requests
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Appends a leaf to the served MMR
+    pub fn append(&mut self, leaf: B256) {
+        self.mmr.append(leaf);
+        self.leaves.push(leaf);
+    }
+
+    /// Returns the served MMR
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+
+    /// Handles a single JSON-RPC request body, returning the response body
+    pub fn handle(&self, request: &str) -> String {
+        let response = match serde_json::from_str::<Value>(request) {
+            Ok(request) => self.handle_value(&request),
+            Err(_) => error_response(Value::Null, PARSE_ERROR, "Parse error"),
+        };
+        response.to_string()
+    }
+
+    fn handle_value(&self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let (Some("2.0"), Some(method)) = (
+            request.get("jsonrpc").and_then(Value::as_str),
+            request.get("method").and_then(Value::as_str),
+        ) else {
+            return error_response(id, INVALID_REQUEST, "Invalid request");
+        };
+        let params = request.get("params").cloned().unwrap_or(json!([]));
+        let result = match method {
+            "mmr_root" => Ok(json!(self.mmr.get_root().to_string())),
+            "mmr_generateProof" => self.generate_proof(&params),
+            "mmr_verifyProof" => self.verify_proof(&params),
+            _ => return error_response(id, METHOD_NOT_FOUND, "Method not found"),
+        };
+        match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(ParamsError::Malformed(message)) => error_response(id, INVALID_PARAMS, message),
+            Err(ParamsError::Mmr(err)) => {
+                let mut response = error_response(id, INVALID_PARAMS, &err.to_string());
+                response["error"]["data"] = json!({ "code": err.code() });
+                response
+            }
+        }
+    }
+
+    fn generate_proof(&self, params: &Value) -> Result<Value, ParamsError> {
+        let leaf_index = params
+            .get(0)
+            .and_then(Value::as_u64)
+            .ok_or("Expected [leafIndex]")?;
+        let proof = prove(&self.mmr, &self.leaves, leaf_index)?;
+        let leaf = self.leaves[(leaf_index - self.mmr.start()) as usize];
+        Ok(json!({
+            "leafIndex": leaf_index,
+            "leaf": leaf.to_string(),
+            "path": proof.path().map(|node| node.to_string()).collect::<Vec<_>>(),
+        }))
+    }
+
+    fn verify_proof(&self, params: &Value) -> Result<Value, ParamsError> {
+        let proof = params.get(0).ok_or("Expected [proof]")?;
+        let leaf_index = proof
+            .get("leafIndex")
+            .and_then(Value::as_u64)
+            .ok_or("Missing leafIndex")?;
+        let leaf = parse_hash(proof.get("leaf")).ok_or("Malformed leaf")?;
+        let path = proof
+            .get("path")
+            .and_then(Value::as_array)
+            .ok_or("Missing path")?;
+        self.limits.check_path(path.len())?;
+        let path = path
+            .iter()
+            .map(|node| parse_hash(Some(node)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("Malformed path")?;
+        Ok(json!(
+            InclusionProof::new(leaf_index, path).verify(&self.mmr, leaf)
+        ))
+    }
+}
+
+/// Why a method rejected its params
+enum ParamsError {
+    Malformed(&'static str),
+    Mmr(MMRError),
+}
+
+impl From<&'static str> for ParamsError {
+    fn from(message: &'static str) -> Self {
+        ParamsError::Malformed(message)
+    }
+}
+
+impl From<MMRError> for ParamsError {
+    fn from(err: MMRError) -> Self {
+        ParamsError::Mmr(err)
+    }
+}
+
+fn parse_hash(value: Option<&Value>) -> Option<B256> {
+    value?.as_str()?.parse().ok()
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    fn call(handler: &RpcHandler, method: &str, params: Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        serde_json::from_str(&handler.handle(&request.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_root_and_proofs() {
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let mut handler = RpcHandler::new(leaves[..10].to_vec());
+        handler.append(leaves[10]);
+
+        let root = call(&handler, "mmr_root", json!([]));
+        assert_eq!(
+            root["result"],
+            json!(MMR::from_leaves(&leaves).get_root().to_string())
+        );
+
+        let proof = call(&handler, "mmr_generateProof", json!([6]))["result"].clone();
+        assert_eq!(proof["leaf"], json!(leaves[6].to_string()));
+        let verified = call(&handler, "mmr_verifyProof", json!([proof.clone()]));
+        assert_eq!(verified["result"], json!(true));
+
+        let mut tampered = proof;
+        tampered["leaf"] = json!(get_random_hash().to_string());
+        let verified = call(&handler, "mmr_verifyProof", json!([tampered]));
+        assert_eq!(verified["result"], json!(false));
+    }
+
+    #[test]
+    fn test_errors() {
+        let handler = RpcHandler::new(vec![get_random_hash(); 3]);
+        let code = |response: Value| response["error"]["code"].as_i64();
+
+        assert_eq!(
+            code(call(&handler, "mmr_unknown", json!([]))),
+            Some(METHOD_NOT_FOUND)
+        );
+        assert_eq!(
+            code(call(&handler, "mmr_generateProof", json!([3]))),
+            Some(INVALID_PARAMS)
+        );
+        assert_eq!(
+            code(call(
+                &handler,
+                "mmr_verifyProof",
+                json!([{ "leafIndex": 0 }])
+            )),
+            Some(INVALID_PARAMS)
+        );
+        let limited = handler.clone().with_limits(DecodeLimits {
+            max_path_len: 1,
+            ..DecodeLimits::DEFAULT
+        });
+        let proof = json!({ "leafIndex": 0, "leaf": B256::ZERO, "path": [B256::ZERO, B256::ZERO] });
+        let response = call(&limited, "mmr_verifyProof", json!([proof]));
+        assert_eq!(code(response.clone()), Some(INVALID_PARAMS));
+        assert_eq!(
+            response["error"]["message"],
+            json!(MMRError::PathTooLong.to_string())
+        );
+        assert_eq!(
+            response["error"]["data"]["code"],
+            json!(MMRError::PathTooLong.code())
+        );
+        let response: Value = serde_json::from_str(&handler.handle("{")).unwrap();
+        assert_eq!(code(response), Some(PARSE_ERROR));
+        let response: Value =
+            serde_json::from_str(&handler.handle(r#"{"id": 7, "method": "mmr_root"}"#)).unwrap();
+        assert_eq!(code(response.clone()), Some(INVALID_REQUEST));
+        assert_eq!(response["id"], json!(7));
+    }
+}
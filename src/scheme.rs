@@ -0,0 +1,142 @@
+use crate::error::MMRError;
+
+/// Hash function used for internal nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HasherId {
+    Keccak256 = 0,
+    /// Keccak256 keyed with a secret, see `MMR::with_key`
+    KeyedKeccak256 = 1,
+    /// SHA-256, see `Sha256Hasher`
+    Sha256 = 2,
+    /// SHA3-256, see `Sha3Hasher`
+    Sha3 = 3,
+    /// Keccak256 reduced into the BN254 scalar field, see `Bn254Keccak256Hasher`
+    Bn254Keccak256 = 4,
+    /// Keccak256 of the sorted children, see `SortedKeccak256Hasher`
+    SortedKeccak256 = 5,
+    /// SHA-256 with RFC 6962 domain separation, see `Rfc6962Hasher`
+    Rfc6962 = 6,
+    /// Poseidon over the BN254 scalar field, see `PoseidonHasher`
+    Poseidon = 7,
+}
+
+/// Strategy used to bag peaks into a root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BaggingId {
+    /// Left peaks folded left to right, right peaks folded right to left, see `bag_peaks`.
+    SplitLeftRight = 0,
+}
+
+/// How application data is turned into leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LeafPolicy {
+    /// Leaves are appended as given.
+    Raw = 0,
+    /// Leaves are `keccak256(len || data)` of variable-length records, see `hash_leaf_bytes`.
+    LengthPrefixed = 1,
+}
+
+/// Identifies the hashing and bagging conventions an MMR was built with.
+///
+/// Two MMRs built under different schemes have incomparable roots, so the descriptor is
+/// carried with the state and checked whenever MMRs are merged or compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SchemeDescriptor {
+    pub hasher: HasherId,
+    pub bagging: BaggingId,
+    pub leaf_policy: LeafPolicy,
+}
+
+impl SchemeDescriptor {
+    /// The scheme of every MMR built with the default configuration
+    pub const DEFAULT: Self = Self {
+        hasher: HasherId::Keccak256,
+        bagging: BaggingId::SplitLeftRight,
+        leaf_policy: LeafPolicy::Raw,
+    };
+
+    /// Number of bytes in the encoded descriptor
+    pub const ENCODED_LEN: usize = 3;
+
+    /// Encodes the descriptor as one id byte per component, for embedding in serialized state
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        [
+            self.hasher as u8,
+            self.bagging as u8,
+            self.leaf_policy as u8,
+        ]
+    }
+
+    /// Decodes a descriptor, rejecting unknown ids
+    pub fn from_bytes(bytes: [u8; Self::ENCODED_LEN]) -> Result<Self, MMRError> {
+        let hasher = match bytes[0] {
+            0 => HasherId::Keccak256,
+            1 => HasherId::KeyedKeccak256,
+            2 => HasherId::Sha256,
+            3 => HasherId::Sha3,
+            4 => HasherId::Bn254Keccak256,
+            5 => HasherId::SortedKeccak256,
+            6 => HasherId::Rfc6962,
+            7 => HasherId::Poseidon,
+            _ => return Err(MMRError::UnknownScheme),
+        };
+        let bagging = match bytes[1] {
+            0 => BaggingId::SplitLeftRight,
+            _ => return Err(MMRError::UnknownScheme),
+        };
+        let leaf_policy = match bytes[2] {
+            0 => LeafPolicy::Raw,
+            1 => LeafPolicy::LengthPrefixed,
+            _ => return Err(MMRError::UnknownScheme),
+        };
+        Ok(Self {
+            hasher,
+            bagging,
+            leaf_policy,
+        })
+    }
+}
+
+impl Default for SchemeDescriptor {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let scheme = SchemeDescriptor::DEFAULT;
+        assert_eq!(scheme.to_bytes(), [0, 0, 0]);
+        assert_eq!(
+            SchemeDescriptor::from_bytes(scheme.to_bytes()).unwrap(),
+            scheme
+        );
+    }
+
+    #[test]
+    fn test_length_prefixed_round_trip() {
+        let scheme = SchemeDescriptor {
+            leaf_policy: LeafPolicy::LengthPrefixed,
+            ..SchemeDescriptor::DEFAULT
+        };
+        assert_eq!(scheme.to_bytes(), [0, 0, 1]);
+        assert_eq!(SchemeDescriptor::from_bytes([0, 0, 1]).unwrap(), scheme);
+    }
+
+    #[test]
+    fn test_unknown_ids() {
+        for bytes in [[8, 0, 0], [0, 1, 0], [0, 0, 2]] {
+            assert!(matches!(
+                SchemeDescriptor::from_bytes(bytes),
+                Err(MMRError::UnknownScheme)
+            ));
+        }
+    }
+}
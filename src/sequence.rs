@@ -0,0 +1,115 @@
+use crate::compact::CompactRange;
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use alloy_primitives::B256;
+
+/// Verifies that appending `leaves` in order to the MMR with root `old_root` yields
+/// `claimed_new_root`, as needed to adjudicate a sequencer's claim in a fraud proof.
+///
+/// The verifier only knows the old MMR by its root, so the caller supplies its compact range
+/// as `witness`. The two failures are told apart, since they blame different parties: a
+/// witness that doesn't bag to `old_root` is the challenger's fault, while a correct witness
+/// that doesn't lead to `claimed_new_root` proves the claim false.
+///
+/// # Arguments
+///
+/// * `old_root` - The root before the append
+/// * `leaves` - The appended leaves, in order
+/// * `claimed_new_root` - The root claimed after the append
+/// * `witness` - The range and peaks of the MMR before the append
+///
+/// # Returns
+///
+/// `Ok(())` if the claim holds, `MMRError::InvalidWitness` if the witness is malformed or
+/// doesn't match `old_root`, `MMRError::IndexOverflow` if the leaves don't fit after the
+/// witness' end and `MMRError::RootMismatch` if the claim is false.
+///
+/// # Examples
+///
+/// ```
+/// use alloy_primitives::B256;
+/// use rust_mmr::{compact::CompactRange, sequence::verify_append_sequence, MMR};
+///
+/// let old = MMR::from_leaves(&[B256::repeat_byte(1), B256::repeat_byte(2)]);
+/// let leaves = [B256::repeat_byte(3)];
+/// let mut new = old.clone();
+/// new.append(leaves[0]);
+///
+/// let witness = CompactRange::from(&old);
+/// assert!(verify_append_sequence(old.get_root(), &leaves, new.get_root(), &witness).is_ok());
+/// ```
+pub fn verify_append_sequence(
+    old_root: B256,
+    leaves: &[B256],
+    claimed_new_root: B256,
+    witness: &CompactRange,
+) -> Result<(), MMRError> {
+    let mut mmr = MMR::try_from(witness.clone()).map_err(|_| MMRError::InvalidWitness)?;
+    if mmr.get_root() != old_root {
+        return Err(MMRError::InvalidWitness);
+    }
+    mmr.end()
+        .checked_add(leaves.len() as u64)
+        .ok_or(MMRError::IndexOverflow)?;
+    mmr.append_batch(leaves);
+    if mmr.get_root() != claimed_new_root {
+        return Err(MMRError::RootMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    fn setup() -> (MMR, Vec<B256>, MMR) {
+        let old_leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let old = MMR::from_leaves(&old_leaves);
+        let leaves: Vec<B256> = (0..6).map(|_| get_random_hash()).collect();
+        let mut new = old.clone();
+        new.append_batch(&leaves);
+        (old, leaves, new)
+    }
+
+    #[test]
+    fn test_valid_sequence() {
+        let (old, leaves, new) = setup();
+        let witness = CompactRange::from(&old);
+        assert!(verify_append_sequence(old.get_root(), &leaves, new.get_root(), &witness).is_ok());
+        // Appending nothing keeps the root.
+        assert!(verify_append_sequence(old.get_root(), &[], old.get_root(), &witness).is_ok());
+    }
+
+    #[test]
+    fn test_false_claims() {
+        let (old, mut leaves, new) = setup();
+        let witness = CompactRange::from(&old);
+        // Leaves reordered, dropped or replaced.
+        leaves.swap(1, 2);
+        assert!(matches!(
+            verify_append_sequence(old.get_root(), &leaves, new.get_root(), &witness),
+            Err(MMRError::RootMismatch)
+        ));
+        assert!(matches!(
+            verify_append_sequence(old.get_root(), &leaves[..5], new.get_root(), &witness),
+            Err(MMRError::RootMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_witness() {
+        let (old, leaves, new) = setup();
+        let mut witness = CompactRange::from(&old);
+        witness.hashes[0] = get_random_hash();
+        assert!(matches!(
+            verify_append_sequence(old.get_root(), &leaves, new.get_root(), &witness),
+            Err(MMRError::InvalidWitness)
+        ));
+        witness.hashes.pop();
+        assert!(matches!(
+            verify_append_sequence(old.get_root(), &leaves, new.get_root(), &witness),
+            Err(MMRError::InvalidWitness)
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::peaks::Peaks;
+use crate::proof::InclusionProof;
+use crate::utils::hash::{Hasher, SortedKeccak256Hasher};
+use crate::utils::range::{decompose, locate_leaf};
+use alloc::vec::Vec;
+use alloy_primitives::B256;
+
+/// Bags left peaks like `bag_peaks`, folding them left to right.
+fn fold_left(peaks: &[B256]) -> Option<B256> {
+    peaks
+        .iter()
+        .copied()
+        .reduce(|acc, peak| SortedKeccak256Hasher::hash_parent(&acc, &peak))
+}
+
+/// Bags right peaks like `bag_peaks`, folding them right to left. The sorted pair hash is
+/// commutative but not associative, so this nests differently from `fold_left`.
+fn fold_right(peaks: &[B256]) -> Option<B256> {
+    peaks.iter().rfold(None, |acc, peak| match acc {
+        None => Some(*peak),
+        Some(acc) => Some(SortedKeccak256Hasher::hash_parent(peak, &acc)),
+    })
+}
+
+/// Converts an inclusion proof of an MMR built with `SortedKeccak256Hasher` into a proof of
+/// the leaf under the MMR's root, as checked by OpenZeppelin's `MerkleProof.verify(proof,
+/// root, leaf)` and Solady's `MerkleProofLib.verify`.
+///
+/// With a commutative pair hash, bagging the peaks is just more levels of the same tree, so
+/// the proof is the leaf's path to its peak followed by one sibling per bagging step. The
+/// leaf is passed on-chain as appended to the MMR; hashing it first, as OpenZeppelin
+/// recommends, is up to the application.
+///
+/// Expressible this way are proofs of a single leaf, under its peak (the `InclusionProof`
+/// path as is, with the peak as root) or under the root (this function). Range, consistency
+/// and merge proofs relate whole ranges rather than a leaf to a root, and still need an
+/// MMR-aware verifier.
+///
+/// Returns `None` if the proof's leaf isn't covered by `peaks`, its path doesn't lead to a
+/// peak, or a peak is zero, which bagging would skip.
+pub fn to_sorted_pair_proof<P: AsRef<[B256]>, Q: AsRef<[B256]>>(
+    proof: &InclusionProof<P>,
+    peaks: &Peaks<Q>,
+) -> Option<Vec<B256>> {
+    let (peak_index, height, _) = locate_leaf(peaks.start(), peaks.end(), proof.leaf_index())?;
+    let mut path: Vec<B256> = proof.path().copied().collect();
+    if path.len() != height as usize || peaks.as_slice().contains(&B256::ZERO) {
+        return None;
+    }
+
+    let (left, _) = decompose(peaks.start(), peaks.end());
+    let (left_peaks, right_peaks) = peaks.as_slice().split_at(left.count_ones() as usize);
+    if peak_index < left_peaks.len() {
+        // Left peaks fold left to right, then the left root meets the right one.
+        path.extend(fold_left(&left_peaks[..peak_index]));
+        path.extend_from_slice(&left_peaks[peak_index + 1..]);
+        path.extend(fold_right(right_peaks));
+    } else {
+        // Right peaks fold right to left, then the right root meets the left one.
+        let index = peak_index - left_peaks.len();
+        path.extend(fold_right(&right_peaks[index + 1..]));
+        path.extend(right_peaks[..index].iter().rev());
+        path.extend(fold_left(left_peaks));
+    }
+    Some(path)
+}
+
+/// Computes the root a sorted pair proof commits `leaf` to, like OpenZeppelin's
+/// `MerkleProof.processProof`
+pub fn process_sorted_pair_proof(proof: &[B256], leaf: B256) -> B256 {
+    proof.iter().fold(leaf, |node, sibling| {
+        SortedKeccak256Hasher::hash_parent(&node, sibling)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmr::MMR;
+    use crate::proof::prove;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_proofs_reach_the_root() {
+        for (start, len) in [
+            (0, 1),
+            (0, 11),
+            (0, 15),
+            (1, 31),
+            (0, 63),
+            (3, 1),
+            (3, 4),
+            (5, 27),
+            (64, 37),
+        ] {
+            let leaves: Vec<B256> = (0..len).map(|_| get_random_hash()).collect();
+            let mut mmr: MMR<SortedKeccak256Hasher> =
+                MMR::from_peaks_with_hasher(Peaks::empty(start));
+            mmr.append_batch(&leaves);
+            let peaks = Peaks::new(mmr.start(), mmr.end(), mmr.peaks()).unwrap();
+
+            for index in start..mmr.end() {
+                let proof = prove(&mmr, &leaves, index).unwrap();
+                let sorted = to_sorted_pair_proof(&proof, &peaks).unwrap();
+                let leaf = leaves[(index - start) as usize];
+                assert_eq!(process_sorted_pair_proof(&sorted, leaf), mmr.get_root());
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_proofs() {
+        let leaves: Vec<B256> = (0..6).map(|_| get_random_hash()).collect();
+        let mmr: MMR<SortedKeccak256Hasher> = leaves.iter().copied().collect();
+        let peaks = Peaks::new(mmr.start(), mmr.end(), mmr.peaks()).unwrap();
+
+        let proof = prove(&mmr, &leaves, 2).unwrap();
+        let truncated = InclusionProof::new(2, proof.path().skip(1).copied().collect::<Vec<_>>());
+        assert!(to_sorted_pair_proof(&truncated, &peaks).is_none());
+        let outside = InclusionProof::new(6, Vec::new());
+        assert!(to_sorted_pair_proof(&outside, &peaks).is_none());
+    }
+}
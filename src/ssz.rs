@@ -0,0 +1,241 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::proof::InclusionProof;
+use crate::utils::hash::{Hasher, Sha256Hasher};
+use alloy_primitives::B256;
+
+/// Capacity `N` of the `List[Bytes32, N]` holding the peaks of an MMR. A range has at most
+/// `MAX_PEAKS` (126) peaks.
+pub const PEAKS_LIMIT: usize = 128;
+
+/// Capacity `N` of the `List[Bytes32, N]` holding the path of an inclusion proof.
+pub const PATH_LIMIT: usize = 64;
+
+/// SSZ serialization and merkleization, for embedding MMR state in beacon-chain-style
+/// containers. Only available with the `ssz` feature.
+///
+/// The types are encoded as the containers
+///
+/// * `MMR`: `{ start: uint64, end: uint64, peaks: List[Bytes32, PEAKS_LIMIT] }`
+/// * `InclusionProof`: `{ leaf_index: uint64, path: List[Bytes32, PATH_LIMIT] }`
+///
+/// `hash_tree_root` always merkleizes with SHA-256, whatever hasher built the MMR.
+pub trait Ssz: Sized {
+    /// Serializes the value as an SSZ container
+    fn to_ssz_bytes(&self) -> Vec<u8>;
+
+    /// Deserializes a value serialized by `to_ssz_bytes`
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, MMRError>;
+
+    /// Returns the SSZ `hash_tree_root` of the container
+    fn hash_tree_root(&self) -> B256;
+}
+
+impl<H: Hasher> Ssz for MMR<H> {
+    fn to_ssz_bytes(&self) -> Vec<u8> {
+        let mut fixed = [0; 16];
+        fixed[..8].copy_from_slice(&self.start().to_le_bytes());
+        fixed[8..].copy_from_slice(&self.end().to_le_bytes());
+        encode_container(&fixed, self.peaks())
+    }
+
+    /// Deserializes an MMR, returning `MMRError::TooManyPeaks` past `PEAKS_LIMIT` peaks
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, MMRError> {
+        let (fixed, peaks) = decode_container::<16>(bytes, PEAKS_LIMIT, MMRError::TooManyPeaks)?;
+        let start = u64::from_le_bytes(fixed[..8].try_into().unwrap());
+        let end = u64::from_le_bytes(fixed[8..].try_into().unwrap());
+        Ok(Self::from_peaks_with_hasher(Peaks::new(start, end, peaks)?))
+    }
+
+    fn hash_tree_root(&self) -> B256 {
+        merkleize(
+            &[
+                uint64_chunk(self.start()),
+                uint64_chunk(self.end()),
+                list_root(self.peaks(), PEAKS_LIMIT),
+            ],
+            2,
+        )
+    }
+}
+
+impl Ssz for InclusionProof {
+    fn to_ssz_bytes(&self) -> Vec<u8> {
+        let path: Vec<B256> = self.path().copied().collect();
+        encode_container(&self.leaf_index().to_le_bytes(), &path)
+    }
+
+    /// Deserializes a proof, returning `MMRError::PathTooLong` past `PATH_LIMIT` siblings
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, MMRError> {
+        let (fixed, path) = decode_container::<8>(bytes, PATH_LIMIT, MMRError::PathTooLong)?;
+        Ok(Self::new(u64::from_le_bytes(fixed), path))
+    }
+
+    fn hash_tree_root(&self) -> B256 {
+        let path: Vec<B256> = self.path().copied().collect();
+        merkleize(
+            &[
+                uint64_chunk(self.leaf_index()),
+                list_root(&path, PATH_LIMIT),
+            ],
+            1,
+        )
+    }
+}
+
+/// Encodes a container of fixed-size fields followed by a single list of hashes: the fixed
+/// fields, the 4-byte offset of the list, then the list.
+fn encode_container(fixed: &[u8], list: &[B256]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(fixed.len() + 4 + 32 * list.len());
+    bytes.extend_from_slice(fixed);
+    bytes.extend_from_slice(&(fixed.len() as u32 + 4).to_le_bytes());
+    for hash in list {
+        bytes.extend_from_slice(hash.as_slice());
+    }
+    bytes
+}
+
+/// Decodes a container encoded by `encode_container`, returning `too_long` if the list has
+/// more than `limit` hashes
+fn decode_container<const N: usize>(
+    bytes: &[u8],
+    limit: usize,
+    too_long: MMRError,
+) -> Result<([u8; N], Vec<B256>), MMRError> {
+    let (fixed, rest) = bytes
+        .split_first_chunk::<N>()
+        .ok_or(MMRError::InvalidEncoding)?;
+    let (offset, list) = rest
+        .split_first_chunk::<4>()
+        .ok_or(MMRError::InvalidEncoding)?;
+    if u32::from_le_bytes(*offset) as usize != N + 4 || list.len() % 32 != 0 {
+        return Err(MMRError::InvalidEncoding);
+    }
+    if list.len() / 32 > limit {
+        return Err(too_long);
+    }
+    Ok((
+        *fixed,
+        list.chunks_exact(32).map(B256::from_slice).collect(),
+    ))
+}
+
+fn uint64_chunk(value: u64) -> B256 {
+    let mut chunk = B256::ZERO;
+    chunk[..8].copy_from_slice(&value.to_le_bytes());
+    chunk
+}
+
+/// Returns the root of a `List[Bytes32, limit]`, mixing the length into its merkleization
+fn list_root(list: &[B256], limit: usize) -> B256 {
+    let root = merkleize(list, limit.next_power_of_two().trailing_zeros());
+    Sha256Hasher::hash_parent(&root, &uint64_chunk(list.len() as u64))
+}
+
+/// Merkleizes `chunks` in a tree of the given depth, padding them with zero chunks
+fn merkleize(chunks: &[B256], depth: u32) -> B256 {
+    let mut layer = chunks.to_vec();
+    let mut zero = B256::ZERO;
+    for _ in 0..depth {
+        if layer.len() % 2 == 1 {
+            layer.push(zero);
+        }
+        layer = layer
+            .chunks_exact(2)
+            .map(|pair| Sha256Hasher::hash_parent(&pair[0], &pair[1]))
+            .collect();
+        zero = Sha256Hasher::hash_parent(&zero, &zero);
+    }
+    layer.first().copied().unwrap_or(zero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove;
+    use crate::utils::hash::get_random_hash;
+    use alloy_primitives::b256;
+
+    #[test]
+    fn test_mmr_roundtrip() {
+        let mmr = MMR::from_params(4, 7, vec![B256::repeat_byte(1), B256::repeat_byte(2)]).unwrap();
+        let bytes = mmr.to_ssz_bytes();
+        assert_eq!(
+            &bytes[..20],
+            &[4, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0]
+        );
+        assert_eq!(bytes.len(), 20 + 64);
+        assert_eq!(<MMR>::from_ssz_bytes(&bytes).unwrap(), mmr);
+
+        let mut mmr = MMR::new();
+        for _ in 0..20 {
+            assert_eq!(<MMR>::from_ssz_bytes(&mmr.to_ssz_bytes()).unwrap(), mmr);
+            mmr.append(get_random_hash());
+        }
+    }
+
+    #[test]
+    fn test_proof_roundtrip() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        for index in 0..13 {
+            let proof = prove(&mmr, &leaves, index).unwrap();
+            let decoded = InclusionProof::from_ssz_bytes(&proof.to_ssz_bytes()).unwrap();
+            assert_eq!(decoded, proof);
+            assert!(decoded.verify(&mmr, leaves[index as usize]));
+        }
+    }
+
+    #[test]
+    fn test_hash_tree_root() {
+        // Computed independently by padding every list to its full capacity.
+        let mmr = MMR::from_params(4, 7, vec![B256::repeat_byte(1), B256::repeat_byte(2)]).unwrap();
+        assert_eq!(
+            mmr.hash_tree_root(),
+            b256!("a5f5b829b757868d52bde859b6aceee78b3cded9bf0c060542b95839a9661e76")
+        );
+        assert_eq!(
+            MMR::new().hash_tree_root(),
+            b256!("dec8b387f58bc73d76cd91401c6cac24c14455f9cc0d8da43ce6188817880266")
+        );
+        let proof = InclusionProof::new(5, vec![B256::repeat_byte(3); 3]);
+        assert_eq!(
+            proof.hash_tree_root(),
+            b256!("5ae4de8b64110b8d3286cf321dba1973cbe91069452ed0ca58b451c2576c554d")
+        );
+    }
+
+    #[test]
+    fn test_invalid_encodings() {
+        let bytes = MMR::from_leaves(&[get_random_hash(); 3]).to_ssz_bytes();
+        for len in [0, 19, 21, 51] {
+            assert!(matches!(
+                <MMR>::from_ssz_bytes(&bytes[..len]),
+                Err(MMRError::InvalidEncoding)
+            ));
+        }
+        let mut wrong_offset = bytes.clone();
+        wrong_offset[16] = 24;
+        assert!(matches!(
+            <MMR>::from_ssz_bytes(&wrong_offset),
+            Err(MMRError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            <MMR>::from_ssz_bytes(&bytes[..52]),
+            Err(MMRError::InvalidNumberOfPeaks)
+        ));
+
+        let mut too_many = bytes[..20].to_vec();
+        too_many.resize(20 + 32 * (PEAKS_LIMIT + 1), 0);
+        assert!(matches!(
+            <MMR>::from_ssz_bytes(&too_many),
+            Err(MMRError::TooManyPeaks)
+        ));
+        let too_long = InclusionProof::new(0, vec![B256::ZERO; PATH_LIMIT + 1]).to_ssz_bytes();
+        assert!(matches!(
+            InclusionProof::from_ssz_bytes(&too_long),
+            Err(MMRError::PathTooLong)
+        ));
+    }
+}
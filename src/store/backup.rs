@@ -0,0 +1,257 @@
+use super::{append_leaves, snapshot::StoredMMR, NodePosition, NodeStore, StoreError};
+use crate::error::MMRError;
+use crate::limits::DecodeLimits;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::scheme::{HasherId, SchemeDescriptor};
+use alloy_primitives::{keccak256, B256};
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+const MAGIC: &[u8; 8] = b"MMRBAK01";
+
+impl From<io::Error> for StoreError {
+    fn from(err: io::Error) -> Self {
+        StoreError::Backend(Box::new(err))
+    }
+}
+
+impl<S: NodeStore> StoredMMR<S> {
+    /// Writes the committed state to `writer` as a single streamable archive.
+    ///
+    /// The archive holds the leaves, from which restoring rebuilds every node, so it is
+    /// independent of the store's layout. Layout, with integers as big-endian bytes:
+    ///
+    /// * Header: `"MMRBAK01"`, scheme, start, end (u64) and chunk size (u32).
+    /// * Chunks: the leaves of `[start, end)`, `chunk_size` per chunk except the last.
+    /// * Manifest: the keccak256 hash of each chunk, then the root.
+    ///
+    /// Leaves are read from the store one chunk at a time, as of the committed state. Chunks
+    /// over `DecodeLimits::DEFAULT.max_leaves` need `restore_with_limits` to be restored.
+    pub async fn backup<W: Write>(&self, mut writer: W, chunk_size: u32) -> Result<(), StoreError> {
+        assert!(chunk_size > 0, "chunk_size must be positive");
+        let mmr = self.mmr();
+        writer.write_all(MAGIC)?;
+        writer.write_all(&mmr.scheme().to_bytes())?;
+        writer.write_all(&mmr.start().to_be_bytes())?;
+        writer.write_all(&mmr.end().to_be_bytes())?;
+        writer.write_all(&chunk_size.to_be_bytes())?;
+
+        let mut manifest = vec![];
+        for chunk_start in (mmr.start()..mmr.end()).step_by(chunk_size as usize) {
+            let chunk_end = mmr.end().min(chunk_start + chunk_size as u64);
+            let positions: Vec<NodePosition> = (chunk_start..chunk_end)
+                .map(|index| NodePosition::new(0, index))
+                .collect();
+            let mut chunk = Vec::with_capacity(32 * positions.len());
+            let leaves = self.store().get_nodes_at(&positions, mmr.end()).await?;
+            for (leaf, position) in leaves.into_iter().zip(positions) {
+                chunk.extend_from_slice(leaf.ok_or(StoreError::MissingNode(position))?.as_slice());
+            }
+            writer.write_all(&chunk)?;
+            manifest.push(keccak256(&chunk));
+        }
+
+        for hash in manifest {
+            writer.write_all(hash.as_slice())?;
+        }
+        writer.write_all(mmr.get_root().as_slice())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Restores an archive written by `backup` into `store`, verifying each chunk against
+    /// the manifest and the rebuilt root against the archived one.
+    ///
+    /// Chunks are stored as they are read, before the manifest can be checked, so on error
+    /// `store` may hold nodes of the partial restore; they are not part of any committed
+    /// state.
+    pub async fn restore<R: Read>(store: Arc<S>, reader: R) -> Result<Self, StoreError> {
+        Self::restore_with_limits(store, reader, &DecodeLimits::DEFAULT).await
+    }
+
+    /// Like `restore`, for an archive of an MMR hashed under `key`, see `MMR::with_key`
+    pub async fn restore_keyed<R: Read>(
+        store: Arc<S>,
+        reader: R,
+        key: B256,
+    ) -> Result<Self, StoreError> {
+        let template = MMR::new().with_key(key);
+        Self::restore_from(store, reader, &template, &DecodeLimits::DEFAULT).await
+    }
+
+    /// Like `restore`, but rejects archives whose chunk size exceeds `limits.max_leaves`
+    /// before allocating a chunk
+    pub async fn restore_with_limits<R: Read>(
+        store: Arc<S>,
+        reader: R,
+        limits: &DecodeLimits,
+    ) -> Result<Self, StoreError> {
+        Self::restore_from(store, reader, &MMR::new(), limits).await
+    }
+
+    /// Like `restore_with_limits`, rebuilding the MMR with the key, limit and strictness of
+    /// `template` and the archived scheme.
+    ///
+    /// Returns `MMRError::SchemeMismatch` if the archive is keyed and `template` isn't, or
+    /// the other way around.
+    pub async fn restore_from<R: Read>(
+        store: Arc<S>,
+        mut reader: R,
+        template: &MMR,
+        limits: &DecodeLimits,
+    ) -> Result<Self, StoreError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(StoreError::Corrupt);
+        }
+        let mut scheme = [0; SchemeDescriptor::ENCODED_LEN];
+        reader.read_exact(&mut scheme)?;
+        let scheme = SchemeDescriptor::from_bytes(scheme)?;
+        if (scheme.hasher == HasherId::KeyedKeccak256) != template.is_keyed() {
+            return Err(MMRError::SchemeMismatch.into());
+        }
+        let start = read_u64(&mut reader)?;
+        let end = read_u64(&mut reader)?;
+        let mut chunk_size = [0; 4];
+        reader.read_exact(&mut chunk_size)?;
+        let chunk_size = u32::from_be_bytes(chunk_size);
+        if start > end || chunk_size == 0 {
+            return Err(StoreError::Corrupt);
+        }
+        limits.check_leaves(chunk_size as usize)?;
+
+        let mut mmr = template.with_peaks(Peaks::empty(start)).with_scheme(scheme);
+        let mut hashes = vec![];
+        while mmr.end() < end {
+            let len = (end - mmr.end()).min(chunk_size as u64) as usize;
+            let mut chunk = vec![0; 32 * len];
+            reader.read_exact(&mut chunk)?;
+            hashes.push(keccak256(&chunk));
+            let leaves: Vec<B256> = chunk.chunks_exact(32).map(B256::from_slice).collect();
+            append_leaves(store.as_ref(), &mut mmr, &leaves).await?;
+        }
+
+        for hash in hashes {
+            if read_hash(&mut reader)? != hash {
+                return Err(StoreError::Corrupt);
+            }
+        }
+        if read_hash(&mut reader)? != mmr.get_root() {
+            return Err(StoreError::Corrupt);
+        }
+        Ok(Self::new(store, mmr))
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+fn read_hash<R: Read>(reader: &mut R) -> io::Result<B256> {
+    let mut bytes = B256::ZERO;
+    reader.read_exact(bytes.as_mut_slice())?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use crate::utils::hash::get_random_hash;
+
+    async fn stored(num_leaves: usize) -> StoredMMR<MemoryStore> {
+        let leaves: Vec<B256> = (0..num_leaves).map(|_| get_random_hash()).collect();
+        let mut stored = StoredMMR::new(Arc::new(MemoryStore::new()), MMR::new());
+        stored.append(&leaves).await.unwrap();
+        stored
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let stored = stored(23).await;
+        let mut archive = vec![];
+        stored.backup(&mut archive, 5).await.unwrap();
+        // Header, 23 leaves, 5 chunk hashes and the root.
+        assert_eq!(archive.len(), 31 + 32 * (23 + 5 + 1));
+
+        let store = Arc::new(MemoryStore::new());
+        let restored = StoredMMR::restore(store.clone(), &archive[..])
+            .await
+            .unwrap();
+        assert_eq!(restored.mmr(), stored.mmr());
+        assert_eq!(store.len(), stored.store().len());
+        let proof = restored.snapshot().prove(17).await.unwrap();
+        assert_eq!(proof, stored.snapshot().prove(17).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_keyed_backup_and_restore() {
+        let key = get_random_hash();
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let mut keyed = StoredMMR::new(Arc::new(MemoryStore::new()), MMR::new().with_key(key));
+        keyed.append(&leaves).await.unwrap();
+        let mut archive = vec![];
+        keyed.backup(&mut archive, 4).await.unwrap();
+
+        let restored = StoredMMR::restore_keyed(Arc::new(MemoryStore::new()), &archive[..], key)
+            .await
+            .unwrap();
+        assert_eq!(restored.mmr(), keyed.mmr());
+        let proof = restored.snapshot().prove(6).await.unwrap();
+        assert!(proof.verify(restored.mmr(), leaves[6]));
+
+        // A keyed archive needs its key, and only it.
+        assert!(matches!(
+            StoredMMR::restore(Arc::new(MemoryStore::new()), &archive[..]).await,
+            Err(StoreError::Mmr(MMRError::SchemeMismatch))
+        ));
+        let other_key = get_random_hash();
+        assert!(matches!(
+            StoredMMR::restore_keyed(Arc::new(MemoryStore::new()), &archive[..], other_key).await,
+            Err(StoreError::Corrupt)
+        ));
+        let mut plain = vec![];
+        stored(3).await.backup(&mut plain, 4).await.unwrap();
+        assert!(matches!(
+            StoredMMR::restore_keyed(Arc::new(MemoryStore::new()), &plain[..], key).await,
+            Err(StoreError::Mmr(MMRError::SchemeMismatch))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restore_empty() {
+        let stored = stored(0).await;
+        let mut archive = vec![];
+        stored.backup(&mut archive, 5).await.unwrap();
+        let restored = StoredMMR::restore(Arc::new(MemoryStore::new()), &archive[..])
+            .await
+            .unwrap();
+        assert_eq!(restored.mmr(), &MMR::new());
+    }
+
+    #[tokio::test]
+    async fn test_restore_detects_corruption() {
+        let stored = stored(9).await;
+        let mut archive = vec![];
+        stored.backup(&mut archive, 4).await.unwrap();
+
+        // A flipped leaf, chunk hash or root.
+        for offset in [31 + 32 * 5, 31 + 32 * 10, archive.len() - 1] {
+            let mut corrupted = archive.clone();
+            corrupted[offset] ^= 1;
+            assert!(matches!(
+                StoredMMR::restore(Arc::new(MemoryStore::new()), &corrupted[..]).await,
+                Err(StoreError::Corrupt)
+            ));
+        }
+        // A truncated archive.
+        assert!(matches!(
+            StoredMMR::restore(Arc::new(MemoryStore::new()), &archive[..archive.len() - 1]).await,
+            Err(StoreError::Backend(_))
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use super::{NodePosition, NodeStore, StoreError};
+use crate::lru::Lru;
+use alloy_primitives::B256;
+use std::sync::Mutex;
+
+/// Bytes accounted per cached node: its position and hash.
+pub const NODE_BYTES: usize = core::mem::size_of::<(NodePosition, B256)>();
+
+/// Hit/miss counters of a [`CachedStore`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NodeCacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// The bytes currently held, at most the budget
+    pub bytes: usize,
+}
+
+#[derive(Debug)]
+struct LruNodes {
+    nodes: Lru<NodePosition, B256>,
+    metrics: NodeCacheMetrics,
+}
+
+impl LruNodes {
+    fn get(&mut self, position: &NodePosition) -> Option<B256> {
+        self.nodes.get(position).copied()
+    }
+
+    fn insert(&mut self, position: NodePosition, node: B256) {
+        if self.nodes.insert(position, node) {
+            self.metrics.evictions += 1;
+        }
+        self.metrics.bytes = self.nodes.len() * NODE_BYTES;
+    }
+}
+
+/// Wraps a [`NodeStore`] with an LRU cache of the nodes read and written through it.
+///
+/// The nodes near the top of the tree lie on nearly every sibling path, so they stay cached
+/// while the rarely shared lower levels cycle through, and proofs skip most backend reads.
+/// Nodes never change once stored, so cached nodes never go stale, and snapshot reads check
+/// the LSN before touching the cache.
+#[derive(Debug)]
+pub struct CachedStore<S> {
+    store: S,
+    min_level: u32,
+    cache: Mutex<LruNodes>,
+}
+
+impl<S: NodeStore> CachedStore<S> {
+    /// Wraps `store` with a cache holding at most `budget` bytes of nodes
+    pub fn new(store: S, budget: usize) -> Self {
+        Self {
+            store,
+            min_level: 0,
+            cache: Mutex::new(LruNodes {
+                nodes: Lru::new(budget / NODE_BYTES),
+                metrics: NodeCacheMetrics::default(),
+            }),
+        }
+    }
+
+    /// Only caches nodes at `min_level` or above, so that scans over the lower levels don't
+    /// evict the top of the tree
+    pub fn with_min_level(mut self, min_level: u32) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Returns the hit/miss counters
+    pub fn metrics(&self) -> NodeCacheMetrics {
+        self.cache.lock().unwrap().metrics
+    }
+
+    /// Returns the wrapped store
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Caches the nodes at admitted levels
+    fn admit(&self, nodes: impl IntoIterator<Item = (NodePosition, B256)>) {
+        let mut cache = self.cache.lock().unwrap();
+        for (position, node) in nodes {
+            if position.level >= self.min_level {
+                cache.insert(position, node);
+            }
+        }
+    }
+}
+
+impl<S: NodeStore> NodeStore for CachedStore<S> {
+    async fn get_nodes(&self, positions: &[NodePosition]) -> Result<Vec<Option<B256>>, StoreError> {
+        let mut nodes: Vec<Option<B256>> = {
+            let mut cache = self.cache.lock().unwrap();
+            let nodes: Vec<Option<B256>> = positions.iter().map(|p| cache.get(p)).collect();
+            let hits = nodes.iter().filter(|node| node.is_some()).count() as u64;
+            cache.metrics.hits += hits;
+            cache.metrics.misses += positions.len() as u64 - hits;
+            nodes
+        };
+        let missing: Vec<NodePosition> = positions
+            .iter()
+            .zip(&nodes)
+            .filter(|(_, node)| node.is_none())
+            .map(|(position, _)| *position)
+            .collect();
+        if missing.is_empty() {
+            return Ok(nodes);
+        }
+
+        let mut fetched = self.store.get_nodes(&missing).await?.into_iter();
+        for node in nodes.iter_mut().filter(|node| node.is_none()) {
+            *node = fetched.next().flatten();
+        }
+        self.admit(
+            positions
+                .iter()
+                .zip(&nodes)
+                .filter_map(|(position, node)| Some((*position, (*node)?))),
+        );
+        Ok(nodes)
+    }
+
+    async fn put_nodes(&self, nodes: &[(NodePosition, B256)]) -> Result<(), StoreError> {
+        self.store.put_nodes(nodes).await?;
+        self.admit(nodes.iter().copied());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{append_leaves, prove, MemoryStore};
+    use crate::utils::hash::get_random_hash;
+    use crate::MMR;
+
+    async fn filled(budget: usize, leaves: &[B256]) -> (CachedStore<MemoryStore>, MMR) {
+        let store = CachedStore::new(MemoryStore::new(), budget);
+        let mut mmr = MMR::new();
+        append_leaves(store.store(), &mut mmr, leaves)
+            .await
+            .unwrap();
+        (store, mmr)
+    }
+
+    #[tokio::test]
+    async fn test_repeated_proofs_hit() {
+        let leaves: Vec<B256> = (0..32).map(|_| get_random_hash()).collect();
+        let (store, mmr) = filled(1 << 20, &leaves).await;
+
+        let proof = prove(&store, &mmr, 5).await.unwrap();
+        assert!(proof.verify(&mmr, leaves[5]));
+        assert_eq!(
+            store.metrics(),
+            NodeCacheMetrics {
+                misses: 5,
+                bytes: 5 * NODE_BYTES,
+                ..Default::default()
+            }
+        );
+
+        // Leaf 4 shares all but the first sibling with leaf 5.
+        assert_eq!(prove(&store, &mmr, 5).await.unwrap(), proof);
+        prove(&store, &mmr, 4).await.unwrap();
+        let metrics = store.metrics();
+        assert_eq!((metrics.hits, metrics.misses), (9, 6));
+    }
+
+    #[tokio::test]
+    async fn test_budget_evicts_least_recently_used() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let (store, _) = filled(2 * NODE_BYTES, &leaves).await;
+        let positions = [
+            NodePosition::new(0, 0),
+            NodePosition::new(0, 1),
+            NodePosition::new(0, 0),
+            NodePosition::new(0, 2),
+        ];
+        for position in positions {
+            store.get_nodes(&[position]).await.unwrap();
+        }
+        // Leaf 1 was evicted for leaf 2, leaf 0 is still cached.
+        store.get_nodes(&[NodePosition::new(0, 0)]).await.unwrap();
+        store.get_nodes(&[NodePosition::new(0, 1)]).await.unwrap();
+        let metrics = store.metrics();
+        assert_eq!((metrics.hits, metrics.misses), (2, 4));
+        assert_eq!(metrics.evictions, 2);
+        assert_eq!(metrics.bytes, 2 * NODE_BYTES);
+    }
+
+    #[tokio::test]
+    async fn test_min_level_and_write_through() {
+        let leaves: Vec<B256> = (0..8).map(|_| get_random_hash()).collect();
+        let store = CachedStore::new(MemoryStore::new(), 1 << 20).with_min_level(2);
+        let mut mmr = MMR::new();
+        append_leaves(&store, &mut mmr, &leaves).await.unwrap();
+        // Only the nodes at levels 2 and 3 were cached when written.
+        assert_eq!(store.metrics().bytes, 3 * NODE_BYTES);
+        assert_eq!(store.store().len(), 15);
+
+        let proof = prove(&store, &mmr, 0).await.unwrap();
+        assert!(proof.verify(&mmr, leaves[0]));
+        let metrics = store.metrics();
+        assert_eq!((metrics.hits, metrics.misses), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_missing_nodes_are_not_cached() {
+        let store = CachedStore::new(MemoryStore::new(), 1 << 20);
+        let position = NodePosition::new(0, 3);
+        assert_eq!(store.get_nodes(&[position]).await.unwrap(), vec![None]);
+        store.put_nodes(&[(position, B256::ZERO)]).await.unwrap();
+        assert_eq!(
+            store.get_nodes(&[position]).await.unwrap(),
+            vec![Some(B256::ZERO)]
+        );
+        assert_eq!(store.metrics().hits, 1);
+    }
+}
@@ -0,0 +1,243 @@
+use super::{append_leaves, NodePosition, NodeStore, StoreError};
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::scheme::{HasherId, SchemeDescriptor};
+use crate::utils::range::get_peak_heights;
+use alloy_primitives::B256;
+use std::future::Future;
+use std::sync::Mutex;
+
+/// A committed MMR state, as recorded in a [`CommitLog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Commit {
+    pub start: u64,
+    pub end: u64,
+    /// The scheme of the MMR, restored on recovery
+    pub scheme: SchemeDescriptor,
+    /// The root of the MMR, checked against the stored peaks on recovery
+    pub root: B256,
+}
+
+/// Durable record of the last committed MMR state.
+///
+/// A state is committed once all of its nodes are stored, so the peaks of the last commit
+/// can always be read back from the [`NodeStore`]. Writing the commit is the atomic commit
+/// point of an append: a crash before it recovers the previous state, one after it the new
+/// state, and never a mix of both.
+pub trait CommitLog: Sync {
+    /// Durably records `commit` as the last committed state
+    fn commit(&self, commit: Commit) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Returns the last committed state, if any
+    fn last_commit(&self) -> impl Future<Output = Result<Option<Commit>, StoreError>> + Send;
+}
+
+/// A [`CommitLog`] keeping the last commit in memory.
+#[derive(Debug, Default)]
+pub struct MemoryLog {
+    last: Mutex<Option<Commit>>,
+}
+
+impl MemoryLog {
+    /// Creates a new empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CommitLog for MemoryLog {
+    async fn commit(&self, commit: Commit) -> Result<(), StoreError> {
+        *self.last.lock().unwrap() = Some(commit);
+        Ok(())
+    }
+
+    async fn last_commit(&self) -> Result<Option<Commit>, StoreError> {
+        Ok(*self.last.lock().unwrap())
+    }
+}
+
+/// Appends `leaves` to `mmr` like [`append_leaves`], then commits the new state to `log`.
+///
+/// The MMR is only updated once the commit is written, so on error it still matches the
+/// state [`recover`] would return.
+pub async fn append_committed<S: NodeStore, L: CommitLog>(
+    store: &S,
+    log: &L,
+    mmr: &mut MMR,
+    leaves: &[B256],
+) -> Result<(), StoreError> {
+    let mut appended = mmr.clone();
+    append_leaves(store, &mut appended, leaves).await?;
+    log.commit(Commit {
+        start: appended.start(),
+        end: appended.end(),
+        scheme: appended.scheme(),
+        root: appended.get_root(),
+    })
+    .await?;
+    *mmr = appended;
+    Ok(())
+}
+
+/// Rebuilds the last committed MMR from its peaks in `store`, with the committed scheme
+/// and the key, limit and strictness of `template`, which a commit doesn't record.
+///
+/// # Returns
+///
+/// The MMR, or `None` if nothing was committed yet. Fails with `StoreError::MissingNode`
+/// if a peak of the commit is not stored, `MMRError::SchemeMismatch` if the commit is keyed
+/// and `template` isn't, or the other way around, and `StoreError::Corrupt` if the peaks
+/// don't bag to the committed root.
+pub async fn recover<S: NodeStore, L: CommitLog>(
+    store: &S,
+    log: &L,
+    template: &MMR,
+) -> Result<Option<MMR>, StoreError> {
+    let Some(commit) = log.last_commit().await? else {
+        return Ok(None);
+    };
+    if commit.start > commit.end {
+        return Err(StoreError::Corrupt);
+    }
+    if (commit.scheme.hasher == HasherId::KeyedKeccak256) != template.is_keyed() {
+        return Err(MMRError::SchemeMismatch.into());
+    }
+    // Every peak is an aligned subtree completed by an append within the range.
+    let mut begin = commit.start;
+    let positions: Vec<NodePosition> = get_peak_heights(commit.start, commit.end)
+        .into_iter()
+        .map(|height| {
+            let position = NodePosition::new(height, begin >> height);
+            begin += 1 << height;
+            position
+        })
+        .collect();
+    let peaks = store
+        .get_nodes_at(&positions, commit.end)
+        .await?
+        .into_iter()
+        .zip(&positions)
+        .map(|(node, &position)| node.ok_or(StoreError::MissingNode(position)))
+        .collect::<Result<_, _>>()?;
+    let mmr = template
+        .with_peaks(Peaks::new(commit.start, commit.end, peaks)?)
+        .with_scheme(commit.scheme);
+    mmr.validate()?;
+    if mmr.get_root() != commit.root {
+        return Err(StoreError::Corrupt);
+    }
+    Ok(Some(mmr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheme::LeafPolicy;
+    use crate::store::MemoryStore;
+    use crate::utils::hash::get_random_hash;
+
+    #[tokio::test]
+    async fn test_append_committed_and_recover() {
+        let store = MemoryStore::new();
+        let log = MemoryLog::new();
+        assert_eq!(recover(&store, &log, &MMR::new()).await.unwrap(), None);
+
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::from_params(3, 3, vec![]).unwrap();
+        append_committed(&store, &log, &mut mmr, &leaves[..6])
+            .await
+            .unwrap();
+        append_committed(&store, &log, &mut mmr, &leaves[6..])
+            .await
+            .unwrap();
+        assert_eq!(recover(&store, &log, &MMR::new()).await.unwrap(), Some(mmr));
+    }
+
+    #[tokio::test]
+    async fn test_recover_scheme_and_key() {
+        let leaves: Vec<B256> = (0..9).map(|_| get_random_hash()).collect();
+        let scheme = SchemeDescriptor {
+            leaf_policy: LeafPolicy::LengthPrefixed,
+            ..SchemeDescriptor::DEFAULT
+        };
+        let key = get_random_hash();
+        for template in [
+            MMR::new().with_scheme(scheme).with_max_end(100),
+            MMR::new().with_key(key),
+        ] {
+            let store = MemoryStore::new();
+            let log = MemoryLog::new();
+            let mut mmr = template.clone();
+            append_committed(&store, &log, &mut mmr, &leaves)
+                .await
+                .unwrap();
+            let recovered = recover(&store, &log, &template).await.unwrap().unwrap();
+            assert_eq!(recovered, mmr);
+
+            // The recovered MMR keeps hashing and limiting like the committed one.
+            let mut resumed = recovered;
+            resumed.append_bytes(b"record");
+            mmr.append_bytes(b"record");
+            assert_eq!(resumed, mmr);
+            assert_eq!(resumed.max_end(), mmr.max_end());
+        }
+
+        // A keyed commit needs its key.
+        let store = MemoryStore::new();
+        let log = MemoryLog::new();
+        let mut keyed = MMR::new().with_key(key);
+        append_committed(&store, &log, &mut keyed, &leaves)
+            .await
+            .unwrap();
+        assert!(matches!(
+            recover(&store, &log, &MMR::new()).await,
+            Err(StoreError::Mmr(MMRError::SchemeMismatch))
+        ));
+        let other_key = MMR::new().with_key(get_random_hash());
+        assert!(matches!(
+            recover(&store, &log, &other_key).await,
+            Err(StoreError::Corrupt)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recover_rejects_inconsistent_commit() {
+        let store = MemoryStore::new();
+        let log = MemoryLog::new();
+        let mut mmr = MMR::new();
+        let leaves: Vec<B256> = (0..5).map(|_| get_random_hash()).collect();
+        append_committed(&store, &log, &mut mmr, &leaves)
+            .await
+            .unwrap();
+
+        // A commit past the stored nodes.
+        let root = mmr.get_root();
+        log.commit(Commit {
+            start: 0,
+            end: 6,
+            scheme: mmr.scheme(),
+            root,
+        })
+        .await
+        .unwrap();
+        assert!(matches!(
+            recover(&store, &log, &MMR::new()).await,
+            Err(StoreError::MissingNode(NodePosition { level: 1, index: 2 }))
+        ));
+        // A commit whose root doesn't match its peaks.
+        let root = get_random_hash();
+        log.commit(Commit {
+            start: 0,
+            end: 5,
+            scheme: mmr.scheme(),
+            root,
+        })
+        .await
+        .unwrap();
+        assert!(matches!(
+            recover(&store, &log, &MMR::new()).await,
+            Err(StoreError::Corrupt)
+        ));
+    }
+}
@@ -0,0 +1,452 @@
+pub mod backup;
+pub mod cache;
+pub mod commit;
+#[cfg(feature = "object-store")]
+pub mod object;
+pub mod payload;
+pub mod snapshot;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+use crate::consistency::ConsistencyProof;
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::proof::InclusionProof;
+use crate::utils::range::get_peak_heights;
+use alloy_primitives::B256;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+
+/// The position of a node: the root of the perfect subtree of `2^level` leaves starting at
+/// leaf `index * 2^level`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodePosition {
+    pub level: u32,
+    pub index: u64,
+}
+
+impl NodePosition {
+    pub fn new(level: u32, index: u64) -> Self {
+        Self { level, index }
+    }
+
+    /// Returns the log sequence number of the node: the end of the first MMR containing it,
+    /// at which its subtree is completed
+    pub fn lsn(&self) -> u64 {
+        (self.index + 1) << self.level
+    }
+}
+
+/// Error returned by a [`NodeStore`], a [`payload::PayloadStore`] or by the operations
+/// running against one.
+#[derive(Debug)]
+pub enum StoreError {
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+    Mmr(MMRError),
+    /// A node needed by the operation was never stored.
+    MissingNode(NodePosition),
+    /// A node was read past the log sequence number of a snapshot.
+    NotInSnapshot(NodePosition),
+    /// Stored data could not be decoded.
+    Corrupt,
+    /// A payload was stored with a codec the reader doesn't decode, identified by its tag.
+    UnknownCodec(u8),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Backend(err) => write!(f, "Storage backend error: {}", err),
+            StoreError::Mmr(err) => write!(f, "MMR error: {}", err),
+            StoreError::MissingNode(position) => write!(
+                f,
+                "Missing node at level {}, index {}",
+                position.level, position.index
+            ),
+            StoreError::NotInSnapshot(position) => write!(
+                f,
+                "Node at level {}, index {} is not in the snapshot",
+                position.level, position.index
+            ),
+            StoreError::Corrupt => write!(f, "Corrupt stored data"),
+            StoreError::UnknownCodec(tag) => write!(f, "Unknown payload codec {}", tag),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl StoreError {
+    /// Returns the stable code of the error. Wrapped `MMRError`s keep their own code, and the
+    /// storage errors take codes from 1000, clear of those.
+    pub fn code(&self) -> u16 {
+        match self {
+            StoreError::Mmr(err) => err.code(),
+            StoreError::Backend(_) => 1000,
+            StoreError::MissingNode(_) => 1001,
+            StoreError::NotInSnapshot(_) => 1002,
+            StoreError::Corrupt => 1003,
+            StoreError::UnknownCodec(_) => 1004,
+        }
+    }
+}
+
+impl From<MMRError> for StoreError {
+    fn from(err: MMRError) -> Self {
+        StoreError::Mmr(err)
+    }
+}
+
+/// Asynchronous storage of MMR nodes by position.
+///
+/// Nodes are written once, when the subtree they root is completed, and never change.
+///
+/// This makes every MMR state a consistent snapshot, identified by its end as log sequence
+/// number (LSN): the nodes it can read are the ones with an LSN up to its end, which were
+/// stored before it was committed, while appends flushing past it only write nodes with
+/// greater LSNs.
+pub trait NodeStore: Sync {
+    /// Fetches the nodes at `positions`, with `None` for the ones never stored
+    fn get_nodes(
+        &self,
+        positions: &[NodePosition],
+    ) -> impl Future<Output = Result<Vec<Option<B256>>, StoreError>> + Send;
+
+    /// Stores `nodes`
+    fn put_nodes(
+        &self,
+        nodes: &[(NodePosition, B256)],
+    ) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Fetches the nodes at `positions` as of the snapshot at `lsn`, or returns
+    /// `StoreError::NotInSnapshot` if one of them is only part of later snapshots
+    fn get_nodes_at(
+        &self,
+        positions: &[NodePosition],
+        lsn: u64,
+    ) -> impl Future<Output = Result<Vec<Option<B256>>, StoreError>> + Send {
+        async move {
+            if let Some(&position) = positions.iter().find(|position| position.lsn() > lsn) {
+                return Err(StoreError::NotInSnapshot(position));
+            }
+            self.get_nodes(positions).await
+        }
+    }
+}
+
+/// A [`NodeStore`] keeping nodes in a hash map.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    nodes: Mutex<HashMap<NodePosition, B256>>,
+}
+
+impl MemoryStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of stored nodes
+    pub fn len(&self) -> usize {
+        self.nodes.lock().unwrap().len()
+    }
+
+    /// Returns true if no node is stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl NodeStore for MemoryStore {
+    async fn get_nodes(&self, positions: &[NodePosition]) -> Result<Vec<Option<B256>>, StoreError> {
+        let nodes = self.nodes.lock().unwrap();
+        Ok(positions
+            .iter()
+            .map(|position| nodes.get(position).copied())
+            .collect())
+    }
+
+    async fn put_nodes(&self, nodes: &[(NodePosition, B256)]) -> Result<(), StoreError> {
+        self.nodes.lock().unwrap().extend(nodes.iter().copied());
+        Ok(())
+    }
+}
+
+/// Appends `leaves` to `mmr`, writing every node they complete, hashed like `mmr`, to
+/// `store` in one batch.
+///
+/// The MMR is only updated once the nodes are stored, so on error it still matches the
+/// store's last successful append.
+pub async fn append_leaves<S: NodeStore>(
+    store: &S,
+    mmr: &mut MMR,
+    leaves: &[B256],
+) -> Result<(), StoreError> {
+    let mut appended = mmr.clone();
+    let mut nodes = vec![];
+    for &leaf in leaves {
+        let mut position = NodePosition::new(0, appended.end());
+        let mut node = leaf;
+        nodes.push((position, node));
+        for sibling in appended.peaks().iter().rev().take(appended.next_merges()) {
+            node = appended.hash_to_parent(sibling, &node);
+            position = NodePosition::new(position.level + 1, position.index >> 1);
+            nodes.push((position, node));
+        }
+        appended.try_append(leaf)?;
+    }
+    store.put_nodes(&nodes).await?;
+    *mmr = appended;
+    Ok(())
+}
+
+/// Generates an inclusion proof for the leaf at `leaf_index` from the nodes in `store`,
+/// fetching the whole sibling path in one batch from the snapshot at `mmr.end()`.
+pub async fn prove<S: NodeStore>(
+    store: &S,
+    mmr: &MMR,
+    leaf_index: u64,
+) -> Result<InclusionProof, StoreError> {
+    let height = mmr
+        .peak_for_leaf(leaf_index)
+        .ok_or(MMRError::LeafIndexOutOfRange)?
+        .height;
+    let positions: Vec<NodePosition> = (0..height)
+        .map(|level| NodePosition::new(level, (leaf_index >> level) ^ 1))
+        .collect();
+    let path = store
+        .get_nodes_at(&positions, mmr.end())
+        .await?
+        .into_iter()
+        .zip(&positions)
+        .map(|(node, &position)| node.ok_or(StoreError::MissingNode(position)))
+        .collect::<Result<_, _>>()?;
+    Ok(InclusionProof::new(leaf_index, path))
+}
+
+/// Generates a proof that `mmr` extends the MMR over `[0, old_end)` from the nodes in
+/// `store`, fetching the peaks of `[old_end, mmr.end())` in one batch from the snapshot at
+/// `mmr.end()`.
+///
+/// Fails with `MMRError::RangeMismatch` if `mmr` doesn't start at 0, as consistency proofs
+/// only cover MMRs that do, and `MMRError::StartGreaterThanEnd` if `old_end` is past its end.
+pub async fn prove_consistency<S: NodeStore>(
+    store: &S,
+    mmr: &MMR,
+    old_end: u64,
+) -> Result<ConsistencyProof, StoreError> {
+    if mmr.start() != 0 {
+        return Err(MMRError::RangeMismatch.into());
+    }
+    if old_end > mmr.end() {
+        return Err(MMRError::StartGreaterThanEnd.into());
+    }
+    let mut begin = old_end;
+    let positions: Vec<NodePosition> = get_peak_heights(old_end, mmr.end())
+        .into_iter()
+        .map(|height| {
+            let position = NodePosition::new(height, begin >> height);
+            begin += 1 << height;
+            position
+        })
+        .collect();
+    let peaks = store
+        .get_nodes_at(&positions, mmr.end())
+        .await?
+        .into_iter()
+        .zip(&positions)
+        .map(|(node, &position)| node.ok_or(StoreError::MissingNode(position)))
+        .collect::<Result<_, _>>()?;
+    Ok(ConsistencyProof::new(old_end, mmr.end(), peaks))
+}
+
+/// Returns the historical nodes a stateless prover needs to append `count` leaves to the
+/// MMR over `[start, end)` and prove them.
+///
+/// These are the peaks of `[start, end)` that the appended leaves merge, which are the only
+/// stored nodes on their sibling paths. The other peaks are not touched by the appends and
+/// are only needed to bag the new root.
+///
+/// # Arguments
+///
+/// * `start` - The start index of the MMR
+/// * `end` - The end index of the MMR before the appends
+/// * `count` - The number of leaves to append
+///
+/// # Returns
+///
+/// The positions of the nodes, in leaf order.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::store::{witness_for_appends, NodePosition};
+///
+/// // Leaf 5 completes [4, 6), merging the peak at leaf 4. The peak [0, 4) stays a peak.
+/// assert_eq!(witness_for_appends(0, 5, 1), vec![NodePosition::new(0, 4)]);
+/// ```
+pub fn witness_for_appends(start: u64, end: u64, count: u64) -> Vec<NodePosition> {
+    let new_end = end.saturating_add(count) as u128;
+    let mut begin = start;
+    get_peak_heights(start, end)
+        .into_iter()
+        .filter_map(|height| {
+            let position = NodePosition::new(height, begin >> height);
+            begin += 1 << height;
+            let parent_begin = ((position.index >> 1) as u128) << (height + 1);
+            let parent_end = parent_begin + (1u128 << (height + 1));
+            (parent_begin >= start as u128 && parent_end <= new_end).then_some(position)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::full::FullMMR;
+    use crate::utils::hash::get_random_hash;
+
+    #[tokio::test]
+    async fn test_append_and_prove() {
+        let leaves: Vec<B256> = (0..21).map(|_| get_random_hash()).collect();
+        let store = MemoryStore::new();
+        let mut mmr = MMR::new();
+        append_leaves(&store, &mut mmr, &leaves[..5]).await.unwrap();
+        append_leaves(&store, &mut mmr, &leaves[5..]).await.unwrap();
+
+        let full = FullMMR::from_leaves(&leaves);
+        assert_eq!(&mmr, full.mmr());
+        // 21 leaves, 10 + 5 + 2 + 1 inner nodes.
+        assert_eq!(store.len(), 39);
+        for index in 0..21 {
+            let proof = prove(&store, &mmr, index).await.unwrap();
+            assert_eq!(proof, full.prove(index).unwrap());
+        }
+        assert!(matches!(
+            prove(&store, &mmr, 21).await,
+            Err(StoreError::Mmr(MMRError::LeafIndexOutOfRange))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_keyed_append_and_prove() {
+        let leaves: Vec<B256> = (0..13).map(|_| get_random_hash()).collect();
+        let keyed = MMR::new().with_key(get_random_hash());
+        let store = MemoryStore::new();
+        let mut mmr = keyed.clone();
+        append_leaves(&store, &mut mmr, &leaves).await.unwrap();
+
+        let mut expected = keyed;
+        expected.append_batch(&leaves);
+        assert_eq!(mmr, expected);
+        for index in 0..13 {
+            let proof = prove(&store, &mmr, index).await.unwrap();
+            assert!(proof.verify(&mmr, leaves[index as usize]));
+        }
+    }
+
+    #[test]
+    fn test_witness_for_appends() {
+        for start in [0, 1, 5, 8] {
+            for end in start..start + 20 {
+                for count in 0..20 {
+                    let peaks = vec![get_random_hash(); get_peak_heights(start, end).len()];
+                    let mut mmr = MMR::from_params(start, end, peaks).unwrap();
+                    mmr.append_batch(&vec![get_random_hash(); count as usize]);
+
+                    // Every node on the paths of the appended leaves completed by `end`.
+                    let mut expected: Vec<NodePosition> = (end..end + count)
+                        .flat_map(|index| {
+                            let height = mmr.peak_for_leaf(index).unwrap().height;
+                            (0..height)
+                                .map(move |level| NodePosition::new(level, (index >> level) ^ 1))
+                        })
+                        .filter(|position| position.lsn() <= end)
+                        .collect();
+                    expected.sort_by_key(|position| position.index << position.level);
+                    expected.dedup();
+                    assert_eq!(witness_for_appends(start, end, count), expected);
+                }
+            }
+        }
+        assert!(witness_for_appends(0, u64::MAX, 1).is_empty());
+    }
+
+    #[test]
+    fn test_lsn() {
+        assert_eq!(NodePosition::new(0, 0).lsn(), 1);
+        assert_eq!(NodePosition::new(0, 6).lsn(), 7);
+        assert_eq!(NodePosition::new(2, 1).lsn(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_prove_consistency() {
+        let leaves: Vec<B256> = (0..21).map(|_| get_random_hash()).collect();
+        let store = MemoryStore::new();
+        let mut mmr = MMR::new();
+        append_leaves(&store, &mut mmr, &leaves).await.unwrap();
+        for old_end in [0, 1, 7, 8, 13, 21] {
+            let old = MMR::from_leaves(&leaves[..old_end as usize]);
+            let proof = prove_consistency(&store, &mmr, old_end).await.unwrap();
+            assert!(proof.verify(&old, mmr.get_root()));
+        }
+        assert!(matches!(
+            prove_consistency(&store, &mmr, 22).await,
+            Err(StoreError::Mmr(MMRError::StartGreaterThanEnd))
+        ));
+        let shifted = MMR::from_params(1, 1, vec![]).unwrap();
+        assert!(matches!(
+            prove_consistency(&store, &shifted, 0).await,
+            Err(StoreError::Mmr(MMRError::RangeMismatch))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_nodes_at() {
+        let store = MemoryStore::new();
+        let mut mmr = MMR::new();
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        append_leaves(&store, &mut mmr, &leaves).await.unwrap();
+        let positions = [NodePosition::new(0, 2), NodePosition::new(1, 1)];
+        assert_eq!(store.get_nodes_at(&positions, 4).await.unwrap().len(), 2);
+        assert!(matches!(
+            store.get_nodes_at(&positions, 3).await,
+            Err(StoreError::NotInSnapshot(NodePosition {
+                level: 1,
+                index: 1
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prove_missing_node() {
+        let leaves: Vec<B256> = (0..4).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        let store = MemoryStore::new();
+        let err = prove(&store, &mmr, 2).await.unwrap_err();
+        assert!(matches!(
+            err,
+            StoreError::MissingNode(NodePosition { level: 0, index: 3 })
+        ));
+        assert_eq!(err.code(), 1001);
+        assert_eq!(
+            StoreError::from(MMRError::Cancelled).code(),
+            MMRError::Cancelled.code()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_append_leaves_mmr_unchanged() {
+        let store = MemoryStore::new();
+        let mut mmr = MMR::new().with_max_end(2);
+        let leaves: Vec<B256> = (0..3).map(|_| get_random_hash()).collect();
+        assert!(matches!(
+            append_leaves(&store, &mut mmr, &leaves).await,
+            Err(StoreError::Mmr(MMRError::RangeLimitReached))
+        ));
+        assert_eq!(mmr.end(), 0);
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use super::{NodePosition, NodeStore, StoreError};
+use crate::lru::Lru;
+use alloy_primitives::B256;
+use object_store::{path::Path, ObjectStore, PutPayload};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+type PageKey = (u32, u64);
+
+impl From<object_store::Error> for StoreError {
+    fn from(err: object_store::Error) -> Self {
+        StoreError::Backend(Box::new(err))
+    }
+}
+
+/// A [`NodeStore`] backed by an object store such as S3, GCS or Azure Blob Storage.
+///
+/// Nodes are batched into pages of `page_size` consecutive nodes of one level, stored under
+/// `<prefix>/<level>/<page>` as concatenated hashes. Pages are only ever extended, since
+/// nodes never change. Full pages are immutable, so they are kept in a local LRU cache;
+/// partially filled pages are always fetched, as other writers may have extended them.
+#[derive(Debug)]
+pub struct ObjectNodeStore {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    page_size: u64,
+    cache: Mutex<Lru<PageKey, Arc<Vec<B256>>>>,
+}
+
+impl ObjectNodeStore {
+    /// Creates a store writing pages of `page_size` nodes under `prefix`, caching up to
+    /// `cache_pages` full pages
+    pub fn new(
+        store: Arc<dyn ObjectStore>,
+        prefix: Path,
+        page_size: u64,
+        cache_pages: usize,
+    ) -> Self {
+        assert!(page_size > 0, "page_size must be positive");
+        Self {
+            store,
+            prefix,
+            page_size,
+            cache: Mutex::new(Lru::new(cache_pages)),
+        }
+    }
+
+    fn page_path(&self, (level, page): PageKey) -> Path {
+        self.prefix.child(level.to_string()).child(page.to_string())
+    }
+
+    /// Loads a page from the cache or the object store; a missing page is empty
+    async fn load_page(&self, key: PageKey) -> Result<Arc<Vec<B256>>, StoreError> {
+        if let Some(page) = self.cache.lock().unwrap().get(&key) {
+            return Ok(page.clone());
+        }
+        let bytes = match self.store.get(&self.page_path(key)).await {
+            Ok(result) => result.bytes().await?,
+            Err(object_store::Error::NotFound { .. }) => return Ok(Arc::new(vec![])),
+            Err(err) => return Err(err.into()),
+        };
+        if bytes.len() % 32 != 0 || bytes.len() as u64 > 32 * self.page_size {
+            return Err(StoreError::Corrupt);
+        }
+        let page = Arc::new(bytes.chunks_exact(32).map(B256::from_slice).collect());
+        self.cache_if_full(key, &page);
+        Ok(page)
+    }
+
+    fn cache_if_full(&self, key: PageKey, page: &Arc<Vec<B256>>) {
+        if page.len() as u64 == self.page_size {
+            self.cache.lock().unwrap().insert(key, page.clone());
+        }
+    }
+
+    /// Groups positions by page, keeping their offset within the page
+    fn by_page<T>(
+        &self,
+        items: impl Iterator<Item = (NodePosition, T)>,
+    ) -> BTreeMap<PageKey, Vec<(usize, T)>> {
+        let mut pages: BTreeMap<PageKey, Vec<(usize, T)>> = BTreeMap::new();
+        for (position, item) in items {
+            let key = (position.level, position.index / self.page_size);
+            let offset = (position.index % self.page_size) as usize;
+            pages.entry(key).or_default().push((offset, item));
+        }
+        pages
+    }
+}
+
+impl NodeStore for ObjectNodeStore {
+    async fn get_nodes(&self, positions: &[NodePosition]) -> Result<Vec<Option<B256>>, StoreError> {
+        let mut nodes = vec![None; positions.len()];
+        let pages = self.by_page(positions.iter().copied().zip(0..));
+        for (key, slots) in pages {
+            let page = self.load_page(key).await?;
+            for (offset, i) in slots {
+                nodes[i] = page.get(offset).copied();
+            }
+        }
+        Ok(nodes)
+    }
+
+    async fn put_nodes(&self, nodes: &[(NodePosition, B256)]) -> Result<(), StoreError> {
+        let pages = self.by_page(nodes.iter().copied());
+        for (key, writes) in pages {
+            let mut page = (*self.load_page(key).await?).clone();
+            for (offset, node) in writes {
+                // Pages can't have holes, so a node must follow the ones already stored.
+                if offset > page.len() {
+                    let index = key.1 * self.page_size + page.len() as u64;
+                    return Err(StoreError::MissingNode(NodePosition::new(key.0, index)));
+                }
+                if offset == page.len() {
+                    page.push(node);
+                } else {
+                    page[offset] = node;
+                }
+            }
+            let bytes: Vec<u8> = page.iter().flat_map(|node| node.0).collect();
+            self.store
+                .put(&self.page_path(key), PutPayload::from(bytes))
+                .await?;
+            self.cache_if_full(key, &Arc::new(page));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::full::FullMMR;
+    use crate::mmr::MMR;
+    use crate::store::{append_leaves, prove};
+    use crate::utils::hash::get_random_hash;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_append_and_prove() {
+        let leaves: Vec<B256> = (0..37).map(|_| get_random_hash()).collect();
+        let objects = Arc::new(InMemory::new());
+        let store = ObjectNodeStore::new(objects.clone(), Path::from("mmr"), 4, 8);
+        let mut mmr = MMR::new();
+        for chunk in leaves.chunks(5) {
+            append_leaves(&store, &mut mmr, chunk).await.unwrap();
+        }
+
+        // A fresh store, as a serverless prover would open it.
+        let store = ObjectNodeStore::new(objects.clone(), Path::from("mmr"), 4, 8);
+        let full = FullMMR::from_leaves(&leaves);
+        for index in 0..37 {
+            assert_eq!(
+                prove(&store, &mmr, index).await.unwrap(),
+                full.prove(index).unwrap()
+            );
+        }
+        assert!(store.cache.lock().unwrap().len() <= 8);
+
+        // Level 0 holds 37 leaves in 10 pages, the last one partial.
+        let page = objects.get(&Path::from("mmr/0/9")).await.unwrap();
+        assert_eq!(page.bytes().await.unwrap().len(), 32);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_page() {
+        let objects = Arc::new(InMemory::new());
+        objects
+            .put(&Path::from("mmr/0/0"), PutPayload::from(vec![0u8; 33]))
+            .await
+            .unwrap();
+        let store = ObjectNodeStore::new(objects, Path::from("mmr"), 4, 8);
+        assert!(matches!(
+            store.get_nodes(&[NodePosition::new(0, 0)]).await,
+            Err(StoreError::Corrupt)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_put_nodes_rejects_holes() {
+        let store = ObjectNodeStore::new(Arc::new(InMemory::new()), Path::from("mmr"), 4, 8);
+        let node = get_random_hash();
+        assert!(matches!(
+            store.put_nodes(&[(NodePosition::new(1, 2), node)]).await,
+            Err(StoreError::MissingNode(NodePosition { level: 1, index: 0 }))
+        ));
+        // The next page starts empty, so only its first node can be written.
+        store
+            .put_nodes(&[(NodePosition::new(1, 4), node)])
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_nodes(&[NodePosition::new(1, 4)]).await.unwrap(),
+            vec![Some(node)]
+        );
+    }
+}
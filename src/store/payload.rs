@@ -0,0 +1,349 @@
+use super::{append_leaves, NodeStore, StoreError};
+use crate::mmr::MMR;
+use alloy_primitives::B256;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+/// Asynchronous storage of the records leaves were hashed from, by leaf index.
+///
+/// Unlike nodes, payloads are stored as opaque bytes, so they can be compressed; see
+/// [`CompressedPayloads`].
+pub trait PayloadStore: Sync {
+    /// Fetches the payloads of the leaves at `indices`, with `None` for the ones never stored
+    fn get_payloads(
+        &self,
+        indices: &[u64],
+    ) -> impl Future<Output = Result<Vec<Option<Vec<u8>>>, StoreError>> + Send;
+
+    /// Stores `payloads` by leaf index
+    fn put_payloads(
+        &self,
+        payloads: &[(u64, Vec<u8>)],
+    ) -> impl Future<Output = Result<(), StoreError>> + Send;
+}
+
+/// A [`PayloadStore`] keeping payloads in a hash map.
+#[derive(Debug, Default)]
+pub struct MemoryPayloadStore {
+    payloads: Mutex<HashMap<u64, Vec<u8>>>,
+}
+
+impl MemoryPayloadStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of stored payloads
+    pub fn len(&self) -> usize {
+        self.payloads.lock().unwrap().len()
+    }
+
+    /// Returns true if no payload is stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total size of the stored payloads in bytes
+    pub fn stored_bytes(&self) -> usize {
+        self.payloads.lock().unwrap().values().map(Vec::len).sum()
+    }
+}
+
+impl PayloadStore for MemoryPayloadStore {
+    async fn get_payloads(&self, indices: &[u64]) -> Result<Vec<Option<Vec<u8>>>, StoreError> {
+        let payloads = self.payloads.lock().unwrap();
+        Ok(indices
+            .iter()
+            .map(|index| payloads.get(index).cloned())
+            .collect())
+    }
+
+    async fn put_payloads(&self, payloads: &[(u64, Vec<u8>)]) -> Result<(), StoreError> {
+        self.payloads
+            .lock()
+            .unwrap()
+            .extend(payloads.iter().cloned());
+        Ok(())
+    }
+}
+
+/// A compression codec for stored payloads, identified by a tag stored with every payload.
+pub trait Codec: Send + Sync {
+    /// The tag stored before every payload compressed with this codec. Tags are never
+    /// reused, so payloads stay readable after switching codecs.
+    const TAG: u8;
+
+    /// Compresses a payload
+    fn compress(&self, data: &[u8]) -> Vec<u8>;
+
+    /// Decompresses a payload compressed by `compress`, or returns `StoreError::Corrupt`
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, StoreError>;
+}
+
+/// Stores payloads as they are.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Uncompressed;
+
+impl Codec for Uncompressed {
+    const TAG: u8 = 0;
+
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        data.to_vec()
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, StoreError> {
+        Ok(data.to_vec())
+    }
+}
+
+/// LZ4 block compression, fast enough for the write path. Only available with the `lz4`
+/// feature.
+#[cfg(feature = "lz4")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Lz4;
+
+#[cfg(feature = "lz4")]
+impl Codec for Lz4 {
+    const TAG: u8 = 1;
+
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        lz4_flex::compress_prepend_size(data)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, StoreError> {
+        lz4_flex::decompress_size_prepended(data).map_err(|_| StoreError::Corrupt)
+    }
+}
+
+/// Zstandard compression at `level`, for the best ratio on cold payloads. Only available
+/// with the `zstd` feature.
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy)]
+pub struct Zstd {
+    pub level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl Default for Zstd {
+    fn default() -> Self {
+        Self {
+            level: zstd::DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Codec for Zstd {
+    const TAG: u8 = 2;
+
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        zstd::encode_all(data, self.level).expect("compressing from memory can't fail")
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, StoreError> {
+        zstd::decode_all(data).map_err(|_| StoreError::Corrupt)
+    }
+}
+
+/// Wraps a [`PayloadStore`], compressing the payloads written through it with `C`.
+///
+/// Every payload is stored behind the tag of its codec. Reads decode payloads tagged with
+/// `C` or stored uncompressed, so a store can switch from `Uncompressed` to a codec
+/// without rewriting what it already holds.
+#[derive(Debug)]
+pub struct CompressedPayloads<S, C> {
+    inner: S,
+    codec: C,
+}
+
+impl<S: PayloadStore, C: Codec> CompressedPayloads<S, C> {
+    /// Wraps `inner`, compressing new payloads with `codec`
+    pub fn new(inner: S, codec: C) -> Self {
+        Self { inner, codec }
+    }
+
+    /// Returns the wrapped store
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Decodes a stored payload by its tag, or returns `StoreError::UnknownCodec` if it was
+    /// compressed with another codec
+    fn decode(&self, stored: &[u8]) -> Result<Vec<u8>, StoreError> {
+        let (&tag, data) = stored.split_first().ok_or(StoreError::Corrupt)?;
+        if tag == C::TAG {
+            self.codec.decompress(data)
+        } else if tag == Uncompressed::TAG {
+            Ok(data.to_vec())
+        } else {
+            Err(StoreError::UnknownCodec(tag))
+        }
+    }
+}
+
+impl<S: PayloadStore, C: Codec> PayloadStore for CompressedPayloads<S, C> {
+    async fn get_payloads(&self, indices: &[u64]) -> Result<Vec<Option<Vec<u8>>>, StoreError> {
+        self.inner
+            .get_payloads(indices)
+            .await?
+            .into_iter()
+            .map(|stored| stored.map(|stored| self.decode(&stored)).transpose())
+            .collect()
+    }
+
+    async fn put_payloads(&self, payloads: &[(u64, Vec<u8>)]) -> Result<(), StoreError> {
+        let tagged: Vec<(u64, Vec<u8>)> = payloads
+            .iter()
+            .map(|(index, payload)| {
+                let mut stored = vec![C::TAG];
+                stored.extend_from_slice(&self.codec.compress(payload));
+                (*index, stored)
+            })
+            .collect();
+        self.inner.put_payloads(&tagged).await
+    }
+}
+
+/// Appends the leaves hashed from `payloads` like `MMR::append_bytes_batch`, storing the
+/// payloads by leaf index in `payload_store` and the nodes they complete in `store`.
+///
+/// The payloads are stored first, so every leaf of the MMR has its payload. On error the
+/// MMR is unchanged, and payloads stored past its end are overwritten by the next append.
+pub async fn append_payloads<S: NodeStore, P: PayloadStore>(
+    store: &S,
+    payload_store: &P,
+    mmr: &mut MMR,
+    payloads: &[Vec<u8>],
+) -> Result<(), StoreError> {
+    let leaves: Vec<B256> = payloads
+        .iter()
+        .map(|payload| mmr.hash_leaf_bytes(payload))
+        .collect();
+    let indexed: Vec<(u64, Vec<u8>)> = (mmr.end()..).zip(payloads.iter().cloned()).collect();
+    payload_store.put_payloads(&indexed).await?;
+    append_leaves(store, mmr, &leaves).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{prove, MemoryStore};
+
+    /// Block-header-like payloads: mostly fixed fields, with a few changing bytes.
+    fn headers(count: u64) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|number| {
+                let mut header = vec![0u8; 508];
+                header[..8].copy_from_slice(&number.to_be_bytes());
+                header
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_append_payloads() {
+        let payloads = headers(9);
+        let store = MemoryStore::new();
+        let payload_store = MemoryPayloadStore::new();
+        let mut mmr = MMR::new();
+        append_payloads(&store, &payload_store, &mut mmr, &payloads[..4])
+            .await
+            .unwrap();
+        append_payloads(&store, &payload_store, &mut mmr, &payloads[4..])
+            .await
+            .unwrap();
+
+        let mut expected = MMR::new();
+        expected.append_bytes_batch(&payloads);
+        assert_eq!(mmr, expected);
+        let stored = payload_store.get_payloads(&[0, 6, 9]).await.unwrap();
+        assert_eq!(
+            stored,
+            vec![Some(payloads[0].clone()), Some(payloads[6].clone()), None]
+        );
+
+        // A stored payload proves against the MMR through its leaf hash.
+        let proof = prove(&store, &mmr, 6).await.unwrap();
+        assert!(proof.verify(&mmr, crate::utils::hash::hash_leaf_bytes(&payloads[6])));
+    }
+
+    #[tokio::test]
+    async fn test_uncompressed_is_tagged() {
+        let store = CompressedPayloads::new(MemoryPayloadStore::new(), Uncompressed);
+        store
+            .put_payloads(&[(0, b"record".to_vec())])
+            .await
+            .unwrap();
+        assert_eq!(
+            store.inner().get_payloads(&[0]).await.unwrap(),
+            vec![Some(b"\0record".to_vec())]
+        );
+        assert_eq!(
+            store.get_payloads(&[0]).await.unwrap(),
+            vec![Some(b"record".to_vec())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_and_corrupt_payloads() {
+        let store = CompressedPayloads::new(MemoryPayloadStore::new(), Uncompressed);
+        store
+            .inner()
+            .put_payloads(&[(0, vec![200, 1, 2]), (1, vec![])])
+            .await
+            .unwrap();
+        assert!(matches!(
+            store.get_payloads(&[0]).await,
+            Err(StoreError::UnknownCodec(200))
+        ));
+        assert!(matches!(
+            store.get_payloads(&[1]).await,
+            Err(StoreError::Corrupt)
+        ));
+    }
+
+    #[cfg(feature = "lz4")]
+    #[tokio::test]
+    async fn test_lz4() {
+        let payloads = headers(16);
+        let raw = CompressedPayloads::new(MemoryPayloadStore::new(), Uncompressed);
+        let indexed: Vec<(u64, Vec<u8>)> = (0..).zip(payloads.iter().cloned()).collect();
+        raw.put_payloads(&indexed[..8]).await.unwrap();
+
+        // Switching codecs keeps the payloads written before readable.
+        let compressed = CompressedPayloads::new(raw.inner, Lz4);
+        compressed.put_payloads(&indexed[8..]).await.unwrap();
+        let indices: Vec<u64> = (0..16).collect();
+        let stored = compressed.get_payloads(&indices).await.unwrap();
+        assert!(stored
+            .iter()
+            .zip(&payloads)
+            .all(|(stored, payload)| stored.as_ref() == Some(payload)));
+        assert!(compressed.inner().stored_bytes() < 8 * 509 + 8 * 509 / 2);
+
+        assert!(matches!(
+            Lz4.decompress(&[4, 0, 0, 0, 0xff]),
+            Err(StoreError::Corrupt)
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn test_zstd() {
+        let payloads = headers(8);
+        let store = CompressedPayloads::new(MemoryPayloadStore::new(), Zstd::default());
+        let indexed: Vec<(u64, Vec<u8>)> = (0..).zip(payloads.iter().cloned()).collect();
+        store.put_payloads(&indexed).await.unwrap();
+        let stored = store
+            .get_payloads(&(0..8).collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert!(stored
+            .iter()
+            .zip(&payloads)
+            .all(|(stored, payload)| stored.as_ref() == Some(payload)));
+        assert!(store.inner().stored_bytes() < 8 * 509 / 2);
+    }
+}
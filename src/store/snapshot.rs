@@ -0,0 +1,144 @@
+use super::commit::{append_committed, recover, CommitLog};
+use super::{append_leaves, prove, NodeStore, StoreError};
+use crate::mmr::MMR;
+use crate::proof::InclusionProof;
+use alloy_primitives::B256;
+use std::sync::Arc;
+
+/// An MMR whose nodes are kept in a [`NodeStore`].
+///
+/// Appends flush their nodes before committing the new state, and readers work against
+/// [`Snapshot`]s of committed states, so a proof never mixes nodes of different states even
+/// while an append is being flushed.
+#[derive(Debug)]
+pub struct StoredMMR<S> {
+    store: Arc<S>,
+    mmr: MMR,
+}
+
+impl<S: NodeStore> StoredMMR<S> {
+    /// Creates a storage-backed MMR from its committed state and the store holding its nodes
+    pub fn new(store: Arc<S>, mmr: MMR) -> Self {
+        Self { store, mmr }
+    }
+
+    /// Returns the committed state
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+
+    /// Returns the store holding the nodes
+    pub fn store(&self) -> &Arc<S> {
+        &self.store
+    }
+
+    /// Appends `leaves`, committing the new state once their nodes are stored
+    pub async fn append(&mut self, leaves: &[B256]) -> Result<(), StoreError> {
+        append_leaves(self.store.as_ref(), &mut self.mmr, leaves).await
+    }
+
+    /// Appends `leaves` and records the new state in `log` once their nodes are stored
+    pub async fn append_committed<L: CommitLog>(
+        &mut self,
+        log: &L,
+        leaves: &[B256],
+    ) -> Result<(), StoreError> {
+        append_committed(self.store.as_ref(), log, &mut self.mmr, leaves).await
+    }
+
+    /// Reopens the last state committed to `log`, or `None` if there is none, keyed and
+    /// limited like `template`, see [`recover`]
+    pub async fn recover<L: CommitLog>(
+        store: Arc<S>,
+        log: &L,
+        template: &MMR,
+    ) -> Result<Option<Self>, StoreError> {
+        let mmr = recover(store.as_ref(), log, template).await?;
+        Ok(mmr.map(|mmr| Self { store, mmr }))
+    }
+
+    /// Returns a snapshot of the committed state, unaffected by later appends
+    pub fn snapshot(&self) -> Snapshot<S> {
+        Snapshot {
+            store: self.store.clone(),
+            mmr: self.mmr.clone(),
+        }
+    }
+}
+
+/// A read-only view of a [`StoredMMR`] at a committed state.
+#[derive(Debug)]
+pub struct Snapshot<S> {
+    store: Arc<S>,
+    mmr: MMR,
+}
+
+impl<S: NodeStore> Snapshot<S> {
+    /// Returns the log sequence number of the snapshot, the end of its MMR
+    pub fn lsn(&self) -> u64 {
+        self.mmr.end()
+    }
+
+    /// Returns the MMR at the snapshot
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+
+    /// Returns the root at the snapshot
+    pub fn get_root(&self) -> B256 {
+        self.mmr.get_root()
+    }
+
+    /// Generates an inclusion proof against the snapshot's root
+    pub async fn prove(&self, leaf_index: u64) -> Result<InclusionProof, StoreError> {
+        prove(self.store.as_ref(), &self.mmr, leaf_index).await
+    }
+}
+
+impl<S> Clone for Snapshot<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            mmr: self.mmr.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MemoryStore, NodePosition};
+    use crate::utils::hash::get_random_hash;
+
+    #[tokio::test]
+    async fn test_snapshot_survives_appends() {
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        let mut stored = StoredMMR::new(Arc::new(MemoryStore::new()), MMR::new());
+        stored.append(&leaves[..5]).await.unwrap();
+        let snapshot = stored.snapshot();
+
+        // Flush nodes of a later state, as a concurrent append would have done halfway.
+        let leaf = get_random_hash();
+        stored
+            .store()
+            .put_nodes(&[(NodePosition::new(0, 5), leaf)])
+            .await
+            .unwrap();
+        stored.append(&leaves[5..]).await.unwrap();
+
+        assert_eq!(snapshot.lsn(), 5);
+        assert_eq!(
+            snapshot.get_root(),
+            MMR::from_leaves(&leaves[..5]).get_root()
+        );
+        for index in 0..5 {
+            let proof = snapshot.prove(index).await.unwrap();
+            assert!(proof.verify(snapshot.mmr(), leaves[index as usize]));
+        }
+        assert!(snapshot.prove(5).await.is_err());
+
+        let latest = stored.snapshot();
+        let proof = latest.prove(9).await.unwrap();
+        assert!(proof.verify(latest.mmr(), leaves[9]));
+    }
+}
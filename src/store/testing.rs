@@ -0,0 +1,104 @@
+use super::commit::{Commit, CommitLog};
+use super::{NodePosition, NodeStore, StoreError};
+use alloy_primitives::B256;
+use std::sync::Mutex;
+
+/// Where a [`FaultyStore`] simulates a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashPoint {
+    /// While storing nodes, after the first `n` nodes of the batch were written
+    DuringNodes(usize),
+    /// After the nodes are stored, before the commit is written
+    BeforeCommit,
+    /// After the commit is written, before the caller learns of it
+    AfterCommit,
+}
+
+/// Wraps a [`NodeStore`] and a [`CommitLog`], failing the next write at an armed
+/// [`CrashPoint`].
+///
+/// Whatever was written before the crash stays in the wrapped store and log, so a test can
+/// drop its in-memory state, recover from them and check the outcome of the interrupted
+/// append.
+#[derive(Debug)]
+pub struct FaultyStore<S, L> {
+    store: S,
+    log: L,
+    crash: Mutex<Option<CrashPoint>>,
+}
+
+impl<S: NodeStore, L: CommitLog> FaultyStore<S, L> {
+    /// Wraps `store` and `log`, with no crash armed
+    pub fn new(store: S, log: L) -> Self {
+        Self {
+            store,
+            log,
+            crash: Mutex::new(None),
+        }
+    }
+
+    /// Arms a crash, disarmed once it fires
+    pub fn arm(&self, crash: CrashPoint) {
+        *self.crash.lock().unwrap() = Some(crash);
+    }
+
+    /// Returns the wrapped store
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the wrapped log
+    pub fn log(&self) -> &L {
+        &self.log
+    }
+
+    /// Disarms and returns the armed crash if `fires` selects it
+    fn take_crash(&self, fires: impl FnOnce(CrashPoint) -> bool) -> Option<CrashPoint> {
+        let mut crash = self.crash.lock().unwrap();
+        crash
+            .filter(|&point| fires(point))
+            .inspect(|_| *crash = None)
+    }
+}
+
+fn crashed() -> StoreError {
+    StoreError::Backend("injected crash".into())
+}
+
+impl<S: NodeStore, L: CommitLog> NodeStore for FaultyStore<S, L> {
+    async fn get_nodes(&self, positions: &[NodePosition]) -> Result<Vec<Option<B256>>, StoreError> {
+        self.store.get_nodes(positions).await
+    }
+
+    async fn put_nodes(&self, nodes: &[(NodePosition, B256)]) -> Result<(), StoreError> {
+        match self.take_crash(|point| matches!(point, CrashPoint::DuringNodes(_))) {
+            Some(CrashPoint::DuringNodes(written)) => {
+                self.store
+                    .put_nodes(&nodes[..written.min(nodes.len())])
+                    .await?;
+                Err(crashed())
+            }
+            _ => self.store.put_nodes(nodes).await,
+        }
+    }
+}
+
+impl<S: NodeStore, L: CommitLog> CommitLog for FaultyStore<S, L> {
+    async fn commit(&self, commit: Commit) -> Result<(), StoreError> {
+        let crash = self.take_crash(|point| {
+            matches!(point, CrashPoint::BeforeCommit | CrashPoint::AfterCommit)
+        });
+        match crash {
+            Some(CrashPoint::BeforeCommit) => Err(crashed()),
+            Some(_) => {
+                self.log.commit(commit).await?;
+                Err(crashed())
+            }
+            None => self.log.commit(commit).await,
+        }
+    }
+
+    async fn last_commit(&self) -> Result<Option<Commit>, StoreError> {
+        self.log.last_commit().await
+    }
+}
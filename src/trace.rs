@@ -0,0 +1,127 @@
+use crate::mmr::MMR;
+use crate::utils::hash::{hash_to_parent, HashKey};
+use alloy_primitives::B256;
+
+/// Maximum number of peaks a single append can merge with, one per bit of a `u64` index.
+pub const MAX_TRACE_LEN: usize = u64::BITS as usize;
+
+/// The hashing performed by a single append, in a fixed-size layout.
+///
+/// Entries past `len` are always `B256::ZERO`, so a verifier can walk all `MAX_TRACE_LEN`
+/// slots with a constant loop bound and no heap allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppendTrace {
+    leaf: B256,
+    len: u8,
+    siblings: [B256; MAX_TRACE_LEN],
+}
+
+impl AppendTrace {
+    /// Returns the appended leaf
+    pub fn leaf(&self) -> B256 {
+        self.leaf
+    }
+
+    /// Returns the number of peaks the leaf was merged with
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns true if the leaf became a peak without merging
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the merged peaks, from the lowest (most recent) to the highest
+    pub fn siblings(&self) -> &[B256] {
+        &self.siblings[..self.len()]
+    }
+
+    /// Returns all slots of the trace, including the zero padding past `len`
+    pub fn entries(&self) -> &[B256; MAX_TRACE_LEN] {
+        &self.siblings
+    }
+
+    /// Recomputes the peak the leaf was folded into, for an MMR without a key
+    pub fn replay(&self) -> B256 {
+        self.siblings()
+            .iter()
+            .fold(self.leaf, |acc, sibling| hash_to_parent(sibling, &acc))
+    }
+
+    /// Like `replay`, for an MMR hashing with `key`, see `MMR::with_key`
+    pub fn replay_keyed(&self, key: &HashKey) -> B256 {
+        self.siblings()
+            .iter()
+            .fold(self.leaf, |acc, sibling| key.hash_to_parent(sibling, &acc))
+    }
+}
+
+impl MMR {
+    /// Appends an element and returns the trace of the hashes it took
+    pub fn append_traced(&mut self, element: B256) -> AppendTrace {
+        let mut trace = AppendTrace {
+            leaf: element,
+            len: 0,
+            siblings: [B256::ZERO; MAX_TRACE_LEN],
+        };
+        for (slot, peak) in trace
+            .siblings
+            .iter_mut()
+            .zip(self.peaks().iter().rev().take(self.next_merges()))
+        {
+            *slot = *peak;
+            trace.len += 1;
+        }
+        self.append(element);
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_replay_matches_peak() {
+        let mut mmr = MMR::new();
+        for end in 0..40_u64 {
+            let trace = mmr.append_traced(get_random_hash());
+            assert_eq!(trace.len() as u32, (!end).trailing_zeros());
+            assert_eq!(Some(&trace.replay()), mmr.peaks().last());
+            assert!(trace.entries()[trace.len()..]
+                .iter()
+                .all(|entry| *entry == B256::ZERO));
+        }
+    }
+
+    #[test]
+    fn test_longest_trace() {
+        // 63 peaks of sizes 2^62, ..., 1 all merge with the next leaf.
+        let peaks: Vec<B256> = (0..63).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::from_params(0, (1 << 63) - 1, peaks.clone()).unwrap();
+        let trace = mmr.append_traced(get_random_hash());
+        assert_eq!(trace.len(), 63);
+        assert!(trace.siblings().iter().eq(peaks.iter().rev()));
+        assert_eq!(mmr.peaks(), &[trace.replay()]);
+    }
+
+    #[test]
+    fn test_replay_keyed() {
+        let key = get_random_hash();
+        let mut mmr = MMR::new().with_key(key);
+        for _ in 0..23 {
+            let trace = mmr.append_traced(get_random_hash());
+            assert_eq!(
+                Some(&trace.replay_keyed(&HashKey::new(key))),
+                mmr.peaks().last()
+            );
+        }
+        // The append at 23 merges three peaks, which unkeyed hashing doesn't reproduce.
+        assert_ne!(
+            Some(&mmr.append_traced(get_random_hash()).replay()),
+            mmr.peaks().last()
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use super::hash::{Hasher, Keccak256Hasher};
+use alloy_primitives::B256;
+
+/// Bags the peaks of a compact range into a single root.
+///
+/// The peaks of the left part of the decomposition are folded left to right, the peaks of the
+/// right part are folded right to left, and the two resulting roots are hashed together. An
+/// empty side is represented by `B256::ZERO` and skipped.
+///
+/// # Arguments
+///
+/// * `left` - Bitmap representing the left part of the range, as returned by `decompose`
+/// * `right` - Bitmap representing the right part of the range, as returned by `decompose`
+/// * `peaks` - The peaks of the range, left part first. Must hold one peak per set bit.
+///
+/// # Returns
+///
+/// The root committing to all the peaks, or `B256::ZERO` if there are none.
+///
+/// # Examples
+///
+/// ```
+/// use alloy_primitives::B256;
+/// use rust_mmr::utils::{bag::bag_peaks, hash::hash_to_parent, range::decompose};
+///
+/// let peaks = [B256::repeat_byte(1), B256::repeat_byte(2), B256::repeat_byte(3)];
+/// let (left, right) = decompose(3, 7);
+/// assert_eq!(
+///     bag_peaks(left, right, &peaks),
+///     hash_to_parent(&peaks[0], &hash_to_parent(&peaks[1], &peaks[2]))
+/// );
+/// ```
+pub fn bag_peaks(left: u64, right: u64, peaks: &[B256]) -> B256 {
+    bag_peaks_with::<Keccak256Hasher>(left, right, peaks)
+}
+
+/// Like [`bag_peaks`], hashing with `H`. Without peaks, the root is `H::empty_root()`.
+pub fn bag_peaks_with<H: Hasher>(left: u64, right: u64, peaks: &[B256]) -> B256 {
+    if peaks.is_empty() {
+        return H::empty_root();
+    }
+    bag_peaks_by(left, right, peaks, H::hash_parent)
+}
+
+/// Like [`bag_peaks`], hashing parents with `hash`.
+pub(crate) fn bag_peaks_by(
+    left: u64,
+    right: u64,
+    peaks: &[B256],
+    hash: impl Fn(&B256, &B256) -> B256,
+) -> B256 {
+    debug_assert_eq!(
+        (left.count_ones() + right.count_ones()) as usize,
+        peaks.len()
+    );
+    let (left_peaks, right_peaks) = peaks.split_at((left.count_ones() as usize).min(peaks.len()));
+
+    // Bag the peaks for the left side
+    let left_root = left_peaks
+        .iter()
+        .fold(None, |acc, &peak| match acc {
+            None => Some(peak),
+            Some(prev) => Some(hash(&prev, &peak)),
+        })
+        .unwrap_or(B256::ZERO);
+
+    // Bag the peaks for the right side
+    let right_root = right_peaks
+        .iter()
+        .rfold(None, |acc, &peak| match acc {
+            None => Some(peak),
+            Some(prev) => Some(hash(&peak, &prev)),
+        })
+        .unwrap_or(B256::ZERO);
+
+    // Combine the left and right roots
+    if left_root == B256::ZERO {
+        right_root
+    } else if right_root == B256::ZERO {
+        left_root
+    } else {
+        hash(&left_root, &right_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::{get_random_hash, hash_to_parent};
+
+    #[test]
+    fn test_bag_peaks_empty() {
+        assert_eq!(bag_peaks(0, 0, &[]), B256::ZERO);
+    }
+
+    #[test]
+    fn test_bag_peaks_right_only() {
+        // Subtree sizes [], [4, 2, 1].
+        let peaks = [get_random_hash(), get_random_hash(), get_random_hash()];
+        assert_eq!(
+            bag_peaks(0, 7, &peaks),
+            hash_to_parent(&peaks[0], &hash_to_parent(&peaks[1], &peaks[2]))
+        );
+    }
+
+    #[test]
+    fn test_bag_peaks_left_only() {
+        // Subtree sizes [1, 2, 4], [].
+        let peaks = [get_random_hash(), get_random_hash(), get_random_hash()];
+        assert_eq!(
+            bag_peaks(7, 0, &peaks),
+            hash_to_parent(&hash_to_parent(&peaks[0], &peaks[1]), &peaks[2])
+        );
+    }
+
+    #[test]
+    fn test_bag_peaks_both_sides() {
+        // Subtree sizes [4, 8], [8, 4].
+        let peaks = [
+            get_random_hash(),
+            get_random_hash(),
+            get_random_hash(),
+            get_random_hash(),
+        ];
+        assert_eq!(
+            bag_peaks(12, 12, &peaks),
+            hash_to_parent(
+                &hash_to_parent(&peaks[0], &peaks[1]),
+                &hash_to_parent(&peaks[2], &peaks[3])
+            )
+        );
+    }
+
+    #[test]
+    fn test_bag_peaks_single() {
+        let peak = get_random_hash();
+        assert_eq!(bag_peaks(1, 0, &[peak]), peak);
+        assert_eq!(bag_peaks(0, 8, &[peak]), peak);
+    }
+}
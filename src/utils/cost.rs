@@ -0,0 +1,193 @@
+use super::range::{decompose, get_expected_num_peaks};
+use core::ops::Range;
+
+/// Calculates the number of parent hashes the next append to a range computes.
+///
+/// # Arguments
+///
+/// * `start` - The start of the MMR range (inclusive)
+/// * `end` - The end of the MMR range (exclusive)
+///
+/// # Returns
+///
+/// The number of peaks the appended leaf merges with.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::cost::hashes_for_append;
+///
+/// // The leaf at index 7 completes the subtrees [6, 8), [4, 8) and [0, 8).
+/// assert_eq!(hashes_for_append(0, 7), 3);
+/// assert_eq!(hashes_for_append(0, 8), 0);
+/// ```
+pub fn hashes_for_append(start: u64, end: u64) -> u64 {
+    let (_, right) = decompose(start, end);
+    (!right).trailing_zeros() as u64
+}
+
+/// Calculates the number of parent hashes computed by appending `count` leaves to a range.
+///
+/// Every append adds one peak and every hash removes one, so the count follows from the
+/// number of peaks before and after, without simulating the appends.
+///
+/// # Arguments
+///
+/// * `start` - The start of the MMR range (inclusive)
+/// * `end` - The end of the MMR range (exclusive)
+/// * `count` - The number of leaves appended; `end + count` must not overflow
+///
+/// # Returns
+///
+/// The total number of parent hashes.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::cost::hashes_for_batch;
+///
+/// // Building a perfect tree of 8 leaves computes its 7 internal nodes.
+/// assert_eq!(hashes_for_batch(0, 0, 8), 7);
+/// ```
+pub fn hashes_for_batch(start: u64, end: u64, count: u64) -> u64 {
+    get_expected_num_peaks(start, end) + count - get_expected_num_peaks(start, end + count)
+}
+
+/// Calculates the number of parent hashes computed by merging two bordering ranges.
+///
+/// # Arguments
+///
+/// * `a` - The range of the left MMR
+/// * `b` - The range of the right MMR
+///
+/// # Returns
+///
+/// The number of parent hashes, or `None` if `b` does not start where `a` ends.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::cost::hashes_for_merge;
+///
+/// // [0, 3) + [3, 4): leaf 2 merges with leaf 3, then [0, 2) with [2, 4).
+/// assert_eq!(hashes_for_merge(0..3, 3..4), Some(2));
+/// assert_eq!(hashes_for_merge(0..3, 4..5), None);
+/// ```
+pub fn hashes_for_merge(a: Range<u64>, b: Range<u64>) -> Option<u64> {
+    if a.end != b.start || a.start > a.end || b.start > b.end {
+        return None;
+    }
+    Some(
+        get_expected_num_peaks(a.start, a.end) + get_expected_num_peaks(b.start, b.end)
+            - get_expected_num_peaks(a.start, b.end),
+    )
+}
+
+/// Size of an inclusion proof, as returned by [`proof_cost`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofCost {
+    /// The number of hashes the proof carries besides the proven leaf. Each one is combined
+    /// into exactly one parent, so this is also the number of hashes the verifier computes.
+    pub hashes: u64,
+    /// The number of hashes carried on top of a plain proof into the same peak
+    pub overhead: u64,
+}
+
+/// Calculates the size of an inclusion proof that inlines the leaves of a subtree, as
+/// generated by `proof::prove_inlined`.
+///
+/// A plain proof carries one sibling per level of the peak. Inlining the `2^k` leaves of a
+/// subtree replaces its `k` siblings with `2^k - 1` other leaves, so it pays off in round
+/// trips rather than in size: up to `k = 2` it costs at most one extra hash.
+///
+/// # Arguments
+///
+/// * `height` - The height of the peak covering the proven leaf
+/// * `inline_height` - The height of the inlined subtree, capped at `height`
+///
+/// # Returns
+///
+/// The size of the proof, and its overhead over a plain proof.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::cost::{proof_cost, ProofCost};
+///
+/// // A plain proof into a peak of 16 leaves.
+/// assert_eq!(proof_cost(4, 0), ProofCost { hashes: 4, overhead: 0 });
+/// // Inlining 8 leaves: 3 siblings replaced by 7 leaves.
+/// assert_eq!(proof_cost(4, 3), ProofCost { hashes: 8, overhead: 4 });
+/// ```
+pub fn proof_cost(height: u32, inline_height: u32) -> ProofCost {
+    let inline_height = inline_height.min(height) as u64;
+    let hashes = (1 << inline_height) - 1 + (height as u64 - inline_height);
+    ProofCost {
+        hashes,
+        overhead: hashes - height as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::prove_inlined;
+    use crate::utils::hash::get_random_hash;
+    use crate::MMR;
+    use alloy_primitives::B256;
+
+    #[test]
+    fn test_batch_matches_build() {
+        for start in [0, 1, 5, 12] {
+            for end in start..start + 20 {
+                for count in 0..40 {
+                    let expected: u64 = (end..end + count)
+                        .map(|end| hashes_for_append(start, end))
+                        .sum();
+                    assert_eq!(hashes_for_batch(start, end, count), expected);
+                }
+            }
+        }
+
+        let leaves: Vec<B256> = (0..37).map(|_| get_random_hash()).collect();
+        let mut mmr = MMR::from_params(11, 11, vec![]).unwrap();
+        let mut hashes = 0;
+        mmr.append_with_progress(&leaves, 1000, |progress| hashes = progress.hashes);
+        assert_eq!(hashes, hashes_for_batch(11, 11, 37));
+    }
+
+    #[test]
+    fn test_proof_cost_matches_proofs() {
+        let leaves: Vec<B256> = (0..16).map(|_| get_random_hash()).collect();
+        let mmr = MMR::from_leaves(&leaves);
+        for inline_height in 0..6 {
+            let proof = prove_inlined(&mmr, &leaves, 6, inline_height).unwrap();
+            let carried = proof.leaves().len() - 1 + proof.path().len();
+            assert_eq!(proof_cost(4, inline_height).hashes, carried as u64);
+        }
+        assert_eq!(
+            proof_cost(2, 2),
+            ProofCost {
+                hashes: 3,
+                overhead: 1
+            }
+        );
+        assert_eq!(
+            proof_cost(0, 3),
+            ProofCost {
+                hashes: 0,
+                overhead: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(hashes_for_merge(0..4, 4..8), Some(1));
+        assert_eq!(hashes_for_merge(0..0, 0..5), Some(0));
+        assert_eq!(hashes_for_merge(0..5, 5..5), Some(0));
+        // Merging [0, 5) and [5, 8) completes [4, 6), [4, 8) and [0, 8).
+        assert_eq!(hashes_for_merge(0..5, 5..8), Some(3));
+        assert_eq!(hashes_for_merge(0..4, 3..5), None);
+    }
+}
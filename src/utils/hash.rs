@@ -1,6 +1,359 @@
-use alloy_primitives::{Keccak256, B256};
+use crate::scheme::HasherId;
+use alloc::vec::Vec;
+use alloy_primitives::{keccak256, uint, Keccak256, B256, U256};
+use core::convert::Infallible;
+use core::fmt::{self, Debug};
+#[cfg(any(test, feature = "rand"))]
 use rand::Rng;
 
+/// Hash function of an MMR: how internal nodes and length-prefixed leaves are hashed, and
+/// what the root of an empty MMR is.
+///
+/// Implementors are zero-sized markers selecting the function at compile time, so that the
+/// default `Keccak256Hasher` costs nothing over calling keccak directly.
+pub trait Hasher: Debug + Clone + Copy + Default + PartialEq + Eq + Send + Sync + 'static {
+    /// The id recorded in the scheme of MMRs built with this hasher
+    const ID: HasherId;
+
+    /// State reused across the parents hashed by one batch operation
+    type Context: Default;
+
+    /// Hashes two children into their parent
+    fn hash_parent(left: &B256, right: &B256) -> B256;
+
+    /// Hashes two children into their parent like `hash_parent`, through a `context` that
+    /// batch operations keep across calls instead of setting up the hash state per parent
+    fn hash_parent_in(_context: &mut Self::Context, left: &B256, right: &B256) -> B256 {
+        Self::hash_parent(left, right)
+    }
+
+    /// Hashes a variable-length record into a leaf, prefixed with its length as 8
+    /// big-endian bytes
+    fn hash_leaf(data: &[u8]) -> B256;
+
+    /// Returns the root of an MMR without leaves
+    fn empty_root() -> B256 {
+        B256::ZERO
+    }
+
+    /// Returns true if `leaf` may be appended, i.e. lies in the domain of the hash outputs
+    fn accepts_leaf(_leaf: &B256) -> bool {
+        true
+    }
+}
+
+/// Fallible counterpart of [`Hasher`], for hash functions backed by devices that can fail
+/// transiently, such as HSMs or hardware accelerators.
+///
+/// Unlike `Hasher`, implementors are values, so they can hold a device handle. Every
+/// `Hasher` is a `TryHasher` that never fails.
+pub trait TryHasher {
+    /// The error returned when hashing fails
+    type Error;
+
+    /// Hashes two children into their parent
+    fn try_hash_to_parent(&mut self, left: &B256, right: &B256) -> Result<B256, Self::Error>;
+
+    /// Returns the root of an MMR without leaves
+    fn try_empty_root(&mut self) -> Result<B256, Self::Error> {
+        Ok(B256::ZERO)
+    }
+}
+
+impl<H: Hasher> TryHasher for H {
+    type Error = Infallible;
+
+    fn try_hash_to_parent(&mut self, left: &B256, right: &B256) -> Result<B256, Infallible> {
+        Ok(H::hash_parent(left, right))
+    }
+
+    fn try_empty_root(&mut self) -> Result<B256, Infallible> {
+        Ok(H::empty_root())
+    }
+}
+
+/// The default hasher, Keccak256 as used by the EVM.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Keccak256Hasher;
+
+impl Hasher for Keccak256Hasher {
+    const ID: HasherId = HasherId::Keccak256;
+
+    // The sponge state lives on the stack and `finalize` consumes it, so a fresh state is
+    // already the reset: there is no context worth keeping, and every parent goes through
+    // `keccak256` and the VM's keccak hook.
+    type Context = ();
+
+    fn hash_parent(left: &B256, right: &B256) -> B256 {
+        // A single absorb of the concatenation, which `keccak256` also routes to the VM's
+        // keccak hook under alloy's `native-keccak` feature.
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(left.as_slice());
+        preimage[32..].copy_from_slice(right.as_slice());
+        keccak256(preimage)
+    }
+
+    fn hash_leaf(data: &[u8]) -> B256 {
+        let mut hasher = Keccak256::new();
+        hasher.update((data.len() as u64).to_be_bytes());
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// SHA-256, for MMRs shared with systems outside the EVM. Only available with the `sha2`
+/// feature.
+#[cfg(feature = "sha2")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sha256Hasher;
+
+#[cfg(feature = "sha2")]
+impl Hasher for Sha256Hasher {
+    const ID: HasherId = HasherId::Sha256;
+
+    type Context = sha2::Sha256;
+
+    fn hash_parent(left: &B256, right: &B256) -> B256 {
+        use sha2::{Digest, Sha256};
+        B256::from(<[u8; 32]>::from(
+            Sha256::new()
+                .chain_update(left)
+                .chain_update(right)
+                .finalize(),
+        ))
+    }
+
+    fn hash_parent_in(context: &mut sha2::Sha256, left: &B256, right: &B256) -> B256 {
+        use sha2::Digest;
+        context.update(left);
+        context.update(right);
+        B256::from(<[u8; 32]>::from(context.finalize_reset()))
+    }
+
+    fn hash_leaf(data: &[u8]) -> B256 {
+        use sha2::{Digest, Sha256};
+        B256::from(<[u8; 32]>::from(
+            Sha256::new()
+                .chain_update((data.len() as u64).to_be_bytes())
+                .chain_update(data)
+                .finalize(),
+        ))
+    }
+}
+
+/// SHA-256 with the domain separation of RFC 6962 (Certificate Transparency), as used by
+/// transparency-dev/merkle. Only available with the `sha2` feature.
+///
+/// Leaves are `sha256(0x00 || data)` and parents `sha256(0x01 || left || right)`. An MMR
+/// starting at zero bags its peaks right to left, so its root is the RFC 6962 tree head.
+#[cfg(feature = "sha2")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rfc6962Hasher;
+
+#[cfg(feature = "sha2")]
+impl Hasher for Rfc6962Hasher {
+    const ID: HasherId = HasherId::Rfc6962;
+
+    type Context = sha2::Sha256;
+
+    fn hash_parent(left: &B256, right: &B256) -> B256 {
+        use sha2::{Digest, Sha256};
+        B256::from(<[u8; 32]>::from(
+            Sha256::new()
+                .chain_update([1u8])
+                .chain_update(left)
+                .chain_update(right)
+                .finalize(),
+        ))
+    }
+
+    fn hash_parent_in(context: &mut sha2::Sha256, left: &B256, right: &B256) -> B256 {
+        use sha2::Digest;
+        context.update([1u8]);
+        context.update(left);
+        context.update(right);
+        B256::from(<[u8; 32]>::from(context.finalize_reset()))
+    }
+
+    fn hash_leaf(data: &[u8]) -> B256 {
+        use sha2::{Digest, Sha256};
+        B256::from(<[u8; 32]>::from(
+            Sha256::new()
+                .chain_update([0u8])
+                .chain_update(data)
+                .finalize(),
+        ))
+    }
+
+    /// The hash of an empty tree is the hash of the empty string.
+    fn empty_root() -> B256 {
+        use sha2::{Digest, Sha256};
+        B256::from(<[u8; 32]>::from(Sha256::digest(b"")))
+    }
+}
+
+/// SHA3-256 as standardized by NIST, unlike the EVM's Keccak256 padding. Only available
+/// with the `sha3` feature.
+#[cfg(feature = "sha3")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sha3Hasher;
+
+#[cfg(feature = "sha3")]
+impl Hasher for Sha3Hasher {
+    const ID: HasherId = HasherId::Sha3;
+
+    type Context = sha3::Sha3_256;
+
+    fn hash_parent(left: &B256, right: &B256) -> B256 {
+        use sha3::{Digest, Sha3_256};
+        B256::from(<[u8; 32]>::from(
+            Sha3_256::new()
+                .chain_update(left)
+                .chain_update(right)
+                .finalize(),
+        ))
+    }
+
+    fn hash_parent_in(context: &mut sha3::Sha3_256, left: &B256, right: &B256) -> B256 {
+        use sha3::Digest;
+        context.update(left);
+        context.update(right);
+        B256::from(<[u8; 32]>::from(context.finalize_reset()))
+    }
+
+    fn hash_leaf(data: &[u8]) -> B256 {
+        use sha3::{Digest, Sha3_256};
+        B256::from(<[u8; 32]>::from(
+            Sha3_256::new()
+                .chain_update((data.len() as u64).to_be_bytes())
+                .chain_update(data)
+                .finalize(),
+        ))
+    }
+}
+
+/// Order of the BN254 scalar field, which Groth16 public inputs on that curve live in
+pub const BN254_SCALAR_MODULUS: U256 =
+    uint!(0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001_U256);
+
+/// Keccak256 with every output reduced modulo `BN254_SCALAR_MODULUS`, so each node and the
+/// root is a single BN254 field element and can be passed to a Groth16 verifier as a public
+/// input without splitting it into limbs.
+///
+/// Leaves from `hash_leaf` are reduced like internal nodes. Raw leaves must already be field
+/// elements, since a height-0 peak is the leaf itself: appends reject larger ones with
+/// `MMRError::LeafOutOfDomain`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bn254Keccak256Hasher;
+
+impl Bn254Keccak256Hasher {
+    /// Reduces a hash into the BN254 scalar field
+    pub fn reduce(hash: B256) -> B256 {
+        let reduced = U256::from_be_bytes(hash.0) % BN254_SCALAR_MODULUS;
+        B256::from(reduced.to_be_bytes::<32>())
+    }
+}
+
+impl Hasher for Bn254Keccak256Hasher {
+    const ID: HasherId = HasherId::Bn254Keccak256;
+
+    type Context = ();
+
+    fn hash_parent(left: &B256, right: &B256) -> B256 {
+        Self::reduce(Keccak256Hasher::hash_parent(left, right))
+    }
+
+    fn hash_leaf(data: &[u8]) -> B256 {
+        Self::reduce(Keccak256Hasher::hash_leaf(data))
+    }
+
+    fn accepts_leaf(leaf: &B256) -> bool {
+        U256::from_be_bytes(leaf.0) < BN254_SCALAR_MODULUS
+    }
+}
+
+/// Poseidon over the BN254 scalar field with circomlib's parameters, as implemented by
+/// light-poseidon. Only available with the `poseidon` feature.
+///
+/// Parents are `poseidon(left, right)` of two field elements encoded as 32 big-endian bytes,
+/// matching circomlib's `Poseidon(2)` template, so roots can be recomputed in circuits at a
+/// fraction of the constraints Keccak256 takes. Leaves from `hash_leaf` are Keccak256 hashes
+/// reduced into the field like `Bn254Keccak256Hasher`'s, and raw leaves must already be
+/// field elements.
+///
+/// Panics when hashing children outside the field, which only peaks loaded from untrusted
+/// state can be; appends reject such leaves with `MMRError::LeafOutOfDomain`.
+#[cfg(feature = "poseidon")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoseidonHasher;
+
+/// The Poseidon permutation with its round constants, built once per batch.
+#[cfg(feature = "poseidon")]
+pub struct PoseidonContext(light_poseidon::Poseidon<ark_bn254::Fr>);
+
+#[cfg(feature = "poseidon")]
+impl Default for PoseidonContext {
+    fn default() -> Self {
+        use light_poseidon::Poseidon;
+        Self(Poseidon::<ark_bn254::Fr>::new_circom(2).expect("circomlib has two-input parameters"))
+    }
+}
+
+#[cfg(feature = "poseidon")]
+impl Hasher for PoseidonHasher {
+    const ID: HasherId = HasherId::Poseidon;
+
+    type Context = PoseidonContext;
+
+    fn hash_parent(left: &B256, right: &B256) -> B256 {
+        Self::hash_parent_in(&mut PoseidonContext::default(), left, right)
+    }
+
+    fn hash_parent_in(context: &mut PoseidonContext, left: &B256, right: &B256) -> B256 {
+        use light_poseidon::PoseidonBytesHasher;
+        let hash = context
+            .0
+            .hash_bytes_be(&[left.as_slice(), right.as_slice()])
+            .expect("children of a Poseidon node must be BN254 field elements");
+        B256::from(hash)
+    }
+
+    fn hash_leaf(data: &[u8]) -> B256 {
+        Bn254Keccak256Hasher::hash_leaf(data)
+    }
+
+    fn accepts_leaf(leaf: &B256) -> bool {
+        Bn254Keccak256Hasher::accepts_leaf(leaf)
+    }
+}
+
+/// Keccak256 of the two children in ascending order, the commutative pair hash of
+/// OpenZeppelin's `MerkleProof` and Solady's `MerkleProofLib`.
+///
+/// Siblings then need no left/right flag, so proofs of MMRs built with this hasher can be
+/// checked by those libraries, see `sorted_pair`. Leaves from `hash_leaf` are hashed like
+/// `Keccak256Hasher`'s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SortedKeccak256Hasher;
+
+impl Hasher for SortedKeccak256Hasher {
+    const ID: HasherId = HasherId::SortedKeccak256;
+
+    type Context = ();
+
+    fn hash_parent(left: &B256, right: &B256) -> B256 {
+        if left <= right {
+            Keccak256Hasher::hash_parent(left, right)
+        } else {
+            Keccak256Hasher::hash_parent(right, left)
+        }
+    }
+
+    fn hash_leaf(data: &[u8]) -> B256 {
+        Keccak256Hasher::hash_leaf(data)
+    }
+}
+
 /// Hashes two B256 values to a single B256 value using Keccak256.
 ///
 /// # Arguments
@@ -25,13 +378,252 @@ use rand::Rng;
 /// assert_ne!(parent, right);
 /// ```
 pub fn hash_to_parent(left: &B256, right: &B256) -> B256 {
+    Keccak256Hasher::hash_parent(left, right)
+}
+
+/// Hashes an ordered list of children to a single B256 value using Keccak256.
+///
+/// This generalizes [`hash_to_parent`] to nodes of arbitrary arity; hashing exactly two
+/// children produces the same value as [`hash_to_parent`].
+///
+/// # Arguments
+///
+/// * `children` - The child B256 values, ordered from left to right.
+///
+/// # Returns
+///
+/// A B256 value that is the hash of the concatenated children.
+///
+/// # Examples
+///
+/// ```
+/// use alloy_primitives::B256;
+/// use rust_mmr::utils::hash::{hash_children, hash_to_parent};
+///
+/// let left = B256::repeat_byte(0x11);
+/// let right = B256::repeat_byte(0x22);
+/// assert_eq!(hash_children(&[left, right]), hash_to_parent(&left, &right));
+/// ```
+pub fn hash_children(children: &[B256]) -> B256 {
     let mut hasher = Keccak256::new();
-    hasher.update(left.as_slice());
-    hasher.update(right.as_slice());
+    for child in children {
+        hasher.update(child.as_slice());
+    }
     hasher.finalize()
 }
 
-/// Generates a random B256 value. Mostly used for testing purposes.
+/// Hashes a perfect binary subtree from its leaves.
+///
+/// # Arguments
+///
+/// * `leaves` - The leaves of the subtree. The length must be a power of two.
+///
+/// # Returns
+///
+/// The root of the subtree, or the leaf itself for a single-leaf subtree.
+///
+/// Panics if the number of leaves isn't a power of two, rather than returning the root of
+/// a tree that doesn't exist.
+///
+/// # Examples
+///
+/// ```
+/// use alloy_primitives::B256;
+/// use rust_mmr::utils::hash::{hash_subtree, hash_to_parent};
+///
+/// let leaves = [B256::repeat_byte(0x11), B256::repeat_byte(0x22)];
+/// assert_eq!(hash_subtree(&leaves), hash_to_parent(&leaves[0], &leaves[1]));
+/// ```
+pub fn hash_subtree(leaves: &[B256]) -> B256 {
+    hash_subtree_with::<Keccak256Hasher>(leaves)
+}
+
+/// Like [`hash_subtree`], hashing with `H`.
+pub fn hash_subtree_with<H: Hasher>(leaves: &[B256]) -> B256 {
+    hash_subtree_in::<H>(&mut H::Context::default(), leaves)
+}
+
+/// Like [`hash_subtree_with`], hashing through `context`, for callers hashing several
+/// subtrees in a row.
+pub fn hash_subtree_in<H: Hasher>(context: &mut H::Context, leaves: &[B256]) -> B256 {
+    assert!(
+        leaves.len().is_power_of_two(),
+        "subtree of {} leaves isn't perfect",
+        leaves.len()
+    );
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let (left, right) = leaves.split_at(leaves.len() / 2);
+    let left = hash_subtree_in::<H>(context, left);
+    let right = hash_subtree_in::<H>(context, right);
+    H::hash_parent_in(context, &left, &right)
+}
+
+/// Like [`hash_subtree`], hashing parents with `hash`.
+pub(crate) fn hash_subtree_by(leaves: &[B256], hash: &impl Fn(&B256, &B256) -> B256) -> B256 {
+    assert!(
+        leaves.len().is_power_of_two(),
+        "subtree of {} leaves isn't perfect",
+        leaves.len()
+    );
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let (left, right) = leaves.split_at(leaves.len() / 2);
+    hash(&hash_subtree_by(left, hash), &hash_subtree_by(right, hash))
+}
+
+/// Computes the root of the zero-starting MMR over `leaves` without building the MMR.
+///
+/// The tree is merklized bottom-up, one level at a time, in a single buffer of half the
+/// leaves. A level with an odd number of nodes ends with a peak, and peaks are found from
+/// the lowest up, which is the order they bag in.
+///
+/// # Arguments
+///
+/// * `leaves` - The leaves of the MMR, starting at index zero.
+///
+/// # Returns
+///
+/// The same root as `MMR::from_leaves(leaves).get_root()`, or `B256::ZERO` if there are no
+/// leaves.
+///
+/// # Examples
+///
+/// ```
+/// use alloy_primitives::B256;
+/// use rust_mmr::{utils::hash::compute_root, MMR};
+///
+/// let leaves: Vec<B256> = (0..11).map(B256::repeat_byte).collect();
+/// assert_eq!(compute_root(&leaves), MMR::from_leaves(&leaves).get_root());
+/// ```
+pub fn compute_root(leaves: &[B256]) -> B256 {
+    compute_root_with::<Keccak256Hasher>(leaves)
+}
+
+/// Like [`compute_root`], hashing with `H`.
+pub fn compute_root_with<H: Hasher>(leaves: &[B256]) -> B256 {
+    // Zero-starting peaks all sit on the right side, which bags right to left.
+    fn bag<H: Hasher>(context: &mut H::Context, root: Option<B256>, peak: B256) -> Option<B256> {
+        Some(root.map_or(peak, |root| H::hash_parent_in(context, &peak, &root)))
+    }
+
+    let mut context = H::Context::default();
+    let mut root = None;
+    if let [peak] = leaves.chunks_exact(2).remainder() {
+        root = bag::<H>(&mut context, root, *peak);
+    }
+    let mut nodes: Vec<B256> = leaves
+        .chunks_exact(2)
+        .map(|pair| H::hash_parent_in(&mut context, &pair[0], &pair[1]))
+        .collect();
+    while !nodes.is_empty() {
+        let len = nodes.len();
+        if len % 2 == 1 {
+            root = bag::<H>(&mut context, root, nodes[len - 1]);
+        }
+        for index in 0..len / 2 {
+            nodes[index] =
+                H::hash_parent_in(&mut context, &nodes[2 * index], &nodes[2 * index + 1]);
+        }
+        nodes.truncate(len / 2);
+    }
+    root.unwrap_or_else(H::empty_root)
+}
+
+/// Like [`compute_root`], hashing with a fallible `hasher` and returning its first error.
+pub fn try_compute_root<T: TryHasher>(hasher: &mut T, leaves: &[B256]) -> Result<B256, T::Error> {
+    let mut root = None;
+    let mut nodes = Vec::with_capacity(leaves.len() / 2);
+    if let [peak] = leaves.chunks_exact(2).remainder() {
+        root = Some(*peak);
+    }
+    for pair in leaves.chunks_exact(2) {
+        nodes.push(hasher.try_hash_to_parent(&pair[0], &pair[1])?);
+    }
+    while !nodes.is_empty() {
+        let len = nodes.len();
+        if len % 2 == 1 {
+            let peak = nodes[len - 1];
+            root = Some(match root {
+                Some(root) => hasher.try_hash_to_parent(&peak, &root)?,
+                None => peak,
+            });
+        }
+        for index in 0..len / 2 {
+            nodes[index] = hasher.try_hash_to_parent(&nodes[2 * index], &nodes[2 * index + 1])?;
+        }
+        nodes.truncate(len / 2);
+    }
+    match root {
+        Some(root) => Ok(root),
+        None => hasher.try_empty_root(),
+    }
+}
+
+/// Hashes a variable-length record into a leaf, prefixed with its length.
+///
+/// The length prefix separates records that would otherwise collide when concatenated, e.g.
+/// `["ab", "c"]` and `["a", "bc"]`.
+///
+/// # Arguments
+///
+/// * `data` - The record to hash.
+///
+/// # Returns
+///
+/// `keccak256(len || data)`, with `len` encoded as 8 big-endian bytes.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::hash::hash_leaf_bytes;
+///
+/// assert_ne!(hash_leaf_bytes(b"ab"), hash_leaf_bytes(b"a"));
+/// ```
+pub fn hash_leaf_bytes(data: &[u8]) -> B256 {
+    Keccak256Hasher::hash_leaf(data)
+}
+
+/// A secret for keyed hashing, see `MMR::with_key`. Its `Debug` output doesn't reveal it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct HashKey(B256);
+
+impl HashKey {
+    /// Creates a key from its secret bytes
+    pub fn new(key: B256) -> Self {
+        Self(key)
+    }
+
+    /// Hashes two children into their parent as `keccak256(key || left || right)`
+    pub fn hash_to_parent(&self, left: &B256, right: &B256) -> B256 {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.0.as_slice());
+        hasher.update(left.as_slice());
+        hasher.update(right.as_slice());
+        hasher.finalize()
+    }
+
+    /// Hashes a variable-length record into a leaf as `keccak256(key || len || data)`, the
+    /// keyed counterpart of [`hash_leaf_bytes`]
+    pub fn hash_leaf_bytes(&self, data: &[u8]) -> B256 {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.0.as_slice());
+        hasher.update((data.len() as u64).to_be_bytes());
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+impl fmt::Debug for HashKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HashKey(..)")
+    }
+}
+
+/// Generates a random B256 value. Mostly used for testing purposes, and only available with
+/// the `rand` feature.
 ///
 /// # Returns
 ///
@@ -40,12 +632,16 @@ pub fn hash_to_parent(left: &B256, right: &B256) -> B256 {
 /// # Examples
 ///
 /// ```
+/// # #[cfg(feature = "rand")]
+/// # {
 /// use alloy_primitives::B256;
 /// use rust_mmr::utils::hash::get_random_hash;
 ///
 /// let hash = get_random_hash();
 /// assert_ne!(hash, B256::ZERO);
+/// # }
 /// ```
+#[cfg(any(test, feature = "rand"))]
 pub fn get_random_hash() -> B256 {
     rand::thread_rng().gen::<[u8; 32]>().into()
 }
@@ -72,6 +668,278 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hash_children() {
+        let children = [
+            B256::repeat_byte(0x11),
+            B256::repeat_byte(0x22),
+            B256::repeat_byte(0x33),
+            B256::repeat_byte(0x44),
+        ];
+
+        // Two children hash exactly like a binary parent.
+        assert_eq!(
+            hash_children(&children[..2]),
+            hash_to_parent(&children[0], &children[1])
+        );
+
+        // Wider nodes are not equivalent to nesting binary parents.
+        assert_ne!(
+            hash_children(&children),
+            hash_to_parent(
+                &hash_to_parent(&children[0], &children[1]),
+                &hash_to_parent(&children[2], &children[3])
+            )
+        );
+    }
+
+    fn assert_context_matches<H: Hasher>() {
+        let mut context = H::Context::default();
+        for _ in 0..3 {
+            let (left, right) = (get_random_hash(), get_random_hash());
+            assert_eq!(
+                H::hash_parent_in(&mut context, &left, &right),
+                H::hash_parent(&left, &right)
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_parent_in() {
+        // A reused context hashes every parent like a fresh one.
+        assert_context_matches::<Keccak256Hasher>();
+        assert_context_matches::<Bn254Keccak256Hasher>();
+        #[cfg(feature = "sha2")]
+        assert_context_matches::<Sha256Hasher>();
+        #[cfg(feature = "sha2")]
+        assert_context_matches::<Rfc6962Hasher>();
+        #[cfg(feature = "sha3")]
+        assert_context_matches::<Sha3Hasher>();
+    }
+
+    /// Known answers of circomlibjs' `poseidon` and light-poseidon's own examples.
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_poseidon_hasher() {
+        let field_element = |value: u64| B256::from(U256::from(value));
+        assert_eq!(
+            PoseidonHasher::hash_parent(&field_element(1), &field_element(2)),
+            b256!("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+        );
+        assert_eq!(
+            PoseidonHasher::hash_parent(&B256::repeat_byte(1), &B256::repeat_byte(2)),
+            b256!("0d54e1938f8a8c1c7deb5e0355f26319207b84fe9ca2ce1b26e735c829821990")
+        );
+
+        let mut context = PoseidonContext::default();
+        let leaves: Vec<B256> = (0..8)
+            .map(|_| Bn254Keccak256Hasher::reduce(get_random_hash()))
+            .collect();
+        for pair in leaves.chunks(2) {
+            assert_eq!(
+                PoseidonHasher::hash_parent_in(&mut context, &pair[0], &pair[1]),
+                PoseidonHasher::hash_parent(&pair[0], &pair[1])
+            );
+        }
+
+        let mut mmr: crate::MMR<PoseidonHasher> = crate::MMR::with_hasher();
+        mmr.append_batch(&leaves);
+        assert_eq!(mmr.scheme().hasher, HasherId::Poseidon);
+        assert_eq!(mmr.get_root(), compute_root_with::<PoseidonHasher>(&leaves));
+        let modulus = B256::from(BN254_SCALAR_MODULUS.to_be_bytes::<32>());
+        assert!(matches!(
+            mmr.try_append(modulus),
+            Err(crate::MMRError::LeafOutOfDomain)
+        ));
+    }
+
+    #[test]
+    fn test_sorted_keccak256_hasher() {
+        let (low, high) = (B256::repeat_byte(0x11), B256::repeat_byte(0x22));
+        assert_eq!(
+            SortedKeccak256Hasher::hash_parent(&high, &low),
+            hash_to_parent(&low, &high)
+        );
+        assert_eq!(
+            SortedKeccak256Hasher::hash_parent(&low, &high),
+            hash_to_parent(&low, &high)
+        );
+        assert_context_matches::<SortedKeccak256Hasher>();
+    }
+
+    #[test]
+    #[should_panic(expected = "subtree of 3 leaves isn't perfect")]
+    fn test_hash_subtree_rejects_imperfect_trees() {
+        hash_subtree(&[B256::ZERO; 3]);
+    }
+
+    #[test]
+    fn test_hash_subtree() {
+        let leaves: Vec<B256> = (0..4).map(B256::repeat_byte).collect();
+        assert_eq!(hash_subtree(&leaves[..1]), leaves[0]);
+        assert_eq!(
+            hash_subtree(&leaves),
+            hash_to_parent(
+                &hash_to_parent(&leaves[0], &leaves[1]),
+                &hash_to_parent(&leaves[2], &leaves[3])
+            )
+        );
+    }
+
+    #[test]
+    fn test_compute_root() {
+        let leaves: Vec<B256> = (0..70).map(|_| get_random_hash()).collect();
+        for len in 0..=70 {
+            assert_eq!(
+                compute_root(&leaves[..len]),
+                crate::MMR::from_leaves(&leaves[..len]).get_root()
+            );
+        }
+    }
+
+    /// Fails on the `fail_at`-th hash, like a device going away mid-build.
+    struct FlakyHasher {
+        hashed: usize,
+        fail_at: usize,
+    }
+
+    impl TryHasher for FlakyHasher {
+        type Error = &'static str;
+
+        fn try_hash_to_parent(&mut self, left: &B256, right: &B256) -> Result<B256, &'static str> {
+            self.hashed += 1;
+            if self.hashed == self.fail_at {
+                return Err("device unavailable");
+            }
+            Ok(hash_to_parent(left, right))
+        }
+    }
+
+    #[test]
+    fn test_try_compute_root() {
+        let leaves: Vec<B256> = (0..70).map(|_| get_random_hash()).collect();
+        for len in 0..=70 {
+            // Every infallible hasher is a fallible one that never fails.
+            assert_eq!(
+                try_compute_root(&mut Keccak256Hasher, &leaves[..len]),
+                Ok(compute_root(&leaves[..len]))
+            );
+        }
+
+        let mut hasher = FlakyHasher {
+            hashed: 0,
+            fail_at: usize::MAX,
+        };
+        assert_eq!(
+            try_compute_root(&mut hasher, &leaves),
+            Ok(compute_root(&leaves))
+        );
+        let mut hasher = FlakyHasher {
+            hashed: 0,
+            fail_at: 40,
+        };
+        assert_eq!(
+            try_compute_root(&mut hasher, &leaves),
+            Err("device unavailable")
+        );
+        assert_eq!(hasher.hashed, 40);
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_sha256_hasher() {
+        // The root of a depth-one tree of zero leaves, as used by SSZ.
+        assert_eq!(
+            Sha256Hasher::hash_parent(&B256::ZERO, &B256::ZERO),
+            b256!("f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b")
+        );
+        assert_eq!(
+            Sha256Hasher::hash_leaf(b"abc"),
+            b256!("c3494ca1a2cf8eeb8a11ded316fb55b83c3bbbedb6313cd50415251e5d09e12f")
+        );
+        let leaves: Vec<B256> = (0..11).map(|_| get_random_hash()).collect();
+        assert_ne!(
+            compute_root_with::<Sha256Hasher>(&leaves),
+            compute_root(&leaves)
+        );
+    }
+
+    #[cfg(feature = "sha3")]
+    #[test]
+    fn test_sha3_hasher() {
+        assert_eq!(
+            Sha3Hasher::hash_parent(&B256::ZERO, &B256::ZERO),
+            b256!("070fa1ab6fcc557ed14d42941f1967693048551eb9042a8d0a057afbd75e81e0")
+        );
+        assert_eq!(
+            Sha3Hasher::hash_leaf(b"abc"),
+            b256!("77bc2ec7d651f614ef9428ec90de418c223e9186d9839b7947c95d976a540426")
+        );
+        // NIST SHA3-256 pads differently from the EVM's Keccak256.
+        assert_ne!(
+            Sha3Hasher::hash_parent(&B256::ZERO, &B256::ZERO),
+            hash_to_parent(&B256::ZERO, &B256::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_bn254_keccak256_hasher() {
+        let leaves: Vec<B256> = (0..11)
+            .map(|_| Bn254Keccak256Hasher::reduce(get_random_hash()))
+            .collect();
+        let root = compute_root_with::<Bn254Keccak256Hasher>(&leaves);
+        assert!(U256::from_be_bytes(root.0) < BN254_SCALAR_MODULUS);
+        assert_ne!(root, compute_root(&leaves));
+        let mut mmr: crate::MMR<Bn254Keccak256Hasher> = crate::MMR::with_hasher();
+        mmr.append_batch(&leaves);
+        assert_eq!(mmr.get_root(), root);
+        assert_eq!(mmr.scheme().hasher, HasherId::Bn254Keccak256);
+
+        let parent = Bn254Keccak256Hasher::hash_parent(&leaves[0], &leaves[1]);
+        assert_eq!(
+            U256::from_be_bytes(parent.0),
+            U256::from_be_bytes(hash_to_parent(&leaves[0], &leaves[1]).0) % BN254_SCALAR_MODULUS
+        );
+        assert_eq!(
+            Bn254Keccak256Hasher::reduce(B256::from(BN254_SCALAR_MODULUS.to_be_bytes::<32>())),
+            B256::ZERO
+        );
+    }
+
+    #[test]
+    fn test_bn254_rejects_out_of_field_leaves() {
+        let modulus = B256::from(BN254_SCALAR_MODULUS.to_be_bytes::<32>());
+        let mut mmr: crate::MMR<Bn254Keccak256Hasher> = crate::MMR::with_hasher();
+        assert!(matches!(
+            mmr.try_append(modulus),
+            Err(crate::MMRError::LeafOutOfDomain)
+        ));
+        assert_eq!(mmr.size(), 0);
+
+        // A single leaf is the root, so it must be a field element itself.
+        let below = B256::from((BN254_SCALAR_MODULUS - U256::from(1)).to_be_bytes::<32>());
+        mmr.try_append(below).unwrap();
+        assert_eq!(mmr.get_root(), below);
+    }
+
+    #[test]
+    #[should_panic(expected = "Leaf is outside the hasher's output domain")]
+    fn test_bn254_batch_rejects_out_of_field_leaves() {
+        let mut mmr: crate::MMR<Bn254Keccak256Hasher> = crate::MMR::with_hasher();
+        mmr.append_batch(&[B256::ZERO, B256::repeat_byte(0xff)]);
+    }
+
+    #[test]
+    fn test_hash_leaf_bytes() {
+        let mut prefixed = vec![0, 0, 0, 0, 0, 0, 0, 3];
+        prefixed.extend_from_slice(b"abc");
+        assert_eq!(
+            hash_leaf_bytes(b"abc"),
+            alloy_primitives::keccak256(&prefixed)
+        );
+        assert_ne!(hash_leaf_bytes(b""), alloy_primitives::keccak256([]));
+    }
+
     #[test]
     fn test_get_random_hash() {
         let hash1 = get_random_hash();
@@ -0,0 +1,178 @@
+/*
+Untrusted append hints.
+
+`decompose` and the merge-path computation in `append` rely on `ilog2` and
+`trailing_zeros`, which have no single-instruction equivalent on the SP1
+RISC-V target and compile down to bit-twiddling loops. A host can precompute
+these values and hand them to a guest, which then only has to check them with
+a handful of additions, masks and comparisons.
+*/
+
+use super::range::Decomposition;
+use alloc::vec::Vec;
+
+/// Precomputed merge information for appending a single leaf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppendHint {
+    /// Right part of the decomposition of the range before the append.
+    pub right: u64,
+    /// Number of peaks the appended leaf merges with.
+    pub merges: u32,
+}
+
+/// Computes the hints for appending `count` leaves to the range `[start, end)`.
+///
+/// # Arguments
+///
+/// * `start` - The start of the interval (inclusive)
+/// * `end` - The end of the interval (exclusive) before the first append
+/// * `count` - The number of leaves that will be appended
+///
+/// # Returns
+///
+/// One hint per appended leaf, in append order.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::hint::{get_append_hints, AppendHint};
+///
+/// let hints = get_append_hints(0, 3, 2);
+/// assert_eq!(hints[0], AppendHint { right: 3, merges: 2 });
+/// assert_eq!(hints[1], AppendHint { right: 4, merges: 0 });
+/// ```
+pub fn get_append_hints(start: u64, end: u64, count: u64) -> Vec<AppendHint> {
+    let mut decomposition = Decomposition::new(start, end);
+    (0..count)
+        .map(|_| {
+            let right = decomposition.right();
+            decomposition.increment();
+            AppendHint {
+                right,
+                merges: (!right).trailing_zeros(),
+            }
+        })
+        .collect()
+}
+
+/// Checks that `(left, right)` is the decomposition of `[start, end)` without computing it.
+///
+/// The decomposition splits the range at the unique point `mid = start + left` that is aligned
+/// to a power of two larger than both `left` and `right`. This only needs additions and masks.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::hint::verify_decomposition;
+///
+/// assert!(verify_decomposition(3, 7, 1, 3));
+/// assert!(!verify_decomposition(3, 7, 3, 1));
+/// ```
+pub fn verify_decomposition(start: u64, end: u64, left: u64, right: u64) -> bool {
+    let Some(size) = end.checked_sub(start) else {
+        return false;
+    };
+    if left.checked_add(right) != Some(size) {
+        return false;
+    }
+    let mid = start + left;
+    // Lowest set bit of `mid`, i.e. the largest power of two it is aligned to.
+    let alignment = mid & mid.wrapping_neg();
+    mid == 0 || (left | right) < alignment
+}
+
+/// Checks that `merges` is the number of trailing ones of `right`, without counting them.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::hint::verify_merges;
+///
+/// assert!(verify_merges(0b1011, 2));
+/// assert!(!verify_merges(0b1011, 1));
+/// ```
+pub fn verify_merges(right: u64, merges: u32) -> bool {
+    if merges > u64::BITS {
+        return false;
+    }
+    let low_bits = 1u64.checked_shl(merges).map_or(u64::MAX, |bit| bit - 1);
+    let next_bit = right.checked_shr(merges).map_or(0, |rest| rest & 1);
+    right & low_bits == low_bits && next_bit == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::range::decompose;
+    use rand::Rng;
+
+    #[test]
+    fn test_verify_decomposition_matches_decompose() {
+        for start in 0..64 {
+            for end in start..128 {
+                let (left, right) = decompose(start, end);
+                assert!(verify_decomposition(start, end, left, right));
+                // Any other split of the same size is rejected.
+                for other_left in 0..=(end - start) {
+                    if other_left != left {
+                        assert!(!verify_decomposition(
+                            start,
+                            end,
+                            other_left,
+                            end - start - other_left
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_decomposition_random_ranges() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let a: u64 = rng.gen();
+            let b: u64 = rng.gen();
+            let (start, end) = (a.min(b), a.max(b));
+            let (left, right) = decompose(start, end);
+            assert!(verify_decomposition(start, end, left, right));
+            if left > 0 {
+                assert!(!verify_decomposition(start, end, left - 1, right + 1));
+            }
+            if right > 0 {
+                assert!(!verify_decomposition(start, end, left + 1, right - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_decomposition_edge_cases() {
+        assert!(verify_decomposition(0, 0, 0, 0));
+        assert!(verify_decomposition(0, u64::MAX, 0, u64::MAX));
+        assert!(verify_decomposition(u64::MAX - 1, u64::MAX, 0, 1));
+        assert!(!verify_decomposition(1, 0, 0, 0));
+        assert!(!verify_decomposition(0, 1, u64::MAX, 2));
+    }
+
+    #[test]
+    fn test_verify_merges() {
+        assert!(verify_merges(0, 0));
+        assert!(verify_merges(0b0111, 3));
+        assert!(!verify_merges(0b0111, 2));
+        assert!(!verify_merges(0b0111, 4));
+        assert!(verify_merges(u64::MAX, 64));
+        assert!(!verify_merges(u64::MAX, 63));
+        assert!(!verify_merges(u64::MAX, 65));
+    }
+
+    #[test]
+    fn test_get_append_hints() {
+        let hints = get_append_hints(3, 3, 5);
+        for (hint, end) in hints.iter().zip(3..) {
+            let (left, right) = decompose(3, end);
+            assert_eq!(hint.right, right);
+            assert!(verify_decomposition(3, end, left, hint.right));
+            assert!(verify_merges(hint.right, hint.merges));
+        }
+    }
+}
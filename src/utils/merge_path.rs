@@ -0,0 +1,209 @@
+use super::range::decompose;
+
+/// One step of a [`MergePath`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStep {
+    /// Hash the next peak of the left MMR, from its last peak backwards, into the seed.
+    Left {
+        /// The height of the merged peak, equal to the seed's height
+        height: u32,
+    },
+    /// Hash the seed into the next peak of the right MMR, from its first peak forwards.
+    Right {
+        /// The height of the merged peak, equal to the seed's height
+        height: u32,
+    },
+}
+
+/// The merges that join an MMR over `[start, mid)` with a bordering MMR over `[mid, end)`.
+///
+/// The seed is the last peak of the left MMR, the perfect subtree ending at `mid`: of height
+/// `mid.trailing_zeros()` for zero-starting MMRs, and possibly lower otherwise. At each height
+/// the seed is the left or right child of its parent, depending on the parity of its index at
+/// that height:
+///
+/// * A left child merges with the next peak of the right MMR, as long as the parent's
+///   subtree ends within `end`.
+/// * A right child merges with the previous peak of the left MMR, as long as the parent's
+///   subtree starts within `start`.
+///
+/// The path ends at the first height where neither is possible; the seed is then a peak of
+/// the merged MMR, between the left MMR's unmerged peaks and the right MMR's.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::merge_path::{MergePath, MergeStep};
+///
+/// // [0, 3) + [3, 8): leaf 2 merges with leaf 3, [2, 4) with [0, 2), [0, 4) with [4, 8).
+/// let steps: Vec<MergeStep> = MergePath::new(3, 8).collect();
+/// assert_eq!(
+///     steps,
+///     vec![
+///         MergeStep::Right { height: 0 },
+///         MergeStep::Left { height: 1 },
+///         MergeStep::Right { height: 2 },
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergePath {
+    start: u64,
+    end: u64,
+    height: u32,
+    index: u64,
+    range_start: u64,
+}
+
+impl MergePath {
+    /// Creates the path for merging `[0, mid)` with `[mid, end)`, where `0 < mid <= end`
+    pub fn new(mid: u64, end: u64) -> Self {
+        Self::with_start(0, mid, end)
+    }
+
+    /// Creates the path for merging `[start, mid)` with `[mid, end)`, where
+    /// `start < mid <= end`
+    pub fn with_start(start: u64, mid: u64, end: u64) -> Self {
+        debug_assert!(start < mid && mid <= end);
+        // The last peak is the lowest one of the right part, or the highest one of the left
+        // part if the right part is empty.
+        let (left, right) = decompose(start, mid);
+        let height = if right != 0 {
+            right.trailing_zeros()
+        } else {
+            u64::BITS - 1 - left.leading_zeros()
+        };
+        let index = (mid - 1) >> height;
+        Self {
+            start,
+            end,
+            height,
+            index,
+            range_start: index << height,
+        }
+    }
+
+    /// Returns the height of the seed after the steps taken so far
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the leaf range `[start, end)` covered by the seed after the steps taken so far
+    pub fn seed_range(&self) -> (u64, u64) {
+        (self.range_start, self.range_start + (1 << self.height))
+    }
+}
+
+impl Iterator for MergePath {
+    type Item = MergeStep;
+
+    /// Advances the seed by one height, or returns `None` once it is a peak. The path stays
+    /// exhausted: the state only changes on a successful step.
+    fn next(&mut self) -> Option<MergeStep> {
+        if self.height >= u64::BITS - 1 {
+            return None;
+        }
+        let coverage = 1 << self.height;
+        let step = if self.index & 1 == 0 {
+            // Right merge, if the parent's subtree ends within the right MMR.
+            let parent_end = self.range_start.checked_add(coverage << 1)?;
+            if parent_end > self.end {
+                return None;
+            }
+            MergeStep::Right {
+                height: self.height,
+            }
+        } else {
+            // Left merge, if the parent's subtree starts within the left MMR.
+            if coverage > self.range_start - self.start {
+                return None;
+            }
+            self.range_start -= coverage;
+            MergeStep::Left {
+                height: self.height,
+            }
+        };
+        self.index >>= 1;
+        self.height += 1;
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_right_merge_stops_at_end() {
+        // The seed [0, 4) would need [4, 8) but the right MMR ends at 7.
+        let mut path = MergePath::new(4, 7);
+        assert_eq!(path.seed_range(), (0, 4));
+        assert_eq!(path.next(), None);
+        // The path stays exhausted.
+        assert_eq!(path.next(), None);
+        assert_eq!(path.height(), 2);
+    }
+
+    #[test]
+    fn test_left_merges() {
+        // Leaf 7 is a right child three times over.
+        let steps: Vec<MergeStep> = MergePath::new(7, 8).collect();
+        assert_eq!(
+            steps,
+            vec![
+                MergeStep::Right { height: 0 },
+                MergeStep::Left { height: 1 },
+                MergeStep::Left { height: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_seed_range_tracks_steps() {
+        let mut path = MergePath::new(6, 16);
+        assert_eq!(path.seed_range(), (4, 6));
+        assert_eq!(path.next(), Some(MergeStep::Right { height: 1 }));
+        assert_eq!(path.seed_range(), (4, 8));
+        assert_eq!(path.next(), Some(MergeStep::Left { height: 2 }));
+        assert_eq!(path.seed_range(), (0, 8));
+        assert_eq!(path.next(), Some(MergeStep::Right { height: 3 }));
+        assert_eq!(path.seed_range(), (0, 16));
+        assert_eq!(path.next(), None);
+    }
+
+    #[test]
+    fn test_empty_right() {
+        assert_eq!(MergePath::new(5, 5).count(), 0);
+    }
+
+    #[test]
+    fn test_step_count_matches_peak_counts() {
+        use crate::utils::range::get_expected_num_peaks;
+        for start in 0..40 {
+            for end in start + 1..120 {
+                for mid in start + 1..=end {
+                    let merged = get_expected_num_peaks(start, mid)
+                        + get_expected_num_peaks(mid, end)
+                        - get_expected_num_peaks(start, end);
+                    assert_eq!(
+                        MergePath::with_start(start, mid, end).count() as u64,
+                        merged
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_left_merge_stops_at_start() {
+        // The seed is leaf 3 alone, as [2, 4) would start before the left MMR.
+        let mut path = MergePath::with_start(3, 4, 4);
+        assert_eq!(path.seed_range(), (3, 4));
+        assert_eq!(path.next(), None);
+
+        // [2, 4) + [4, 8): the seed [2, 4) can't merge with [0, 2), so it stays a peak.
+        let mut path = MergePath::with_start(2, 4, 8);
+        assert_eq!(path.seed_range(), (2, 4));
+        assert_eq!(path.next(), None);
+    }
+}
@@ -1,2 +1,6 @@
+pub mod bag;
+pub mod cost;
 pub mod hash;
+pub mod hint;
+pub mod merge_path;
 pub mod range;
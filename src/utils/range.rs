@@ -31,6 +31,15 @@ Total nodes: 15
 Nodes are referenced as a (level, index) tuple.
 */
 
+use alloc::vec::Vec;
+
+/// The largest number of peaks of any range of `u64` leaf indices.
+///
+/// Both parts of a decomposition are masked below the highest bit where `begin - 1` and
+/// `end` differ, so each has at most 63 bits set. The bound is reached by `[1, u64::MAX)`;
+/// zero-starting ranges have at most 64 peaks.
+pub const MAX_PEAKS: usize = 2 * (u64::BITS as usize - 1);
+
 /// Decomposes a non-zero-starting interval into two parts that represent
 /// the compact range needed to express the interval.
 ///
@@ -59,6 +68,10 @@ pub fn decompose(begin: u64, end: u64) -> (u64, u64) {
     if begin == 0 {
         return (0, end);
     }
+    // An inverted range has no peaks, like an empty one.
+    if begin > end {
+        return (0, 0);
+    }
     // The index before 'begin' represents the last node in the complementary "zero-index-starting" interval
     let x_begin = begin - 1;
     // Find the highest bit where x_begin and end differ, which indicates the difference between the left merge path
@@ -71,6 +84,66 @@ pub fn decompose(begin: u64, end: u64) -> (u64, u64) {
     (!x_begin & mask, end & mask)
 }
 
+/// The decomposition of a range, kept up to date incrementally as leaves are appended.
+///
+/// Recomputing `decompose` on every append costs an xor, an `ilog2` and some masking. Since
+/// appending only ever increments `end`, the new decomposition can be derived from the old
+/// one with a comparison and an addition.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::range::{decompose, Decomposition};
+///
+/// let mut decomposition = Decomposition::new(3, 7);
+/// decomposition.increment();
+/// assert_eq!((decomposition.left(), decomposition.right()), decompose(3, 8));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decomposition {
+    begin: u64,
+    left: u64,
+    right: u64,
+}
+
+impl Decomposition {
+    /// Decomposes the interval `[begin, end)`
+    pub fn new(begin: u64, end: u64) -> Self {
+        let (left, right) = decompose(begin, end);
+        Self { begin, left, right }
+    }
+
+    /// Returns the bitmap representing the left part of the interval
+    pub fn left(&self) -> u64 {
+        self.left
+    }
+
+    /// Returns the bitmap representing the right part of the interval
+    pub fn right(&self) -> u64 {
+        self.right
+    }
+
+    /// Returns the end of the interval (exclusive)
+    pub fn end(&self) -> u64 {
+        self.begin + self.left + self.right
+    }
+
+    /// Updates the decomposition for the interval extended by one leaf
+    pub fn increment(&mut self) {
+        // The split point `mid` is aligned to its lowest set bit, which bounds both parts.
+        let mid = self.begin + self.left;
+        let alignment = mid & mid.wrapping_neg();
+        if mid == 0 || self.right + 1 < alignment {
+            self.right += 1;
+        } else {
+            // The right part fills up to the alignment, so the whole range left of the new
+            // end becomes a single merge path into the complementary interval.
+            self.left += self.right + 1;
+            self.right = 0;
+        }
+    }
+}
+
 /// Calculates the expected number of peaks for a range given its begin and end leaf indices.
 ///
 /// # Arguments
@@ -94,12 +167,134 @@ pub fn decompose(begin: u64, end: u64) -> (u64, u64) {
 /// ```
 pub fn get_expected_num_peaks(begin: u64, end: u64) -> u64 {
     let (left, right) = decompose(begin, end);
-    (left.count_ones() + right.count_ones()) as u64
+    let num_peaks = left.count_ones() + right.count_ones();
+    debug_assert!(num_peaks as usize <= MAX_PEAKS);
+    num_peaks as u64
+}
+
+/// Lists the heights of the peaks of a range, in the order of `MMR::peaks`.
+///
+/// # Arguments
+///
+/// * `begin` - The start of the interval (inclusive)
+/// * `end` - The end of the interval (exclusive)
+///
+/// # Returns
+///
+/// The height of each peak, i.e. the base-2 logarithm of the number of leaves it covers.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::range::get_peak_heights;
+///
+/// // [3, 7) has peaks covering [3], [4, 5] and [6].
+/// assert_eq!(get_peak_heights(3, 7), vec![0, 1, 0]);
+/// ```
+pub fn get_peak_heights(begin: u64, end: u64) -> Vec<u32> {
+    let (left, right) = decompose(begin, end);
+    let left_heights = (0..u64::BITS).filter(|height| left >> height & 1 == 1);
+    let right_heights = (0..u64::BITS)
+        .rev()
+        .filter(|height| right >> height & 1 == 1);
+    left_heights.chain(right_heights).collect()
+}
+
+/// Locates the peak whose subtree contains a given leaf.
+///
+/// Peaks are ordered as in `MMR::peaks`: the left part of the decomposition from its smallest
+/// subtree upwards, followed by the right part from its largest subtree downwards.
+///
+/// # Arguments
+///
+/// * `begin` - The start of the interval (inclusive)
+/// * `end` - The end of the interval (exclusive)
+/// * `index` - The index of the leaf to locate
+///
+/// # Returns
+///
+/// `None` if the leaf is outside the interval, otherwise a tuple `(peak_index, height, peak_begin)`
+/// where `peak_begin` is the index of the first leaf covered by the peak.
+///
+/// # Examples
+///
+/// ```
+/// use rust_mmr::utils::range::locate_leaf;
+///
+/// // [3, 7) has peaks covering [3], [4, 5] and [6].
+/// assert_eq!(locate_leaf(3, 7, 3), Some((0, 0, 3)));
+/// assert_eq!(locate_leaf(3, 7, 5), Some((1, 1, 4)));
+/// assert_eq!(locate_leaf(3, 7, 6), Some((2, 0, 6)));
+/// assert_eq!(locate_leaf(3, 7, 7), None);
+/// ```
+pub fn locate_leaf(begin: u64, end: u64, index: u64) -> Option<(usize, u32, u64)> {
+    if index < begin || index >= end {
+        return None;
+    }
+    let (left, right) = decompose(begin, end);
+    let mut peak_begin = begin;
+    let mut peak_index = 0;
+
+    // Left peaks grow in size moving away from `begin`.
+    let mut bits = left;
+    while bits != 0 {
+        let height = bits.trailing_zeros();
+        if index - peak_begin < 1 << height {
+            return Some((peak_index, height, peak_begin));
+        }
+        peak_begin += 1 << height;
+        peak_index += 1;
+        bits &= bits - 1;
+    }
+
+    // Right peaks shrink in size moving towards `end`.
+    let mut bits = right;
+    while bits != 0 {
+        let height = bits.ilog2();
+        if index - peak_begin < 1 << height {
+            return Some((peak_index, height, peak_begin));
+        }
+        peak_begin += 1 << height;
+        peak_index += 1;
+        bits ^= 1 << height;
+    }
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn test_decompose_inverted_range() {
+        assert_eq!(decompose(5, 4), (0, 0));
+        assert_eq!(decompose(u64::MAX, 3), (0, 0));
+        assert_eq!(get_expected_num_peaks(5, 4), 0);
+    }
+
+    #[test]
+    fn test_max_peaks_bound() {
+        assert_eq!(get_expected_num_peaks(1, u64::MAX) as usize, MAX_PEAKS);
+        assert_eq!(get_expected_num_peaks(0, u64::MAX), 64);
+
+        // Ranges with random bit lengths on both ends, so that every divergence level and
+        // every density of set bits is sampled across the whole u64 space.
+        let mut rng = StdRng::seed_from_u64(995);
+        let mut max = 0;
+        for _ in 0..200_000 {
+            let a = rng.gen::<u64>() >> rng.gen_range(0..64);
+            let b = rng.gen::<u64>() >> rng.gen_range(0..64);
+            let (begin, end) = (a.min(b), a.max(b));
+            max = max.max(get_expected_num_peaks(begin, end));
+            // Dense bit patterns around the extremes.
+            let (begin, end) = (begin | 1, end | (u64::MAX >> rng.gen_range(0..64)));
+            if begin <= end {
+                max = max.max(get_expected_num_peaks(begin, end));
+            }
+        }
+        assert!(max as usize <= MAX_PEAKS);
+    }
 
     #[test]
     fn test_decompose_zero_start() {
@@ -217,4 +412,70 @@ mod tests {
             2
         );
     }
+
+    #[test]
+    fn test_locate_leaf() {
+        // Subtree sizes [1, 4, 8], [1].
+        assert_eq!(locate_leaf(3, 17, 2), None);
+        assert_eq!(locate_leaf(3, 17, 3), Some((0, 0, 3)));
+        assert_eq!(locate_leaf(3, 17, 4), Some((1, 2, 4)));
+        assert_eq!(locate_leaf(3, 17, 7), Some((1, 2, 4)));
+        assert_eq!(locate_leaf(3, 17, 8), Some((2, 3, 8)));
+        assert_eq!(locate_leaf(3, 17, 15), Some((2, 3, 8)));
+        assert_eq!(locate_leaf(3, 17, 16), Some((3, 0, 16)));
+        assert_eq!(locate_leaf(3, 17, 17), None);
+
+        // Subtree sizes [], [8, 2].
+        assert_eq!(locate_leaf(0, 10, 0), Some((0, 3, 0)));
+        assert_eq!(locate_leaf(0, 10, 9), Some((1, 1, 8)));
+
+        assert_eq!(
+            locate_leaf(0, u64::MAX, u64::MAX - 1),
+            Some((63, 0, u64::MAX - 1))
+        );
+        assert_eq!(locate_leaf(5, 5, 5), None);
+    }
+
+    #[test]
+    fn test_decomposition_increment_matches_decompose() {
+        for begin in 0..130 {
+            let mut decomposition = Decomposition::new(begin, begin);
+            for end in begin + 1..begin + 300 {
+                decomposition.increment();
+                assert_eq!(
+                    (decomposition.left(), decomposition.right()),
+                    decompose(begin, end)
+                );
+                assert_eq!(decomposition.end(), end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decomposition_increment_large_values() {
+        for begin in [1 << 40, (1 << 40) - 3, u64::MAX - 1000, 0xdead_beef] {
+            let mut decomposition = Decomposition::new(begin, begin + 17);
+            for end in begin + 18..begin + 1000 {
+                decomposition.increment();
+                assert_eq!(
+                    (decomposition.left(), decomposition.right()),
+                    decompose(begin, end)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_peak_heights_match_locate_leaf() {
+        for begin in 0..40 {
+            for end in begin..80 {
+                let heights = get_peak_heights(begin, end);
+                assert_eq!(heights.len() as u64, get_expected_num_peaks(begin, end));
+                for index in begin..end {
+                    let (peak_index, height, _) = locate_leaf(begin, end, index).unwrap();
+                    assert_eq!(heights[peak_index], height);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,377 @@
+use crate::error::MMRError;
+use crate::mmr::MMR;
+use crate::peaks::Peaks;
+use crate::scheme::SchemeDescriptor;
+use alloy_primitives::B256;
+use std::collections::BTreeMap;
+
+/// A contiguous range of leaf indices reserved by a producer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexRange {
+    /// The first reserved index (inclusive)
+    pub start: u64,
+    /// The end of the reservation (exclusive)
+    pub end: u64,
+}
+
+impl IndexRange {
+    /// Returns the number of reserved indices
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Returns true if no indices are reserved
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Hashes a shard of leaves filling exactly this range, ready to be submitted to a writer
+    /// whose MMR is unkeyed and has `scheme`. See `shard_like` for keyed writers.
+    pub fn shard(&self, leaves: &[B256], scheme: SchemeDescriptor) -> Result<MMR, MMRError> {
+        self.shard_like(leaves, &MMR::new().with_scheme(scheme))
+    }
+
+    /// Hashes a shard of leaves filling exactly this range with the scheme and key of `mmr`,
+    /// e.g. a clone of the writer's MMR taken once by each producer
+    pub fn shard_like(&self, leaves: &[B256], mmr: &MMR) -> Result<MMR, MMRError> {
+        if leaves.len() as u64 != self.len() {
+            return Err(MMRError::InvalidLeaves);
+        }
+        let mut shard = mmr.with_peaks(Peaks::empty(self.start));
+        for leaf in leaves {
+            shard.try_append(*leaf)?;
+        }
+        Ok(shard)
+    }
+}
+
+/// The plan of a distributed build: the shard ranges in leaf order and the root of the MMR
+/// they merge into, so shards proven separately can be checked in bulk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardManifest {
+    pub shards: Vec<IndexRange>,
+    pub root: B256,
+}
+
+impl ShardManifest {
+    /// Checks that `shards` cover the manifest's ranges, which must be contiguous, and merge
+    /// into its root, returning the merged MMR.
+    ///
+    /// Returns `MMRError::RangeMismatch` if the ranges differ or leave a gap,
+    /// `MMRError::RootMismatch` if the merged root differs, and the error of `merge` if the
+    /// shards can't be merged.
+    pub fn verify(&self, shards: &[MMR]) -> Result<MMR, MMRError> {
+        if shards.len() != self.shards.len() {
+            return Err(MMRError::RangeMismatch);
+        }
+        let mut merged: Option<MMR> = None;
+        for (range, shard) in self.shards.iter().zip(shards) {
+            if shard.start() != range.start || shard.end() != range.end {
+                return Err(MMRError::RangeMismatch);
+            }
+            merged = Some(match merged {
+                None => shard.clone(),
+                Some(mmr) if mmr.end() == shard.start() => mmr.merge(shard)?,
+                Some(_) => return Err(MMRError::RangeMismatch),
+            });
+        }
+        let merged = merged.ok_or(MMRError::RangeMismatch)?;
+        if merged.get_root() != self.root {
+            return Err(MMRError::RootMismatch);
+        }
+        Ok(merged)
+    }
+}
+
+/// Coordinates producers that append to one MMR in parallel.
+///
+/// Producers reserve contiguous index ranges up front, hash their shards independently, and
+/// submit them in any order. The writer buffers out-of-order shards and merges them strictly
+/// in index order. Share it between threads behind a `Mutex`; only `reserve` and `submit`
+/// need the lock, the hashing happens outside of it.
+#[derive(Debug)]
+pub struct MmrWriter {
+    mmr: MMR,
+    next_index: u64,
+    reserved: BTreeMap<u64, u64>,
+    pending: BTreeMap<u64, MMR>,
+}
+
+impl MmrWriter {
+    /// Creates a writer appending to `mmr`
+    pub fn new(mmr: MMR) -> Self {
+        Self {
+            next_index: mmr.end(),
+            mmr,
+            reserved: BTreeMap::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Reserves the next `count` leaf indices, returning `MMRError::EmptyRange` if `count` is
+    /// zero
+    pub fn reserve(&mut self, count: u64) -> Result<IndexRange, MMRError> {
+        if count == 0 {
+            return Err(MMRError::EmptyRange);
+        }
+        let end = self
+            .next_index
+            .checked_add(count)
+            .ok_or(MMRError::IndexOverflow)?;
+        let range = IndexRange {
+            start: self.next_index,
+            end,
+        };
+        self.reserved.insert(range.start, range.end);
+        self.next_index = end;
+        Ok(range)
+    }
+
+    /// Submits a shard covering exactly one outstanding reservation.
+    ///
+    /// The shard is merged immediately if it is next in line, along with any buffered shards
+    /// that follow it; otherwise it is buffered. A reservation stays outstanding until its
+    /// shard is merged: if a merge fails, its error is returned and the shard stays buffered,
+    /// so a corrected shard can be submitted for the same reservation.
+    pub fn submit(&mut self, shard: MMR) -> Result<(), MMRError> {
+        if self.reserved.get(&shard.start()) != Some(&shard.end()) {
+            return Err(MMRError::UnreservedRange);
+        }
+        self.pending.insert(shard.start(), shard);
+
+        while let Some(next) = self.pending.get(&self.mmr.end()) {
+            let start = next.start();
+            self.mmr = self.mmr.merge(next)?;
+            self.pending.remove(&start);
+            self.reserved.remove(&start);
+        }
+        Ok(())
+    }
+
+    /// Returns the MMR with every shard merged so far
+    pub fn mmr(&self) -> &MMR {
+        &self.mmr
+    }
+
+    /// Returns the number of submitted shards waiting for an earlier shard
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns the number of reservations whose shard is not merged yet
+    pub fn outstanding(&self) -> usize {
+        self.reserved.len()
+    }
+
+    /// Returns the merged MMR, or `None` if shards are still outstanding or pending
+    pub fn finish(self) -> Option<MMR> {
+        (self.reserved.is_empty() && self.pending.is_empty()).then_some(self.mmr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheme::LeafPolicy;
+    use crate::utils::hash::get_random_hash;
+
+    #[test]
+    fn test_out_of_order_submission() {
+        let leaves: Vec<B256> = (0..30).map(|_| get_random_hash()).collect();
+        let mut writer = MmrWriter::new(MMR::new());
+        let ranges: Vec<IndexRange> = [7, 3, 12, 8]
+            .iter()
+            .map(|&count| writer.reserve(count).unwrap())
+            .collect();
+        assert_eq!(ranges[1], IndexRange { start: 7, end: 10 });
+
+        let shards: Vec<MMR> = ranges
+            .iter()
+            .map(|range| {
+                range
+                    .shard(
+                        &leaves[range.start as usize..range.end as usize],
+                        SchemeDescriptor::DEFAULT,
+                    )
+                    .unwrap()
+            })
+            .collect();
+
+        writer.submit(shards[2].clone()).unwrap();
+        writer.submit(shards[1].clone()).unwrap();
+        assert_eq!(writer.mmr().end(), 0);
+        assert_eq!(writer.pending(), 2);
+
+        writer.submit(shards[0].clone()).unwrap();
+        assert_eq!(writer.mmr().end(), 22);
+        assert_eq!(writer.pending(), 0);
+
+        writer.submit(shards[3].clone()).unwrap();
+        assert_eq!(writer.finish().unwrap(), MMR::from_leaves(&leaves));
+    }
+
+    #[test]
+    fn test_keyed_shards() {
+        let leaves: Vec<B256> = (0..12).map(|_| get_random_hash()).collect();
+        let keyed = MMR::new().with_key(get_random_hash());
+        let mut writer = MmrWriter::new(keyed.clone());
+        let ranges = [writer.reserve(5).unwrap(), writer.reserve(7).unwrap()];
+        let template = writer.mmr().clone();
+        for range in ranges.iter().rev() {
+            let shard = range
+                .shard_like(&leaves[range.start as usize..range.end as usize], &template)
+                .unwrap();
+            writer.submit(shard).unwrap();
+        }
+        let mut expected = keyed;
+        expected.append_batch(&leaves);
+        assert_eq!(writer.finish().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_unreserved_submission() {
+        let mut writer = MmrWriter::new(MMR::new());
+        let range = writer.reserve(4).unwrap();
+        let shard = range
+            .shard(&[get_random_hash(); 4], SchemeDescriptor::DEFAULT)
+            .unwrap();
+
+        // A shard that doesn't match a reservation is rejected.
+        let other = IndexRange { start: 0, end: 3 }
+            .shard(&[get_random_hash(); 3], SchemeDescriptor::DEFAULT)
+            .unwrap();
+        assert!(matches!(
+            writer.submit(other),
+            Err(MMRError::UnreservedRange)
+        ));
+
+        // Each reservation can only be submitted once.
+        writer.submit(shard.clone()).unwrap();
+        assert!(matches!(
+            writer.submit(shard),
+            Err(MMRError::UnreservedRange)
+        ));
+    }
+
+    #[test]
+    fn test_failed_merge_keeps_reservation() {
+        let scheme = SchemeDescriptor {
+            leaf_policy: LeafPolicy::LengthPrefixed,
+            ..SchemeDescriptor::DEFAULT
+        };
+        let leaves: Vec<B256> = (0..5).map(|_| get_random_hash()).collect();
+        let mut writer = MmrWriter::new(MMR::new().with_scheme(scheme));
+        let range = writer.reserve(5).unwrap();
+
+        // A shard built under another scheme fails to merge but stays reserved.
+        let wrong = range.shard(&leaves, SchemeDescriptor::DEFAULT).unwrap();
+        assert!(matches!(
+            writer.submit(wrong),
+            Err(MMRError::SchemeMismatch)
+        ));
+        assert_eq!(writer.outstanding(), 1);
+        assert_eq!(writer.pending(), 1);
+
+        writer
+            .submit(range.shard(&leaves, scheme).unwrap())
+            .unwrap();
+        assert_eq!(writer.outstanding(), 0);
+        assert_eq!(
+            writer.finish().unwrap(),
+            MMR::from_leaves(&leaves).with_scheme(scheme)
+        );
+    }
+
+    #[test]
+    fn test_reserve_zero() {
+        let mut writer = MmrWriter::new(MMR::new());
+        assert!(matches!(writer.reserve(0), Err(MMRError::EmptyRange)));
+        assert_eq!(writer.outstanding(), 0);
+        assert_eq!(writer.reserve(2).unwrap(), IndexRange { start: 0, end: 2 });
+    }
+
+    #[test]
+    fn test_shard_length_must_match() {
+        let range = IndexRange { start: 2, end: 5 };
+        assert!(matches!(
+            range.shard(&[get_random_hash(); 2], SchemeDescriptor::DEFAULT),
+            Err(MMRError::InvalidLeaves)
+        ));
+    }
+
+    #[test]
+    fn test_shard_manifest() {
+        let leaves: Vec<B256> = (0..30).map(|_| get_random_hash()).collect();
+        let ranges = vec![
+            IndexRange { start: 0, end: 7 },
+            IndexRange { start: 7, end: 10 },
+            IndexRange { start: 10, end: 30 },
+        ];
+        let shards: Vec<MMR> = ranges
+            .iter()
+            .map(|range| {
+                range
+                    .shard(
+                        &leaves[range.start as usize..range.end as usize],
+                        SchemeDescriptor::DEFAULT,
+                    )
+                    .unwrap()
+            })
+            .collect();
+        let manifest = ShardManifest {
+            shards: ranges.clone(),
+            root: MMR::from_leaves(&leaves).get_root(),
+        };
+        assert_eq!(manifest.verify(&shards).unwrap(), MMR::from_leaves(&leaves));
+
+        // Missing, reordered and mismatched shards are rejected.
+        assert!(matches!(
+            manifest.verify(&shards[..2]),
+            Err(MMRError::RangeMismatch)
+        ));
+        let reordered = [shards[1].clone(), shards[0].clone(), shards[2].clone()];
+        assert!(matches!(
+            manifest.verify(&reordered),
+            Err(MMRError::RangeMismatch)
+        ));
+        let gapped = ShardManifest {
+            shards: vec![ranges[0], ranges[2]],
+            root: manifest.root,
+        };
+        assert!(matches!(
+            gapped.verify(&[shards[0].clone(), shards[2].clone()]),
+            Err(MMRError::RangeMismatch)
+        ));
+        let empty = ShardManifest {
+            shards: vec![],
+            root: manifest.root,
+        };
+        assert!(matches!(empty.verify(&[]), Err(MMRError::RangeMismatch)));
+
+        // A shard built from other leaves doesn't merge into the root.
+        let mut tampered = shards.clone();
+        tampered[1] = ranges[1]
+            .shard(&[get_random_hash(); 3], SchemeDescriptor::DEFAULT)
+            .unwrap();
+        assert!(matches!(
+            manifest.verify(&tampered),
+            Err(MMRError::RootMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_finish_with_outstanding() {
+        let mut writer = MmrWriter::new(MMR::new());
+        writer.reserve(1).unwrap();
+        assert_eq!(writer.outstanding(), 1);
+        assert!(writer.finish().is_none());
+    }
+
+    #[test]
+    fn test_reserve_overflow() {
+        let mmr = MMR::from_params(u64::MAX - 1, u64::MAX - 1, vec![]).unwrap();
+        let mut writer = MmrWriter::new(mmr);
+        assert!(writer.reserve(1).is_ok());
+        assert!(matches!(writer.reserve(1), Err(MMRError::IndexOverflow)));
+    }
+}
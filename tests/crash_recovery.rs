@@ -0,0 +1,78 @@
+#![cfg(feature = "testing")]
+
+use alloy_primitives::B256;
+use rust_mmr::store::commit::{append_committed, recover, MemoryLog};
+use rust_mmr::store::testing::{CrashPoint, FaultyStore};
+use rust_mmr::store::MemoryStore;
+use rust_mmr::utils::hash::hash_leaf_bytes;
+use rust_mmr::MMR;
+
+fn leaves(count: u64) -> Vec<B256> {
+    (0..count)
+        .map(|i| hash_leaf_bytes(&i.to_be_bytes()))
+        .collect()
+}
+
+/// Interrupts the second of two appends at every crash point, then checks that recovery
+/// yields either the state before or the state after the interrupted append.
+#[tokio::test]
+async fn test_recovery_after_crash() {
+    let leaves = leaves(40);
+    for start in [0, 5] {
+        for first in [1, 4, 7] {
+            for second in [1, 3, 8, 13] {
+                let split = start + first;
+                let end = split + second;
+                let batch = &leaves[split as usize..end as usize];
+                let mut crashes = vec![CrashPoint::BeforeCommit, CrashPoint::AfterCommit];
+                crashes.extend((0..2 * second as usize).map(CrashPoint::DuringNodes));
+
+                for crash in crashes {
+                    let store = FaultyStore::new(MemoryStore::new(), MemoryLog::new());
+                    let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+                    let initial = &leaves[start as usize..split as usize];
+                    append_committed(&store, &store, &mut mmr, initial)
+                        .await
+                        .unwrap();
+                    let before = mmr.clone();
+
+                    store.arm(crash);
+                    assert!(append_committed(&store, &store, &mut mmr, batch)
+                        .await
+                        .is_err());
+                    // The in-memory state is never ahead of a failed append.
+                    assert_eq!(mmr, before);
+
+                    // Restart from what reached the store and the log.
+                    let template = MMR::new();
+                    let recovered = recover(store.store(), store.log(), &template)
+                        .await
+                        .unwrap()
+                        .unwrap();
+                    let mut after = before.clone();
+                    after.append_batch(batch);
+                    let expected = if crash == CrashPoint::AfterCommit {
+                        &after
+                    } else {
+                        &before
+                    };
+                    assert_eq!(&recovered, expected, "crash {:?}", crash);
+
+                    // The recovered state keeps appending consistently.
+                    let mut resumed = recovered;
+                    let rest = &leaves[resumed.end() as usize..];
+                    append_committed(&store, &store, &mut resumed, rest)
+                        .await
+                        .unwrap();
+                    let mut full = MMR::from_params(start, start, vec![]).unwrap();
+                    full.append_batch(&leaves[start as usize..]);
+                    assert_eq!(resumed, full);
+                    assert_eq!(
+                        recover(&store, &store, &template).await.unwrap(),
+                        Some(full)
+                    );
+                }
+            }
+        }
+    }
+}
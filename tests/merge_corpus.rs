@@ -0,0 +1,42 @@
+use alloy_primitives::{B256, U256};
+use rust_mmr::MMR;
+
+const CORPUS: &str = include_str!("data/merge_corpus.csv");
+
+fn leaf(seed: u64, index: u64) -> B256 {
+    (U256::from(seed) + U256::from(index)).into()
+}
+
+#[test]
+fn test_merge_corpus() {
+    let cases: Vec<(u64, u64, u64)> = CORPUS
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<u64> = line
+                .split(',')
+                .map(|field| field.parse().unwrap())
+                .collect();
+            (fields[0], fields[1], fields[2])
+        })
+        .collect();
+    assert!(cases.len() >= 400);
+
+    for (split, end, seed) in cases {
+        let leaves: Vec<B256> = (0..end).map(|index| leaf(seed, index)).collect();
+        let left = MMR::from_leaves(&leaves[..split as usize]);
+        let mut right = MMR::from_params(split, split, vec![]).unwrap();
+        right.append_batch(&leaves[split as usize..]);
+
+        let merged = left.merge(&right).unwrap();
+        assert_eq!(
+            merged,
+            MMR::from_leaves(&leaves),
+            "merge of [0, {}) and [{}, {}) with seed {}",
+            split,
+            split,
+            end,
+            seed
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Verification runs on untrusted proofs, so a panic there is a liveness bug for any
+//! service verifying them. These properties feed arbitrary inputs to every verification
+//! entry point, with overflow checks on, and only require that they return.
+
+use alloy_primitives::B256;
+use proptest::prelude::*;
+use rust_mmr::compact::CompactRange;
+use rust_mmr::consistency::ConsistencyProof;
+use rust_mmr::mmr_proof::{MmrProof, ProofContext};
+use rust_mmr::peaks::Peaks;
+use rust_mmr::proof::{InclusionProof, InlinedProof};
+use rust_mmr::range_proof::RangeProof;
+use rust_mmr::sequence::verify_append_sequence;
+use rust_mmr::utils::{hash::hash_to_parent, range::get_expected_num_peaks};
+use rust_mmr::MMR;
+
+/// Indices biased towards the edges of the u64 space, where overflows hide.
+fn index() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        0..64u64,
+        any::<u64>(),
+        (0..64u64).prop_map(|offset| u64::MAX - offset),
+        (0..64u32, 0..3u64).prop_map(|(shift, offset)| (1u64 << shift).wrapping_sub(offset)),
+    ]
+}
+
+fn hashes(max: usize) -> impl Strategy<Value = Vec<B256>> {
+    prop::collection::vec(any::<[u8; 32]>().prop_map(B256::from), 0..max)
+}
+
+/// An MMR over an arbitrary range, with arbitrary peaks of the right count.
+fn mmr() -> impl Strategy<Value = MMR> {
+    (index(), index(), any::<[u8; 32]>()).prop_map(|(a, b, seed)| {
+        let (start, end) = (a.min(b), a.max(b));
+        mmr_over(start, end, seed)
+    })
+}
+
+/// An MMR over `[start, end)` with peaks derived from `seed`.
+fn mmr_over(start: u64, end: u64, seed: [u8; 32]) -> MMR {
+    let mut peak = B256::from(seed);
+    let peaks = (0..get_expected_num_peaks(start, end))
+        .map(|_| {
+            peak = hash_to_parent(&peak, &peak);
+            peak
+        })
+        .collect();
+    MMR::from_params(start, end, peaks).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn inclusion_proofs(mmr in mmr(), leaf_index in index(), path in hashes(80), leaf: [u8; 32], offset in index()) {
+        let leaf = B256::from(leaf);
+        let proof = InclusionProof::new(leaf_index, path);
+        proof.verify(&mmr, leaf);
+        let _ = proof.verify_with_budget(&mmr, leaf, 16);
+        proof.verify_peaks(mmr.as_peaks(), leaf, mmr.get_root());
+        proof.compute_peak(leaf, offset);
+    }
+
+    #[test]
+    fn unchecked_ranges(start in index(), end in index(), peaks in hashes(130), leaf_index in index(), path in hashes(70)) {
+        let proof = InclusionProof::new(leaf_index, path);
+        proof.verify_root(start, end, &peaks, B256::ZERO, B256::ZERO);
+        if let Ok(peaks) = Peaks::new(start, end, &peaks[..]) {
+            peaks.root();
+            peaks.to_fixed();
+        }
+        let _ = MMR::from_params(start, end, peaks);
+    }
+
+    #[test]
+    fn inlined_proofs(mmr in mmr(), leaf_index in index(), leaves in hashes(40), path in hashes(70)) {
+        InlinedProof::new(leaf_index, leaves, path).verify(&mmr, B256::ZERO);
+    }
+
+    #[test]
+    fn range_proofs(mmr in mmr(), range_start in index(), left in hashes(70), leaves in hashes(40), right in hashes(70)) {
+        let proof = RangeProof::new(range_start, left, right);
+        proof.verify(&mmr, &leaves);
+        proof.verify_root(mmr.start(), mmr.end(), &leaves, mmr.get_root());
+    }
+
+    #[test]
+    fn consistency_proofs(old in mmr(), old_end in index(), new_end in index(), peaks in hashes(130)) {
+        let proof = ConsistencyProof::new(old_end, new_end, peaks);
+        proof.verify(&old, B256::ZERO);
+        let _ = proof.verify_with_budget(&old, B256::ZERO, 64);
+    }
+
+    #[test]
+    fn consistency_from_zero(old_end in index(), new_end in index(), seed: [u8; 32], peaks in hashes(130)) {
+        // Old MMRs whose end matches the proof, so that verification reaches the merge.
+        let old = mmr_over(0, old_end, seed);
+        let count = get_expected_num_peaks(old_end, new_end) as usize;
+        let peaks = if count <= peaks.len() { peaks[..count].to_vec() } else { peaks };
+        ConsistencyProof::new(old_end, new_end, peaks).verify(&old, B256::ZERO);
+    }
+
+    #[test]
+    fn append_sequences(begin in index(), end in index(), peaks in hashes(130), leaves in hashes(40)) {
+        let witness = CompactRange { begin, end, hashes: peaks };
+        let _ = verify_append_sequence(B256::ZERO, &leaves, B256::ZERO, &witness);
+    }
+
+    #[test]
+    fn encoded_proofs(bytes in prop::collection::vec(any::<u8>(), 0..600), old_root: Option<[u8; 32]>) {
+        let _ = CompactRange::from_bytes(&bytes);
+        if let Ok(proof) = MmrProof::from_bytes(&bytes) {
+            let context = ProofContext { root: B256::ZERO, old_root: old_root.map(B256::from) };
+            proof.verify(&context);
+            let _ = proof.verify_with_budget(&context, 256);
+        }
+    }
+}
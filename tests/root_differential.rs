@@ -0,0 +1,101 @@
+use alloy_primitives::B256;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_mmr::{utils::hash::hash_to_parent, MMR};
+
+/// Root of the RFC 6962 tree over `leaves`: the largest power-of-two prefix is a perfect
+/// subtree on the left, and the rest recurses on the right.
+fn right_weighted_root(leaves: &[B256]) -> B256 {
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let split = 1 << (leaves.len() - 1).ilog2();
+    hash_to_parent(
+        &right_weighted_root(&leaves[..split]),
+        &right_weighted_root(&leaves[split..]),
+    )
+}
+
+/// Mirror of `right_weighted_root`: the largest power-of-two suffix is a perfect subtree on
+/// the right, and the rest recurses on the left.
+fn left_weighted_root(leaves: &[B256]) -> B256 {
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let split = leaves.len() - (1 << (leaves.len() - 1).ilog2());
+    hash_to_parent(
+        &left_weighted_root(&leaves[..split]),
+        &left_weighted_root(&leaves[split..]),
+    )
+}
+
+/// Builds the tree over `[start, start + leaves.len())` from first principles.
+///
+/// The range splits at `mid`, the multiple of the largest power of two it contains. Leaves
+/// before `mid` lie in subtrees growing towards it, so they form a left-weighted tree, while
+/// leaves from `mid` on lie in subtrees shrinking away from it, and form a right-weighted
+/// tree. The root joins both sides, or is the only non-empty one.
+fn first_principles_root(start: u64, leaves: &[B256]) -> B256 {
+    if leaves.is_empty() {
+        return B256::ZERO;
+    }
+    let end = start + leaves.len() as u64;
+    let mid = (0..u64::BITS)
+        .rev()
+        .filter_map(|height| start.div_ceil(1 << height).checked_mul(1 << height))
+        .find(|multiple| *multiple < end)
+        .unwrap();
+    let (left, right) = leaves.split_at((mid - start) as usize);
+    match (left.is_empty(), right.is_empty()) {
+        (true, _) => right_weighted_root(right),
+        (_, true) => left_weighted_root(left),
+        _ => hash_to_parent(&left_weighted_root(left), &right_weighted_root(right)),
+    }
+}
+
+#[test]
+fn test_weighted_roots() {
+    let leaves: Vec<B256> = (1..=5_u8).map(B256::repeat_byte).collect();
+    let h = |a: &B256, b: &B256| hash_to_parent(a, b);
+    // 5 = 4 + 1 on the right, 1 + 4 on the left.
+    assert_eq!(
+        right_weighted_root(&leaves),
+        h(
+            &h(&h(&leaves[0], &leaves[1]), &h(&leaves[2], &leaves[3])),
+            &leaves[4]
+        )
+    );
+    assert_eq!(
+        left_weighted_root(&leaves),
+        h(
+            &leaves[0],
+            &h(&h(&leaves[1], &leaves[2]), &h(&leaves[3], &leaves[4]))
+        )
+    );
+}
+
+#[test]
+fn test_get_root_matches_first_principles() {
+    let mut rng = StdRng::seed_from_u64(981);
+    for case in 0..3000 {
+        // Starts of every magnitude, including zero and just below powers of two.
+        let start = match case % 4 {
+            0 => 0,
+            1 => rng.gen_range(0..1 << 10),
+            2 => (1_u64 << rng.gen_range(1..63)).saturating_sub(rng.gen_range(1..64)),
+            _ => rng.gen_range(0..1 << 62),
+        };
+        let leaves: Vec<B256> = (0..rng.gen_range(0..100))
+            .map(|_| B256::from(rng.gen::<[u8; 32]>()))
+            .collect();
+
+        let mut mmr = MMR::from_params(start, start, vec![]).unwrap();
+        mmr.append_batch(&leaves);
+        assert_eq!(
+            mmr.get_root(),
+            first_principles_root(start, &leaves),
+            "[{}, {})",
+            start,
+            mmr.end()
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Splitting an MMR at any leaf and merging the halves back must be the identity, down to
+//! the encoded bytes. Proptest shrinks failures to the smallest leaf count and split point,
+//! which is usually enough to pin down the offending decomposition.
+
+use alloy_primitives::{B256, U256};
+use proptest::prelude::*;
+use rust_mmr::full::FullMMR;
+use rust_mmr::MMR;
+
+fn leaf(seed: u64, index: u64) -> B256 {
+    (U256::from(seed) + U256::from(index)).into()
+}
+
+/// Splits the MMR stored by `full` at the leaf `at`, with the witness read from its nodes.
+fn split(full: &FullMMR, at: u64) -> (MMR, MMR) {
+    full.mmr()
+        .split(at, &full.split_witness(at).unwrap())
+        .unwrap()
+}
+
+/// A leaf count and a split point within it.
+fn split_point() -> impl Strategy<Value = (u64, u64)> {
+    (0..600u64).prop_flat_map(|num_leaves| (Just(num_leaves), 0..=num_leaves))
+}
+
+proptest! {
+    #[test]
+    fn split_then_merge_is_identity((num_leaves, at) in split_point(), seed: u64) {
+        let mut full = FullMMR::new();
+        for index in 0..num_leaves {
+            full.append(leaf(seed, index));
+        }
+        let (left, right) = split(&full, at);
+        prop_assert_eq!((left.start(), left.end()), (0, at));
+        prop_assert_eq!((right.start(), right.end()), (at, num_leaves));
+
+        let merged = left.merge(&right).unwrap();
+        prop_assert_eq!(
+            merged.to_bytes(),
+            full.mmr().to_bytes(),
+            "split of [0, {}) at {} with seed {}",
+            num_leaves,
+            at,
+            seed
+        );
+    }
+}